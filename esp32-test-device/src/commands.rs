@@ -1,13 +1,31 @@
 //! Command processing for setup and simulation commands
 
+use esp_idf_svc::hal::{
+    delay::{FreeRtos, TickType},
+    uart::UartDriver,
+};
 use esp_idf_svc::http::server::EspHttpServer;
 use log::*;
+use serde::Serialize;
 
-use crate::http::start_http_server;
+use crate::http::{dashboard_urls, start_http_server};
+use crate::i2c::I2cBridge;
+use crate::nvs_config::{load_sim_data_from_nvs, save_scpi_errors_to_nvs, save_sim_data_to_nvs};
+use crate::ota;
 use crate::protocols::{self, EscPosEmulator, ModbusServer};
-use crate::serial::send_line;
-use crate::types::{ProtocolMode, SharedState};
-use crate::wifi::{clear_wifi_config, save_wifi_config, try_connect_wifi, WifiManager};
+use crate::pwm::{rpm_for_freq, PwmOutput, PWM_FREQ_RANGE};
+use crate::serial::{send_line, AUX_UART_BAUD_RANGE};
+use crate::types::{DeviceState, ProtocolMode, SharedState};
+use crate::wifi::{
+    clear_wifi_config, save_wifi_config, scan_networks, try_connect_wifi, WifiManager,
+};
+
+/// Peripherals driven by serial commands
+pub struct DeviceIo {
+    pub aux_uart: Option<UartDriver<'static>>,
+    pub pwm: Option<PwmOutput>,
+    pub i2c: I2cBridge,
+}
 
 /// Process a line of input based on current protocol mode
 pub fn process_line(
@@ -16,37 +34,76 @@ pub fn process_line(
     state: &SharedState,
     wifi_mgr: &mut WifiManager,
     http_server: &mut Option<EspHttpServer<'static>>,
+    binary_state: &mut BinaryProtocolState,
+    io: &mut DeviceIo,
 ) -> String {
     let line_upper = line.to_uppercase();
 
     // Always process setup commands regardless of mode
     if line_upper.starts_with("WIFI_")
         || line_upper == "HELP"
+        || line_upper == "RESET"
         || line_upper.starts_with("MODE=")
         || line_upper.starts_with("SET_")
+        || line_upper.starts_with("MODBUS_ADDR=")
+        || line_upper.starts_with("MODBUS_ADD_SLAVE=")
+        || line_upper.starts_with("MODBUS_REMOVE_SLAVE=")
         || line_upper == "STATUS"
+        || line_upper == "GET_STATS"
+        || line_upper == "GET_PROTO"
+        || line_upper.starts_with("UART_BAUD=")
+        || line_upper.starts_with("PWM_")
+        || line_upper == "GET_PWM"
+        || line_upper.starts_with("OTA_URL=")
     {
-        return process_setup_command(line, state, wifi_mgr, http_server);
+        return process_setup_command(
+            line,
+            state,
+            wifi_mgr,
+            http_server,
+            binary_state,
+            io,
+        );
     }
 
     // Process based on current mode
     match mode {
-        ProtocolMode::Setup => process_setup_command(line, state, wifi_mgr, http_server),
+        ProtocolMode::Setup => process_setup_command(
+            line,
+            state,
+            wifi_mgr,
+            http_server,
+            binary_state,
+            io,
+        ),
         ProtocolMode::Echo => line.to_string(),
-        ProtocolMode::AtCommand => protocols::process_at_command(line),
+        ProtocolMode::AtCommand => {
+            protocols::process_at_command(line, &state.lock().unwrap().simulated_data)
+        }
         ProtocolMode::ModbusRtu => {
             // Modbus is binary - if we get text here, it's likely a debug/test message
             format!(
                 "Modbus RTU mode active (slave addr {}). Send binary Modbus frames.\r\nUse MODE=AT to return to text mode.",
-                protocols::SLAVE_ADDRESS
+                state.lock().unwrap().modbus_slave_list()
             )
         }
         ProtocolMode::NmeaGps => {
-            let sim_data = &state.lock().unwrap().simulated_data;
-            String::from_utf8_lossy(&protocols::generate_nmea_sentence(sim_data)).to_string()
+            let mut s = state.lock().unwrap();
+            let s = &mut *s;
+            String::from_utf8_lossy(&protocols::generate_nmea_sentence(&s.simulated_data, &mut s.gps))
+                .to_string()
         }
         ProtocolMode::Scpi => {
-            protocols::process_scpi_command(line, &state.lock().unwrap().simulated_data)
+            let mut s = state.lock().unwrap();
+            let s = &mut *s;
+            let response =
+                protocols::process_scpi_command(line, &s.simulated_data, &mut s.scpi_errors);
+            if s.scpi_errors.take_changed() {
+                if let Err(e) = save_scpi_errors_to_nvs(&mut wifi_mgr.scpi_nvs, &s.scpi_errors) {
+                    warn!("Failed to save SCPI error queue to NVS: {:?}", e);
+                }
+            }
+            response
         }
         ProtocolMode::Marlin => {
             protocols::process_marlin_gcode(line, &state.lock().unwrap().simulated_data)
@@ -58,6 +115,7 @@ pub fn process_line(
             // ESC/POS is binary - if we get text here, it's likely a debug/test message
             "ESC/POS thermal printer mode active. Send binary ESC/POS commands.\r\nUse MODE=AT to return to text mode.".to_string()
         }
+        ProtocolMode::I2cMaster => io.i2c.process_command(line),
     }
 }
 
@@ -67,6 +125,8 @@ pub fn process_setup_command(
     state: &SharedState,
     wifi_mgr: &mut WifiManager,
     http_server: &mut Option<EspHttpServer<'static>>,
+    binary_state: &mut BinaryProtocolState,
+    io: &mut DeviceIo,
 ) -> String {
     let line_upper = line.to_uppercase();
 
@@ -74,6 +134,42 @@ pub fn process_setup_command(
         return HELP_TEXT.to_string();
     }
 
+    if line_upper == "RESET" {
+        reset_device(state, wifi_mgr, binary_state);
+        return "RESET".to_string();
+    }
+
+    if line_upper == "GET_STATS" {
+        return binary_state.escpos_emulator.stats_summary();
+    }
+
+    if line_upper == "GET_PROTO" {
+        return protocol_descriptor(&state.lock().unwrap());
+    }
+
+    if line_upper.starts_with("UART_BAUD=") {
+        return handle_uart_baud(line[10..].trim(), state, io.aux_uart.as_ref());
+    }
+
+    if line_upper.starts_with("PWM_FREQ=") {
+        return handle_pwm_freq(line[9..].trim(), state, wifi_mgr, io.pwm.as_mut());
+    }
+
+    if line_upper.starts_with("PWM_DUTY=") {
+        return handle_pwm_duty(line[9..].trim(), io.pwm.as_mut());
+    }
+
+    if line_upper.starts_with("OTA_URL=") {
+        return handle_ota_url(line[8..].trim(), state);
+    }
+
+    if line_upper == "GET_PWM" {
+        return match &io.pwm {
+            Some(pwm) => format!("FREQ={} DUTY={}", pwm.freq_hz(), pwm.duty_pct()),
+            None => "ERROR - PWM output not available".to_string(),
+        };
+    }
+
     if line_upper.starts_with("WIFI_SSID=") {
         let ssid = line[10..].trim().to_string();
         wifi_mgr.pending_ssid = ssid.clone();
@@ -95,7 +191,7 @@ pub fn process_setup_command(
     }
 
     if line_upper == "WIFI_SCAN" {
-        return "Scanning... (check serial monitor for results)".to_string();
+        return handle_wifi_scan(wifi_mgr);
     }
 
     if line_upper == "WIFI_CLEAR" {
@@ -110,6 +206,11 @@ pub fn process_setup_command(
     if line_upper.starts_with("MODE=") {
         let mode_str = line[5..].trim();
         if let Some(new_mode) = ProtocolMode::from_str(mode_str) {
+            if new_mode == ProtocolMode::I2cMaster {
+                if let Err(e) = io.i2c.driver() {
+                    return format!("ERROR - {}", e);
+                }
+            }
             state.lock().unwrap().mode = new_mode;
             return format!("OK - Mode set to: {:?}", new_mode);
         } else {
@@ -117,10 +218,63 @@ pub fn process_setup_command(
         }
     }
 
+    if line_upper.starts_with("MODBUS_ADDR=") {
+        return match line[12..].trim().parse::<u8>() {
+            Ok(addr) if (1..=247).contains(&addr) => {
+                let mut s = state.lock().unwrap();
+                if s.modbus_slaves[1..].contains(&addr) {
+                    return format!("ERROR - Modbus address {} already in use", addr);
+                }
+                s.modbus_slaves[0] = addr;
+                // Rename in place so the primary slave keeps its registers
+                if let Some(primary) = binary_state.modbus_slaves.first_mut() {
+                    primary.set_address(addr);
+                }
+                format!("OK - Modbus slave address set to: {}", addr)
+            }
+            _ => "ERROR - Modbus address must be 1-247".to_string(),
+        };
+    }
+
+    if line_upper.starts_with("MODBUS_ADD_SLAVE=") {
+        return match line[17..].trim().parse::<u8>() {
+            Ok(addr) if (1..=247).contains(&addr) => {
+                let mut s = state.lock().unwrap();
+                if s.modbus_slaves.contains(&addr) {
+                    format!("ERROR - Modbus address {} already in use", addr)
+                } else if s.modbus_slaves.len() >= protocols::MAX_SLAVES {
+                    format!("ERROR - At most {} Modbus slaves", protocols::MAX_SLAVES)
+                } else {
+                    s.modbus_slaves.push(addr);
+                    format!("OK - Modbus slaves: {}", s.modbus_slave_list())
+                }
+            }
+            _ => "ERROR - Modbus address must be 1-247".to_string(),
+        };
+    }
+
+    if line_upper.starts_with("MODBUS_REMOVE_SLAVE=") {
+        let Ok(addr) = line[20..].trim().parse::<u8>() else {
+            return "ERROR - Invalid Modbus address".to_string();
+        };
+        let mut s = state.lock().unwrap();
+        return match s.modbus_slaves.iter().position(|&a| a == addr) {
+            None => format!("ERROR - No Modbus slave at address {}", addr),
+            Some(_) if s.modbus_slaves.len() == 1 => {
+                "ERROR - Cannot remove the last Modbus slave".to_string()
+            }
+            Some(i) => {
+                s.modbus_slaves.remove(i);
+                format!("OK - Modbus slaves: {}", s.modbus_slave_list())
+            }
+        };
+    }
+
     // Simulation data setters
     if line_upper.starts_with("SET_TEMP=") {
         if let Ok(val) = line[9..].trim().parse::<f32>() {
             state.lock().unwrap().simulated_data.temperature = val;
+            persist_sim_data(state, wifi_mgr);
             return format!("OK - Temperature set to: {}°C", val);
         } else {
             return "ERROR - Invalid temperature value".to_string();
@@ -130,6 +284,7 @@ pub fn process_setup_command(
     if line_upper.starts_with("SET_HUMID=") {
         if let Ok(val) = line[10..].trim().parse::<f32>() {
             state.lock().unwrap().simulated_data.humidity = val;
+            persist_sim_data(state, wifi_mgr);
             return format!("OK - Humidity set to: {}%", val);
         } else {
             return "ERROR - Invalid humidity value".to_string();
@@ -139,15 +294,62 @@ pub fn process_setup_command(
     if line_upper.starts_with("SET_RPM=") {
         if let Ok(val) = line[8..].trim().parse::<u16>() {
             state.lock().unwrap().simulated_data.rpm = val;
+            persist_sim_data(state, wifi_mgr);
             return format!("OK - RPM set to: {}", val);
         } else {
             return "ERROR - Invalid RPM value".to_string();
         }
     }
 
+    if line_upper.starts_with("SET_VOLT=") {
+        return match line[9..].trim().parse::<f32>() {
+            Ok(val) if val.is_finite() && val >= 0.0 => {
+                state.lock().unwrap().simulated_data.voltage = val;
+                persist_sim_data(state, wifi_mgr);
+                format!("OK - Voltage set to: {}V", val)
+            }
+            _ => "ERROR - Invalid voltage value".to_string(),
+        };
+    }
+
+    if line_upper.starts_with("SET_HEADING=") {
+        if let Ok(val) = line[12..].trim().parse::<f32>() {
+            let heading = val.rem_euclid(360.0);
+            state.lock().unwrap().gps.heading_deg = heading;
+            return format!("OK - Heading set to: {}°", heading);
+        } else {
+            return "ERROR - Invalid heading value".to_string();
+        }
+    }
+
+    if line_upper.starts_with("SET_BATTERY=") {
+        return match line[12..].trim().parse::<f32>() {
+            Ok(val) if (0.0..=100.0).contains(&val) => {
+                state.lock().unwrap().set_battery_level(val);
+                format!("OK - Battery set to: {}%", val)
+            }
+            _ => "ERROR - Battery must be 0-100".to_string(),
+        };
+    }
+
+    if line_upper.starts_with("SET_CHARGING=") {
+        return match line[13..].trim() {
+            "0" => {
+                state.lock().unwrap().simulated_data.charging = false;
+                "OK - Charging: off".to_string()
+            }
+            "1" => {
+                state.lock().unwrap().simulated_data.charging = true;
+                "OK - Charging: on".to_string()
+            }
+            _ => "ERROR - Charging must be 0 or 1".to_string(),
+        };
+    }
+
     if line_upper.starts_with("SET_SPEED=") {
         if let Ok(val) = line[10..].trim().parse::<f32>() {
             state.lock().unwrap().simulated_data.speed = val;
+            persist_sim_data(state, wifi_mgr);
             return format!("OK - Speed set to: {} km/h", val);
         } else {
             return "ERROR - Invalid speed value".to_string();
@@ -157,7 +359,7 @@ pub fn process_setup_command(
     if line_upper == "STATUS" {
         let s = state.lock().unwrap();
         return format!(
-            "Mode: {:?}\r\nWiFi: {}\r\nMessages: {}\r\nTemp: {}°C\r\nRPM: {}",
+            "Mode: {:?}\r\nWiFi: {}\r\nMessages: {}\r\nTemp: {}°C\r\nRPM: {}\r\nBattery: {}% ({:.2}V{})\r\nModbus slaves: {}",
             s.mode,
             if s.wifi_connected {
                 format!("{} ({})", s.wifi_ssid, s.wifi_ip)
@@ -166,7 +368,11 @@ pub fn process_setup_command(
             },
             s.message_count,
             s.simulated_data.temperature,
-            s.simulated_data.rpm
+            s.simulated_data.rpm,
+            s.simulated_data.battery_percent,
+            s.simulated_data.battery_voltage,
+            if s.simulated_data.charging { ", charging" } else { "" },
+            s.modbus_slave_list()
         );
     }
 
@@ -176,6 +382,165 @@ pub fn process_setup_command(
     )
 }
 
+/// Version of the GET_PROTO descriptor format
+const PROTO_DESCRIPTOR_VERSION: &str = "1.0";
+
+/// Machine-readable description of the current protocol, returned by GET_PROTO
+#[derive(Serialize)]
+struct ProtocolDescriptor {
+    mode: &'static str,
+    version: &'static str,
+    slave_addr: u8,
+    baud: u32,
+    /// Modbus function codes, AT, SCPI or I2C commands, empty for other modes
+    supports: &'static [&'static str],
+}
+
+/// Describe the current protocol as a single line of JSON
+pub fn protocol_descriptor(state: &DeviceState) -> String {
+    let supports = match state.mode {
+        ProtocolMode::ModbusRtu => protocols::modbus::SUPPORTED_FUNCTION_CODES,
+        ProtocolMode::AtCommand => protocols::at::SUPPORTED_COMMANDS,
+        ProtocolMode::Scpi => protocols::scpi::SUPPORTED_MEASUREMENTS,
+        ProtocolMode::I2cMaster => protocols::i2c::SUPPORTED_COMMANDS,
+        _ => &[],
+    };
+    let descriptor = ProtocolDescriptor {
+        mode: state.mode.name(),
+        version: PROTO_DESCRIPTOR_VERSION,
+        slave_addr: state
+            .modbus_slaves
+            .first()
+            .copied()
+            .unwrap_or(protocols::modbus::SLAVE_ADDRESS),
+        baud: state.serial_config.baud_rate,
+        supports,
+    };
+    serde_json::to_string(&descriptor).unwrap_or_default()
+}
+
+/// Save the simulated data so SET_* values survive a restart
+fn persist_sim_data(state: &SharedState, wifi_mgr: &mut WifiManager) {
+    let data = state.lock().unwrap().simulated_data.clone();
+    if let Err(e) = save_sim_data_to_nvs(&mut wifi_mgr.sim_nvs, &data) {
+        warn!("Failed to save simulated data to NVS: {:?}", e);
+    }
+}
+
+/// Switch the auxiliary UART to a new baud rate
+///
+/// The confirmation is also sent on the auxiliary UART at the old rate, so a
+/// host listening there knows when to switch too.
+fn handle_uart_baud(
+    value: &str,
+    state: &SharedState,
+    aux_uart: Option<&UartDriver<'static>>,
+) -> String {
+    let Some(uart) = aux_uart else {
+        return "ERROR - Auxiliary UART not available".to_string();
+    };
+    let rate = match value.parse::<u32>() {
+        Ok(rate) if AUX_UART_BAUD_RANGE.contains(&rate) => rate,
+        _ => {
+            return format!(
+                "ERROR - Baud rate must be {}-{}",
+                AUX_UART_BAUD_RANGE.start(),
+                AUX_UART_BAUD_RANGE.end()
+            )
+        }
+    };
+
+    let response = format!("OK BAUD={}", rate);
+    if let Err(e) = uart.write(format!("{}\r\n", response).as_bytes()) {
+        warn!("Auxiliary UART write failed: {:?}", e);
+    }
+    // Bytes still in the FIFO would go out at the new rate
+    if let Err(e) = uart.wait_tx_done(TickType::new_millis(100).ticks()) {
+        warn!("Auxiliary UART flush failed: {:?}", e);
+    }
+    match uart.change_baudrate(rate) {
+        Ok(actual) => {
+            info!(
+                "Auxiliary UART baud rate set to {} ({} Hz actual)",
+                rate, actual.0
+            );
+            state.lock().unwrap().serial_config.baud_rate = rate;
+            response
+        }
+        Err(e) => format!("ERROR - Failed to change baud rate: {:?}", e),
+    }
+}
+
+/// Change the PWM frequency, the simulated RPM follows at 10 Hz per RPM
+fn handle_pwm_freq(
+    value: &str,
+    state: &SharedState,
+    wifi_mgr: &mut WifiManager,
+    pwm: Option<&mut PwmOutput>,
+) -> String {
+    let Some(pwm) = pwm else {
+        return "ERROR - PWM output not available".to_string();
+    };
+    let freq_hz = match value.parse::<u32>() {
+        Ok(freq_hz) if PWM_FREQ_RANGE.contains(&freq_hz) => freq_hz,
+        _ => {
+            return format!(
+                "ERROR - PWM frequency must be {}-{} Hz",
+                PWM_FREQ_RANGE.start(),
+                PWM_FREQ_RANGE.end()
+            )
+        }
+    };
+    if let Err(e) = pwm.set_frequency(freq_hz) {
+        return format!("ERROR - Failed to set PWM frequency: {:?}", e);
+    }
+    let rpm = rpm_for_freq(freq_hz);
+    state.lock().unwrap().simulated_data.rpm = rpm;
+    persist_sim_data(state, wifi_mgr);
+    format!("OK - PWM frequency set to: {} Hz (RPM {})", freq_hz, rpm)
+}
+
+fn handle_pwm_duty(value: &str, pwm: Option<&mut PwmOutput>) -> String {
+    let Some(pwm) = pwm else {
+        return "ERROR - PWM output not available".to_string();
+    };
+    let duty_pct = match value.parse::<u8>() {
+        Ok(duty_pct) if duty_pct <= 100 => duty_pct,
+        _ => return "ERROR - PWM duty must be 0-100".to_string(),
+    };
+    match pwm.set_duty(duty_pct) {
+        Ok(()) => format!("OK - PWM duty set to: {}%", duty_pct),
+        Err(e) => format!("ERROR - Failed to set PWM duty: {:?}", e),
+    }
+}
+
+/// Download a firmware image and reboot into it
+///
+/// Progress lines are sent while downloading, only returns on failure.
+fn handle_ota_url(url: &str, state: &SharedState) -> String {
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        return "OTA_FAILED URL must start with http:// or https://".to_string();
+    }
+    if !state.lock().unwrap().wifi_connected {
+        return "OTA_FAILED WiFi not connected".to_string();
+    }
+
+    info!("Starting OTA update from {}", url);
+    send_line("OTA_STARTING");
+    match ota::download_and_install(url, |pct| send_line(&format!("OTA_PROGRESS {}", pct))) {
+        Ok(()) => {
+            info!("OTA update written, restarting");
+            send_line("OTA_COMPLETE REBOOT_IN_5s");
+            FreeRtos::delay_ms(5000);
+            esp_idf_svc::hal::reset::restart();
+        }
+        Err(e) => {
+            warn!("OTA update failed: {:?}", e);
+            format!("OTA_FAILED {}", e)
+        }
+    }
+}
+
 fn handle_wifi_connect(
     state: &SharedState,
     wifi_mgr: &mut WifiManager,
@@ -215,8 +580,9 @@ fn handle_wifi_connect(
             }
 
             format!(
-                "OK - Connected! IP: {}\r\nWeb dashboard: http://{}\r\nSwitched to AT command mode",
-                ip, ip
+                "OK - Connected! IP: {}\r\n{}\r\nSwitched to AT command mode",
+                ip,
+                dashboard_urls(&ip)
             )
         }
         Err(e) => format!("ERROR - Connection failed: {}", e),
@@ -243,6 +609,28 @@ fn handle_wifi_status(state: &SharedState, wifi_mgr: &WifiManager) -> String {
     }
 }
 
+fn handle_wifi_scan(wifi_mgr: &mut WifiManager) -> String {
+    match scan_networks(&mut wifi_mgr.wifi) {
+        Ok(networks) if networks.is_empty() => "No networks found".to_string(),
+        Ok(networks) => networks
+            .iter()
+            .enumerate()
+            .map(|(i, net)| {
+                format!(
+                    "{}. \"{}\" RSSI:{}dBm CH:{} {}",
+                    i + 1,
+                    net.ssid,
+                    net.rssi,
+                    net.channel,
+                    net.auth_method
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\r\n"),
+        Err(e) => format!("ERROR - Scan failed: {}", e),
+    }
+}
+
 /// Display help message for first boot
 pub fn show_welcome_message() {
     send_line("");
@@ -256,16 +644,49 @@ pub fn show_welcome_message() {
     send_line("");
 }
 
+/// Restore the state of a freshly booted device, as a break condition would
+///
+/// The WiFi connection and the serial log are kept, simulated data is
+/// reloaded from NVS the same way it is on boot.
+pub fn reset_device(
+    state: &SharedState,
+    wifi_mgr: &WifiManager,
+    binary_state: &mut BinaryProtocolState,
+) {
+    let mut s = state.lock().unwrap();
+    let fresh = DeviceState {
+        mode: if s.wifi_connected {
+            ProtocolMode::AtCommand
+        } else {
+            ProtocolMode::Setup
+        },
+        simulated_data: load_sim_data_from_nvs(&wifi_mgr.sim_nvs),
+        wifi_ssid: std::mem::take(&mut s.wifi_ssid),
+        wifi_connected: s.wifi_connected,
+        wifi_ip: std::mem::take(&mut s.wifi_ip),
+        // Persisted like the simulated data, only *CLS clears it
+        scpi_errors: std::mem::take(&mut s.scpi_errors),
+        log: std::mem::take(&mut s.log),
+        // The WebSocket push tracks new log entries by this counter
+        log_total: s.log_total,
+        ..DeviceState::default()
+    };
+    *s = fresh;
+    *binary_state = BinaryProtocolState::new();
+    info!("Device state reset");
+}
+
 /// Binary protocol state for stateful protocol emulators
 pub struct BinaryProtocolState {
-    pub modbus_server: ModbusServer,
+    /// One server per address in `DeviceState::modbus_slaves`
+    pub modbus_slaves: Vec<ModbusServer>,
     pub escpos_emulator: EscPosEmulator,
 }
 
 impl BinaryProtocolState {
     pub fn new() -> Self {
         Self {
-            modbus_server: ModbusServer::new(),
+            modbus_slaves: vec![ModbusServer::new()],
             escpos_emulator: EscPosEmulator::new(),
         }
     }
@@ -285,18 +706,36 @@ pub fn process_binary_data(
     state: &SharedState,
     binary_state: &mut BinaryProtocolState,
 ) -> Option<Vec<u8>> {
-    let sim_data = &state.lock().unwrap().simulated_data;
+    let device_state = state.lock().unwrap();
+    let sim_data = &device_state.simulated_data;
+
+    // Let the host identify the protocol without leaving binary mode
+    if String::from_utf8_lossy(data)
+        .trim()
+        .eq_ignore_ascii_case("GET_PROTO")
+    {
+        let mut descriptor = protocol_descriptor(&device_state).into_bytes();
+        descriptor.extend_from_slice(b"\r\n");
+        return Some(descriptor);
+    }
 
     match mode {
         ProtocolMode::ModbusRtu => {
+            protocols::sync_slaves(&mut binary_state.modbus_slaves, &device_state.modbus_slaves);
             log::debug!("Modbus RTU: Received {} bytes: {:02X?}", data.len(), data);
-            let response = binary_state.modbus_server.process_frame(data, sim_data);
+            let response = protocols::dispatch_frame(&binary_state.modbus_slaves, data, sim_data);
             if let Some(ref resp) = response {
                 log::debug!("Modbus RTU: Sending {} bytes: {:02X?}", resp.len(), resp);
             }
             response
         }
         ProtocolMode::EscPos => {
+            // Allow querying statistics without leaving binary mode
+            if String::from_utf8_lossy(data).trim().eq_ignore_ascii_case("GET_STATS") {
+                let mut stats = binary_state.escpos_emulator.stats_summary().into_bytes();
+                stats.extend_from_slice(b"\r\n");
+                return Some(stats);
+            }
             log::debug!("ESC/POS: Received {} bytes", data.len());
             let response = binary_state.escpos_emulator.process(data, sim_data);
             if let Some(ref resp) = response {
@@ -334,16 +773,37 @@ Protocol Mode:
   MODE=MARLIN          3D printer (Marlin)
   MODE=ELM327          OBD-II adapter
   MODE=ESCPOS          Thermal printer (binary)
+  MODE=I2C             I2C master bridge (SDA GPIO8, SCL GPIO9)
+  MODBUS_ADDR=<n>      Set Modbus slave address (1-247)
+  MODBUS_ADD_SLAVE=<n> Add another Modbus slave (max 8)
+  MODBUS_REMOVE_SLAVE=<n> Remove a Modbus slave
 
 Simulation:
   SET_TEMP=<value>     Set temperature (°C)
   SET_HUMID=<value>    Set humidity (%)
   SET_RPM=<value>      Set RPM
   SET_SPEED=<value>    Set speed (km/h)
+  SET_VOLT=<value>     Set supply voltage at idle (V)
+  SET_HEADING=<deg>    Set GPS heading (0 = north)
+  SET_BATTERY=<pct>    Set battery charge (0-100)
+  SET_CHARGING=<0|1>   Stop or start charging
 
 Other:
   HELP                 Show this help
   STATUS               Show device status
+  RESET                Restore the state after boot
+  GET_STATS            Show ESC/POS printer statistics
+  GET_PROTO            Describe the current protocol as JSON
+  UART_BAUD=<rate>     Change the auxiliary UART baud rate
+  PWM_FREQ=<hz>        Set PWM frequency on GPIO21 (sets RPM = hz/10)
+  PWM_DUTY=<pct>       Set PWM duty cycle (0-100)
+  GET_PWM              Show PWM frequency and duty cycle
+  OTA_URL=<url>        Download firmware over WiFi and reboot into it
+
+I2C Master (MODE=I2C):
+  I2C_WRITE=<addr>:<hex> Write bytes, e.g. I2C_WRITE=0x3C:00AF
+  I2C_READ=<addr>:<n>  Read n bytes (1-128), replies in hex
+  I2C_SCAN             List addresses that acknowledge
 
 Binary Protocols (Modbus RTU, ESC/POS):
   Send raw binary data in these modes.
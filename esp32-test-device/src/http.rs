@@ -1,15 +1,42 @@
 //! HTTP server and web dashboard for device configuration
 
+use esp_idf_svc::http::server::ws::{EspHttpWsConnection, EspHttpWsDetachedSender};
 use esp_idf_svc::http::server::{Configuration as HttpConfig, EspHttpServer};
 use esp_idf_svc::io::Write as EspWrite;
+use esp_idf_svc::mdns::EspMdns;
+use esp_idf_svc::ws::FrameType;
 use log::*;
+use serde::Serialize;
+use std::sync::Mutex;
 
-use crate::types::{DeviceState, ProtocolMode, SharedState, SimulatedData};
+use crate::types::{DeviceState, LogEntry, ProtocolMode, SharedState, SimulatedData};
+
+/// Maximum number of simultaneous WebSocket clients
+pub const MAX_WS_CLIENTS: usize = 3;
+
+/// Senders for the connected `/ws` clients
+static WS_CLIENTS: Mutex<Vec<EspHttpWsDetachedSender>> = Mutex::new(Vec::new());
+
+/// mDNS hostname, the dashboard is also reachable at `http://serialtester.local`
+pub const MDNS_HOSTNAME: &str = "serialtester";
+
+/// mDNS responder, kept alive once the dashboard has started
+static MDNS: Mutex<Option<EspMdns>> = Mutex::new(None);
+
+/// Message pushed to WebSocket clients on every main loop iteration
+#[derive(Serialize)]
+struct WsUpdate<'a> {
+    state: &'a DeviceState,
+    /// Log entries added since the previous update
+    log: Vec<&'a LogEntry>,
+}
 
 /// Start the HTTP server for the web dashboard
 pub fn start_http_server(state: SharedState) -> anyhow::Result<EspHttpServer<'static>> {
     let config = HttpConfig {
         stack_size: 8192,
+        // Room for the WebSocket clients plus regular requests
+        max_open_sockets: MAX_WS_CLIENTS + 4,
         ..Default::default()
     };
 
@@ -33,6 +60,15 @@ pub fn start_http_server(state: SharedState) -> anyhow::Result<EspHttpServer<'st
         Ok::<(), anyhow::Error>(())
     })?;
 
+    // API: Get recent serial traffic
+    let state_clone = state.clone();
+    server.fn_handler("/api/log", esp_idf_svc::http::Method::Get, move |req| {
+        let state = state_clone.lock().unwrap();
+        let json = serde_json::to_string(&state.log).unwrap_or_default();
+        req.into_ok_response()?.write_all(json.as_bytes())?;
+        Ok::<(), anyhow::Error>(())
+    })?;
+
     // API: Set mode
     let state_clone = state.clone();
     server.fn_handler(
@@ -61,8 +97,13 @@ pub fn start_http_server(state: SharedState) -> anyhow::Result<EspHttpServer<'st
             let mut buf = [0u8; 512];
             let len = req.read(&mut buf)?;
             if let Ok(json_str) = std::str::from_utf8(&buf[..len]) {
-                if let Ok(data) = serde_json::from_str::<SimulatedData>(json_str) {
-                    state_clone.lock().unwrap().simulated_data = data;
+                if let Ok(mut data) = serde_json::from_str::<SimulatedData>(json_str) {
+                    let mut s = state_clone.lock().unwrap();
+                    // The dashboard has no battery controls, keep the simulated battery
+                    data.battery_voltage = s.simulated_data.battery_voltage;
+                    data.battery_percent = s.simulated_data.battery_percent;
+                    data.charging = s.simulated_data.charging;
+                    s.simulated_data = data;
                     info!("Simulated data updated");
                 }
             }
@@ -71,10 +112,105 @@ pub fn start_http_server(state: SharedState) -> anyhow::Result<EspHttpServer<'st
         },
     )?;
 
+    // WebSocket: live state and serial traffic
+    server.ws_handler("/ws", |ws: &mut EspHttpWsConnection| {
+        if ws.is_new() {
+            let mut clients = WS_CLIENTS.lock().unwrap();
+            if clients.len() >= MAX_WS_CLIENTS {
+                warn!(
+                    "Rejecting WebSocket client, limit of {} reached",
+                    MAX_WS_CLIENTS
+                );
+                return Err(anyhow::anyhow!("too many WebSocket clients"));
+            }
+            clients.push(ws.create_detached_sender()?);
+            info!("WebSocket client {} connected", ws.session());
+        } else if ws.is_closed() {
+            WS_CLIENTS
+                .lock()
+                .unwrap()
+                .retain(|client| client.session() != ws.session());
+            info!("WebSocket client {} disconnected", ws.session());
+        } else {
+            // The dashboard never sends anything, just drain the frame
+            let mut buf = [0u8; 64];
+            ws.recv(&mut buf)?;
+        }
+        Ok::<(), anyhow::Error>(())
+    })?;
+
     info!("HTTP server started on port 80");
+
+    // The dashboard still works by IP if mDNS fails
+    let mut mdns = MDNS.lock().unwrap();
+    if mdns.is_none() {
+        match start_mdns() {
+            Ok(responder) => {
+                info!("mDNS started: http://{}.local", MDNS_HOSTNAME);
+                *mdns = Some(responder);
+            }
+            Err(e) => warn!("Failed to start mDNS: {:?}", e),
+        }
+    }
+
     Ok(server)
 }
 
+/// Advertise the dashboard as `_serialtester._tcp` under the `serialtester.local` hostname
+fn start_mdns() -> anyhow::Result<EspMdns> {
+    let mut mdns = EspMdns::take()?;
+    mdns.set_hostname(MDNS_HOSTNAME)?;
+    mdns.set_instance_name("Serial Protocol Tester")?;
+    mdns.add_service(None, "_serialtester", "_tcp", 80, &[])?;
+    Ok(mdns)
+}
+
+/// Dashboard addresses to show the user, the `.local` one only if mDNS is running
+pub fn dashboard_urls(ip: &str) -> String {
+    if MDNS.lock().unwrap().is_some() {
+        format!("Web dashboard: http://{}\r\nOr: http://{}.local", ip, MDNS_HOSTNAME)
+    } else {
+        format!("Web dashboard: http://{}", ip)
+    }
+}
+
+/// Push the current state and new log entries to all WebSocket clients
+///
+/// `log_sent` tracks `DeviceState::log_total` as of the previous push.
+pub fn broadcast_state(state: &SharedState, log_sent: &mut u64) {
+    // Sending blocks until the HTTP server task has written the frame, and
+    // that task locks WS_CLIENTS itself, so send from a copy.
+    let clients = WS_CLIENTS.lock().unwrap().clone();
+
+    let json = {
+        let s = state.lock().unwrap();
+        let new_entries = ((s.log_total - *log_sent) as usize).min(s.log.len());
+        *log_sent = s.log_total;
+        if clients.is_empty() {
+            return;
+        }
+        let update = WsUpdate {
+            state: &s,
+            log: s.log.iter().skip(s.log.len() - new_entries).collect(),
+        };
+        serde_json::to_string(&update).unwrap_or_default()
+    };
+
+    for mut client in clients {
+        if client
+            .send(FrameType::Text(false), json.as_bytes())
+            .is_err()
+        {
+            let session = client.session();
+            WS_CLIENTS
+                .lock()
+                .unwrap()
+                .retain(|c| c.session() != session);
+            warn!("Dropped WebSocket client {}", session);
+        }
+    }
+}
+
 fn generate_dashboard_html(state: &DeviceState) -> String {
     format!(
         r#"<!DOCTYPE html>
@@ -105,6 +241,7 @@ fn generate_dashboard_html(state: &DeviceState) -> String {
         .status {{ display: inline-block; width: 10px; height: 10px; border-radius: 50%; margin-right: 8px; }}
         .status.connected {{ background: #00ff88; }}
         .status.disconnected {{ background: #ff4444; }}
+        .log {{ background: #0f3460; padding: 10px; border-radius: 8px; height: 300px; overflow-y: auto; font-size: 0.85em; white-space: pre-wrap; word-break: break-all; }}
     </style>
 </head>
 <body>
@@ -130,6 +267,14 @@ fn generate_dashboard_html(state: &DeviceState) -> String {
                     <div class="stat-label">IP Address</div>
                     <div class="stat-value">{}</div>
                 </div>
+                <div class="stat">
+                    <div class="stat-label">Last Received</div>
+                    <div class="stat-value" id="lastRx">{}</div>
+                </div>
+                <div class="stat">
+                    <div class="stat-label">Last Sent</div>
+                    <div class="stat-value" id="lastTx">{}</div>
+                </div>
             </div>
         </div>
 
@@ -166,6 +311,11 @@ fn generate_dashboard_html(state: &DeviceState) -> String {
                 <input type="range" min="0" max="8000" step="100" value="{}" id="rpmSlider" onchange="updateData()">
             </div>
         </div>
+
+        <div class="card">
+            <h2>Serial Log</h2>
+            <pre class="log" id="serialLog"></pre>
+        </div>
     </div>
 
     <script>
@@ -194,11 +344,34 @@ fn generate_dashboard_html(state: &DeviceState) -> String {
             fetch('/api/data', {{ method: 'POST', body: JSON.stringify(data) }});
         }}
 
-        setInterval(() => {{
-            fetch('/api/state').then(r => r.json()).then(s => {{
-                document.getElementById('msgCount').textContent = s.message_count;
-            }});
-        }}, 2000);
+        function appendLog(entries) {{
+            const log = document.getElementById('serialLog');
+            const atBottom = log.scrollTop + log.clientHeight >= log.scrollHeight - 5;
+            const lines = log.textContent ? log.textContent.split('\n') : [];
+            for (const e of entries) {{
+                lines.push(`[${{(e.timestamp / 1000).toFixed(3)}}] ${{e.direction}} ${{e.data}}`);
+            }}
+            log.textContent = lines.slice(-{}).join('\n');
+            if (atBottom) log.scrollTop = log.scrollHeight;
+        }}
+
+        function connect() {{
+            const ws = new WebSocket(`ws://${{location.host}}/ws`);
+            ws.onopen = () => {{
+                document.getElementById('serialLog').textContent = '';
+                fetch('/api/log').then(r => r.json()).then(appendLog);
+            }};
+            ws.onmessage = (msg) => {{
+                const u = JSON.parse(msg.data);
+                document.getElementById('modeValue').textContent = u.state.mode;
+                document.getElementById('msgCount').textContent = u.state.message_count;
+                document.getElementById('lastRx').textContent = u.state.last_received;
+                document.getElementById('lastTx').textContent = u.state.last_sent;
+                if (u.log.length) appendLog(u.log);
+            }};
+            ws.onclose = () => setTimeout(connect, 2000);
+        }}
+        connect();
     </script>
 </body>
 </html>"#,
@@ -206,6 +379,8 @@ fn generate_dashboard_html(state: &DeviceState) -> String {
         state.message_count,
         state.wifi_ssid,
         state.wifi_ip,
+        state.last_received,
+        state.last_sent,
         if state.mode == ProtocolMode::Setup { "selected" } else { "" },
         if state.mode == ProtocolMode::Echo { "selected" } else { "" },
         if state.mode == ProtocolMode::AtCommand { "selected" } else { "" },
@@ -222,5 +397,6 @@ fn generate_dashboard_html(state: &DeviceState) -> String {
         state.simulated_data.speed as i32,
         state.simulated_data.rpm,
         state.simulated_data.rpm,
+        crate::types::LOG_CAPACITY,
     )
 }
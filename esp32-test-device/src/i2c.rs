@@ -0,0 +1,88 @@
+//! I2C master on the I2C0 peripheral, turns the device into a USB-to-I2C bridge
+
+use esp_idf_svc::hal::{
+    delay::TickType,
+    gpio::{Gpio8, Gpio9},
+    i2c::{I2cConfig, I2cDriver, I2C0},
+    prelude::*,
+};
+use log::*;
+
+use crate::protocols::i2c::{
+    format_hex, format_scan, parse_i2c_command, I2cCommand, SCAN_ADDRESSES,
+};
+
+/// Bus clock, standard mode so slow peripherals keep up
+const I2C_BAUDRATE_KHZ: u32 = 100;
+
+/// Timeout for a single I2C transaction
+const I2C_TIMEOUT_MS: u64 = 50;
+
+/// The I2C0 peripheral and its pins, the driver starts on first use
+pub struct I2cBridge {
+    pins: Option<(I2C0, Gpio8, Gpio9)>,
+    driver: Option<I2cDriver<'static>>,
+}
+
+impl I2cBridge {
+    /// SDA on GPIO8 and SCL on GPIO9
+    pub fn new(i2c: I2C0, sda: Gpio8, scl: Gpio9) -> Self {
+        Self {
+            pins: Some((i2c, sda, scl)),
+            driver: None,
+        }
+    }
+
+    /// Start the driver if it is not running yet
+    ///
+    /// Called when I2C mode is selected, so wiring errors show up right away.
+    pub fn driver(&mut self) -> Result<&mut I2cDriver<'static>, String> {
+        if self.driver.is_none() {
+            // Pins are consumed by the first attempt, so a failed start cannot be retried
+            let (i2c, sda, scl) = self
+                .pins
+                .take()
+                .ok_or_else(|| "I2C master not available".to_string())?;
+            let config = I2cConfig::new().baudrate(I2C_BAUDRATE_KHZ.kHz().into());
+            let driver = I2cDriver::new(i2c, sda, scl, &config)
+                .map_err(|e| format!("Failed to start I2C master: {:?}", e))?;
+            info!("I2C master started, SDA GPIO8, SCL GPIO9");
+            self.driver = Some(driver);
+        }
+        Ok(self.driver.as_mut().unwrap())
+    }
+
+    /// Run an I2C mode command line and return the reply
+    pub fn process_command(&mut self, line: &str) -> String {
+        let command = match parse_i2c_command(line) {
+            Ok(command) => command,
+            Err(e) => return format!("ERROR - {}", e),
+        };
+        let driver = match self.driver() {
+            Ok(driver) => driver,
+            Err(e) => return format!("ERROR - {}", e),
+        };
+        let timeout = TickType::new_millis(I2C_TIMEOUT_MS).ticks();
+
+        match command {
+            I2cCommand::Write { addr, data } => match driver.write(addr, &data, timeout) {
+                Ok(()) => format!("OK - Wrote {} bytes to 0x{:02X}", data.len(), addr),
+                Err(e) => format!("ERROR - I2C write to 0x{:02X} failed: {:?}", addr, e),
+            },
+            I2cCommand::Read { addr, len } => {
+                let mut buf = vec![0u8; len];
+                match driver.read(addr, &mut buf, timeout) {
+                    Ok(()) => format_hex(&buf),
+                    Err(e) => format!("ERROR - I2C read from 0x{:02X} failed: {:?}", addr, e),
+                }
+            }
+            I2cCommand::Scan => {
+                // An empty write is just the address byte, a device ACKs it if present
+                let found: Vec<u8> = SCAN_ADDRESSES
+                    .filter(|&addr| driver.write(addr, &[], timeout).is_ok())
+                    .collect();
+                format_scan(&found)
+            }
+        }
+    }
+}
@@ -17,11 +17,19 @@
 //! - WIFI_CLEAR                - Clear stored credentials
 //! - WIFI_SCAN                 - Scan for networks
 //! - MODE=<mode>               - Set protocol mode
+//! - UART_BAUD=<rate>          - Change the auxiliary UART baud rate
+//! - PWM_FREQ=<hz>, PWM_DUTY=<pct> - Drive the PWM output on GPIO21
+//! - OTA_URL=<url>             - Update the firmware over WiFi
+//! - MODE=I2C                  - Bridge I2C_WRITE/I2C_READ/I2C_SCAN to SDA GPIO8, SCL GPIO9
 //! - HELP                      - Show available commands
 
 mod commands;
 mod http;
+mod i2c;
+mod nvs_config;
+mod ota;
 mod protocols;
+mod pwm;
 mod serial;
 mod types;
 mod wifi;
@@ -36,10 +44,20 @@ use esp_idf_svc::{
 use log::*;
 use std::sync::{Arc, Mutex};
 
-use commands::{is_binary_mode, process_binary_data, process_line, show_welcome_message, BinaryProtocolState};
-use http::start_http_server;
-use serial::{init_usb_serial, read_bytes, send_bytes, send_line};
-use types::{DeviceState, ProtocolMode};
+use commands::{
+    is_binary_mode, process_binary_data, process_line, show_welcome_message, BinaryProtocolState,
+    DeviceIo,
+};
+use http::{broadcast_state, dashboard_urls, start_http_server};
+use i2c::I2cBridge;
+use nvs_config::{
+    load_scpi_errors_from_nvs, load_sim_data_from_nvs, SCPI_ERR_NAMESPACE, SIM_DATA_NAMESPACE,
+};
+use pwm::PwmOutput;
+use serial::{
+    echo_aux_uart, init_aux_uart, init_usb_serial, read_bytes, send_bytes, send_line, uptime_ms,
+};
+use types::{DeviceState, LogDirection, ProtocolMode};
 use wifi::{load_wifi_config, try_connect_wifi, WifiManager, NVS_NAMESPACE};
 
 fn main() -> anyhow::Result<()> {
@@ -64,6 +82,37 @@ fn main() -> anyhow::Result<()> {
         warn!("USB Serial init error: {}", e);
     }
 
+    // Auxiliary UART (TX GPIO17, RX GPIO18) for testing baud rate switching
+    let aux_uart = match init_aux_uart(
+        peripherals.uart1,
+        peripherals.pins.gpio17,
+        peripherals.pins.gpio18,
+    ) {
+        Ok(uart) => Some(uart),
+        Err(e) => {
+            warn!("Auxiliary UART init error: {:?}", e);
+            None
+        }
+    };
+
+    // PWM output for hardware-in-the-loop tests, GPIO18 is taken by the auxiliary UART
+    let pwm = match PwmOutput::new(
+        peripherals.ledc.timer0,
+        peripherals.ledc.channel0,
+        peripherals.pins.gpio21,
+    ) {
+        Ok(pwm) => Some(pwm),
+        Err(e) => {
+            warn!("PWM init error: {:?}", e);
+            None
+        }
+    };
+
+    // I2C master for MODE=I2C, GPIO21 is taken by the PWM output
+    let i2c = I2cBridge::new(peripherals.i2c0, peripherals.pins.gpio8, peripherals.pins.gpio9);
+
+    let mut io = DeviceIo { aux_uart, pwm, i2c };
+
     // Setup LED for status indication
     let mut led = PinDriver::output(peripherals.pins.gpio2)?;
 
@@ -73,6 +122,14 @@ fn main() -> anyhow::Result<()> {
     // Try to load stored WiFi credentials
     let stored_config = load_wifi_config(&nvs);
 
+    // Restore simulated data saved by SET_* commands
+    let sim_nvs = EspNvs::new(nvs_default.clone(), SIM_DATA_NAMESPACE, true)?;
+    state.lock().unwrap().simulated_data = load_sim_data_from_nvs(&sim_nvs);
+
+    // Restore the SCPI error queue from before the last power cycle
+    let scpi_nvs = EspNvs::new(nvs_default.clone(), SCPI_ERR_NAMESPACE, true)?;
+    state.lock().unwrap().scpi_errors = load_scpi_errors_from_nvs(&scpi_nvs);
+
     // Initialize WiFi
     let wifi = BlockingWifi::wrap(
         EspWifi::new(peripherals.modem, sys_loop.clone(), Some(nvs_default))?,
@@ -82,6 +139,8 @@ fn main() -> anyhow::Result<()> {
     let mut wifi_mgr = WifiManager {
         wifi,
         nvs,
+        sim_nvs,
+        scpi_nvs,
         pending_ssid: String::new(),
         pending_pass: String::new(),
     };
@@ -105,6 +164,7 @@ fn main() -> anyhow::Result<()> {
             match start_http_server(state.clone()) {
                 Ok(server) => {
                     info!("Web dashboard: http://{}", ip);
+                    send_line(&dashboard_urls(&ip));
                     http_server = Some(server);
                 }
                 Err(e) => warn!("Failed to start HTTP server: {:?}", e),
@@ -122,9 +182,11 @@ fn main() -> anyhow::Result<()> {
     let mut line_buf = String::new();
     let mut binary_buf: Vec<u8> = Vec::with_capacity(512);
     let mut stdin_buf = [0u8; 256];
+    let mut aux_buf = [0u8; 256];
     let mut binary_state = BinaryProtocolState::new();
     let mut binary_idle_count = 0u32;
     const BINARY_FRAME_TIMEOUT: u32 = 5; // Number of idle cycles before processing binary frame
+    let mut ws_log_sent = 0u64; // DeviceState::log_total as of the last WebSocket push
 
     loop {
         // Blink LED based on WiFi status
@@ -151,10 +213,7 @@ fn main() -> anyhow::Result<()> {
                 binary_buf.extend_from_slice(&stdin_buf[..bytes_read as usize]);
 
                 // Update state
-                {
-                    let mut s = state.lock().unwrap();
-                    s.message_count += 1;
-                }
+                state.lock().unwrap().record_message();
             } else {
                 // Text-based protocol mode - process lines
                 for &byte in &stdin_buf[..bytes_read as usize] {
@@ -166,17 +225,20 @@ fn main() -> anyhow::Result<()> {
                             // Update state
                             {
                                 let mut s = state.lock().unwrap();
-                                s.message_count += 1;
+                                s.record_message();
                                 s.last_received = line.clone();
+                                s.push_log(LogDirection::Rx, line.clone(), uptime_ms());
                             }
 
                             // Process the line based on mode
                             let response =
-                                process_line(&line, current_mode, &state, &mut wifi_mgr, &mut http_server);
+                                process_line(&line, current_mode, &state, &mut wifi_mgr, &mut http_server, &mut binary_state, &mut io);
 
                             if !response.is_empty() {
                                 send_line(&response);
-                                state.lock().unwrap().last_sent = response;
+                                let mut s = state.lock().unwrap();
+                                s.push_log(LogDirection::Tx, response.clone(), uptime_ms());
+                                s.last_sent = response;
                             }
                         }
                     } else {
@@ -190,12 +252,23 @@ fn main() -> anyhow::Result<()> {
 
             if binary_idle_count >= BINARY_FRAME_TIMEOUT {
                 // Process the accumulated binary frame
+                state.lock().unwrap().push_log(LogDirection::Rx, format!("{:02X?}", binary_buf), uptime_ms());
                 if let Some(response) = process_binary_data(&binary_buf, current_mode, &state, &mut binary_state) {
                     send_bytes(&response);
+                    state.lock().unwrap().push_log(LogDirection::Tx, format!("{:02X?}", response), uptime_ms());
                 }
                 binary_buf.clear();
                 binary_idle_count = 0;
             }
         }
+
+        if let Some(uart) = &io.aux_uart {
+            echo_aux_uart(uart, &mut aux_buf);
+        }
+
+        // Push live state to dashboard WebSocket clients
+        if http_server.is_some() {
+            broadcast_state(&state, &mut ws_log_sent);
+        }
     }
 }
@@ -0,0 +1,80 @@
+//! NVS persistence of simulated data set via SET_* commands and the SCPI error queue
+
+use esp_idf_svc::nvs::{EspNvs, NvsDefault};
+use log::*;
+
+use crate::protocols::ScpiErrorQueue;
+use crate::types::SimulatedData;
+
+/// NVS namespace for storing simulated data values
+pub const SIM_DATA_NAMESPACE: &str = "sim_data";
+const NVS_KEY_TEMP: &str = "temp";
+const NVS_KEY_HUMID: &str = "humid";
+const NVS_KEY_RPM: &str = "rpm";
+const NVS_KEY_SPEED: &str = "speed";
+const NVS_KEY_VOLT: &str = "volt";
+
+/// NVS namespace for storing the SCPI error queue
+pub const SCPI_ERR_NAMESPACE: &str = "scpi_err";
+const NVS_KEY_SCPI_QUEUE: &str = "queue";
+
+/// Load persisted simulated data, keeping defaults for values never saved
+pub fn load_sim_data_from_nvs(nvs: &EspNvs<NvsDefault>) -> SimulatedData {
+    let mut data = SimulatedData::default();
+
+    // NVS has no float type, floats are stored as their bit pattern
+    let get_f32 = |key| nvs.get_u32(key).ok().flatten().map(f32::from_bits);
+    if let Some(temp) = get_f32(NVS_KEY_TEMP) {
+        data.temperature = temp;
+    }
+    if let Some(humid) = get_f32(NVS_KEY_HUMID) {
+        data.humidity = humid;
+    }
+    if let Some(speed) = get_f32(NVS_KEY_SPEED) {
+        data.speed = speed;
+    }
+    if let Some(volt) = get_f32(NVS_KEY_VOLT) {
+        data.voltage = volt;
+    }
+    if let Ok(Some(rpm)) = nvs.get_u16(NVS_KEY_RPM) {
+        data.rpm = rpm;
+    }
+
+    data
+}
+
+/// Save the persisted simulated data values to NVS
+pub fn save_sim_data_to_nvs(
+    nvs: &mut EspNvs<NvsDefault>,
+    data: &SimulatedData,
+) -> anyhow::Result<()> {
+    nvs.set_u32(NVS_KEY_TEMP, data.temperature.to_bits())?;
+    nvs.set_u32(NVS_KEY_HUMID, data.humidity.to_bits())?;
+    nvs.set_u16(NVS_KEY_RPM, data.rpm)?;
+    nvs.set_u32(NVS_KEY_SPEED, data.speed.to_bits())?;
+    nvs.set_u32(NVS_KEY_VOLT, data.voltage.to_bits())?;
+    debug!("Simulated data saved to NVS");
+    Ok(())
+}
+
+/// Load the SCPI error queue saved before the last power cycle
+pub fn load_scpi_errors_from_nvs(nvs: &EspNvs<NvsDefault>) -> ScpiErrorQueue {
+    let mut buf = [0u8; 2048];
+    match nvs.get_str(NVS_KEY_SCPI_QUEUE, &mut buf) {
+        Ok(Some(json)) => serde_json::from_str(json).unwrap_or_else(|e| {
+            warn!("Invalid SCPI error queue in NVS: {:?}", e);
+            ScpiErrorQueue::default()
+        }),
+        _ => ScpiErrorQueue::default(),
+    }
+}
+
+/// Save the SCPI error queue, stored as JSON
+pub fn save_scpi_errors_to_nvs(
+    nvs: &mut EspNvs<NvsDefault>,
+    errors: &ScpiErrorQueue,
+) -> anyhow::Result<()> {
+    nvs.set_str(NVS_KEY_SCPI_QUEUE, &serde_json::to_string(errors)?)?;
+    debug!("SCPI error queue saved to NVS");
+    Ok(())
+}
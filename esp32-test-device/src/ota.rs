@@ -0,0 +1,87 @@
+//! Firmware updates downloaded over HTTP into the inactive OTA slot
+
+use std::time::Duration;
+
+use embedded_svc::{
+    http::{client::Client, Headers, Status},
+    io::Read,
+};
+use esp_idf_svc::{
+    http::client::{Configuration, EspHttpConnection},
+    io::EspIOError,
+    ota::{EspOta, EspOtaUpdate},
+};
+
+/// Bytes read from the HTTP response per flash write
+const OTA_CHUNK_SIZE: usize = 4096;
+
+/// Timeout for each HTTP operation while downloading
+const OTA_HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Download the image at `url` and write it to the next OTA slot
+///
+/// `on_progress` is called with the percentage whenever it changes, only if
+/// the server sends a Content-Length. The device boots the new image after
+/// the next restart.
+pub fn download_and_install(url: &str, on_progress: impl FnMut(u8)) -> anyhow::Result<()> {
+    let connection = EspHttpConnection::new(&Configuration {
+        timeout: Some(OTA_HTTP_TIMEOUT),
+        crt_bundle_attach: Some(esp_idf_svc::sys::esp_crt_bundle_attach),
+        ..Default::default()
+    })?;
+    let mut client = Client::wrap(connection);
+    let mut response = client.get(url)?.submit()?;
+    let status = response.status();
+    if status != 200 {
+        anyhow::bail!("HTTP status {}", status);
+    }
+    let total = response.content_len().unwrap_or(0);
+
+    let mut ota = EspOta::new()?;
+    let mut update = ota.initiate_update()?;
+    match write_image(&mut response, &mut update, total, on_progress) {
+        Ok(()) => {
+            update.complete()?;
+            Ok(())
+        }
+        Err(e) => {
+            if let Err(abort_err) = update.abort() {
+                log::warn!("Failed to abort OTA update: {:?}", abort_err);
+            }
+            Err(e)
+        }
+    }
+}
+
+fn write_image(
+    response: &mut impl Read<Error = EspIOError>,
+    update: &mut EspOtaUpdate<'_>,
+    total: u64,
+    mut on_progress: impl FnMut(u8),
+) -> anyhow::Result<()> {
+    let mut buf = vec![0u8; OTA_CHUNK_SIZE];
+    let mut written = 0u64;
+    let mut last_pct = None;
+    loop {
+        let n = response.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        update.write(&buf[..n])?;
+        written += n as u64;
+        if total > 0 {
+            let pct = (written * 100 / total).min(100) as u8;
+            if last_pct != Some(pct) {
+                on_progress(pct);
+                last_pct = Some(pct);
+            }
+        }
+    }
+    if written == 0 {
+        anyhow::bail!("empty image");
+    }
+    if total > 0 && written < total {
+        anyhow::bail!("download truncated at {} of {} bytes", written, total);
+    }
+    Ok(())
+}
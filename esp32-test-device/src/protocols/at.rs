@@ -1,7 +1,13 @@
 //! AT Command processor (ESP32 style)
 
+use crate::types::SimulatedData;
+
+/// Commands with a specific response, reported by GET_PROTO
+pub const SUPPORTED_COMMANDS: &[&str] =
+    &["AT", "AT+GMR", "AT+RST", "AT+CWMODE", "AT+CWLAP", "AT+CBC"];
+
 /// Process an AT command and return the response
-pub fn process_at_command(line: &str) -> String {
+pub fn process_at_command(line: &str, sim_data: &SimulatedData) -> String {
     let cmd = line.trim().to_uppercase();
 
     match cmd.as_str() {
@@ -16,6 +22,13 @@ pub fn process_at_command(line: &str) -> String {
              +CWLAP:(4,\"OtherWiFi\",-60,\"11:22:33:44:55:66\",6)\r\n\r\nOK"
                 .to_string()
         }
+        // +CBC: <charging>,<percent>,<millivolts>
+        "AT+CBC" => format!(
+            "+CBC: {},{},{}\r\n\r\nOK",
+            sim_data.charging as u8,
+            sim_data.battery_percent,
+            (sim_data.battery_voltage * 1000.0).round() as u32
+        ),
         _ if cmd.starts_with("AT+") => "OK".to_string(),
         _ => "ERROR".to_string(),
     }
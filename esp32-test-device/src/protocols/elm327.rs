@@ -2,6 +2,29 @@
 
 use crate::types::SimulatedData;
 
+/// Vehicle identification number reported for PID 0902
+const VIN: &[u8; 17] = b"WOLDB7JM5539B3232";
+
+/// Data bytes in the first frame and in each consecutive frame of an ISO-TP message
+const FIRST_FRAME_BYTES: usize = 6;
+const CONSECUTIVE_FRAME_BYTES: usize = 7;
+
+/// Format a response longer than one CAN frame the way ELM327 prints it:
+/// the byte count, then one numbered line per frame
+fn format_multi_frame(payload: &[u8]) -> String {
+    let mut response = format!("{:03X}\r", payload.len());
+    let (first, rest) = payload.split_at(payload.len().min(FIRST_FRAME_BYTES));
+    let frames = std::iter::once(first).chain(rest.chunks(CONSECUTIVE_FRAME_BYTES));
+    for (index, frame) in frames.enumerate() {
+        let bytes: Vec<String> = frame.iter().map(|b| format!("{:02X}", b)).collect();
+        // Frame numbers are a single hex digit that wraps around
+        response.push_str(&format!("{:X}: {}\r", index % 16, bytes.join(" ")));
+    }
+    response.pop();
+    response.push_str("\r\n>");
+    response
+}
+
 /// Process an ELM327 OBD-II command and return the response
 pub fn process_elm327_command(line: &str, sim_data: &SimulatedData) -> String {
     let cmd = line.trim().to_uppercase();
@@ -21,6 +44,35 @@ pub fn process_elm327_command(line: &str, sim_data: &SimulatedData) -> String {
     } else if cmd == "0100" {
         // Supported PIDs 01-20
         "41 00 BE 3F A8 13\r\n\r\n>".to_string()
+    } else if cmd == "0101" {
+        // Monitor status since DTCs cleared (PID 01): MIL on with 1 DTC, spark monitors ready
+        "41 01 81 07 FF FF\r\n\r\n>".to_string()
+    } else if cmd == "0104" {
+        // Calculated engine load (PID 04)
+        let load = (sim_data.rpm as f32 / 8000.0 * 255.0) as u8;
+        format!("41 04 {:02X}\r\n\r\n>", load)
+    } else if cmd == "0120" {
+        // Supported PIDs 21-40, the last bit announces 41-60
+        "41 20 80 00 00 01\r\n\r\n>".to_string()
+    } else if cmd == "0121" {
+        // Distance traveled with MIL on (PID 21), km
+        let distance = (sim_data.speed as u16).saturating_mul(10);
+        format!(
+            "41 21 {:02X} {:02X}\r\n\r\n>",
+            (distance >> 8) as u8,
+            (distance & 0xFF) as u8
+        )
+    } else if cmd == "0140" {
+        // Supported PIDs 41-60
+        "41 40 40 00 00 00\r\n\r\n>".to_string()
+    } else if cmd == "0142" {
+        // Control module voltage (PID 42), mV
+        let millivolts = (sim_data.effective_voltage() * 1000.0).round() as u16;
+        format!(
+            "41 42 {:02X} {:02X}\r\n\r\n>",
+            (millivolts >> 8) as u8,
+            (millivolts & 0xFF) as u8
+        )
     } else if cmd == "010C" {
         // Engine RPM (PID 0C)
         let rpm_value = sim_data.rpm * 4;
@@ -35,6 +87,11 @@ pub fn process_elm327_command(line: &str, sim_data: &SimulatedData) -> String {
     } else if cmd == "0105" {
         // Coolant temperature (PID 05)
         format!("41 05 {:02X}\r\n\r\n>", (sim_data.temperature + 40.0) as u8)
+    } else if cmd == "0902" {
+        // VIN (mode 09 PID 02): message count, then the 17 VIN characters
+        let mut payload = vec![0x49, 0x02, 0x01];
+        payload.extend_from_slice(VIN);
+        format_multi_frame(&payload)
     } else if cmd.starts_with("AT") {
         // Other AT commands
         "OK\r\n\r\n>".to_string()
@@ -46,3 +103,48 @@ pub fn process_elm327_command(line: &str, sim_data: &SimulatedData) -> String {
         "?\r\n\r\n>".to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_monitor_status() {
+        let response = process_elm327_command("0101", &SimulatedData::default());
+        assert!(response.starts_with("41 01 81 07 FF FF"));
+    }
+
+    #[test]
+    fn test_engine_load_and_distance() {
+        let sim_data = SimulatedData {
+            rpm: 4000,
+            speed: 60.0,
+            ..Default::default()
+        };
+        assert!(process_elm327_command("0104", &sim_data).starts_with("41 04 7F"));
+        // 600 km = 0x0258
+        assert!(process_elm327_command("0121", &sim_data).starts_with("41 21 02 58"));
+    }
+
+    #[test]
+    fn test_control_module_voltage_sags_with_rpm() {
+        // 3.3V at idle = 3300 mV = 0x0CE4
+        assert!(
+            process_elm327_command("0142", &SimulatedData::default()).starts_with("41 42 0C E4")
+        );
+        let sim_data = SimulatedData {
+            rpm: 8000,
+            ..Default::default()
+        };
+        // 3.0V at full RPM = 3000 mV = 0x0BB8
+        assert!(process_elm327_command("0142", &sim_data).starts_with("41 42 0B B8"));
+    }
+
+    #[test]
+    fn test_vin_multi_frame() {
+        assert_eq!(
+            process_elm327_command("0902", &SimulatedData::default()),
+            "014\r0: 49 02 01 57 4F 4C\r1: 44 42 37 4A 4D 35 35\r2: 33 39 42 33 32 33 32\r\n>"
+        );
+    }
+}
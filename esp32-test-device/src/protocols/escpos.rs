@@ -17,6 +17,9 @@ const STATUS_DRAWER_OPEN: u8 = 0x04;
 const STATUS_PAPER_PRESENT: u8 = 0x00;
 const STATUS_PAPER_END: u8 = 0x0C;
 
+/// Length of the GS v 0 m xL xH yL yH header preceding raster image data
+const RASTER_HEADER_LEN: usize = 8;
+
 /// ESC/POS Printer emulator state
 pub struct EscPosEmulator {
     /// Print buffer (accumulated text)
@@ -43,6 +46,8 @@ pub struct EscPosEmulator {
     bytes_received: u32,
     /// Total lines printed
     lines_printed: u32,
+    /// Raster image bytes still to be skipped, when an image spans several reads
+    raster_bytes_pending: usize,
 }
 
 impl EscPosEmulator {
@@ -60,6 +65,7 @@ impl EscPosEmulator {
             has_error: false,
             bytes_received: 0,
             lines_printed: 0,
+            raster_bytes_pending: 0,
         }
     }
 
@@ -72,7 +78,9 @@ impl EscPosEmulator {
             return None;
         }
 
-        let mut i = 0;
+        // Continue skipping an image whose data did not fit in the previous read
+        let mut i = self.raster_bytes_pending.min(data.len());
+        self.raster_bytes_pending -= i;
         let mut response: Option<Vec<u8>> = None;
 
         while i < data.len() {
@@ -318,6 +326,21 @@ impl EscPosEmulator {
                 log::info!("ESC/POS: Barcode print");
                 (4, None)
             }
+            // GS v 0 m xL xH yL yH d1...dk - Print raster bit image
+            // The image is not rendered, only skipped to keep the stream in sync.
+            // Some drivers send m = 0x00 instead of '0'.
+            b'v' => {
+                if data.len() >= RASTER_HEADER_LEN && matches!(data[2], b'0' | 0) {
+                    let width_bytes = u16::from_le_bytes([data[4], data[5]]) as usize;
+                    let height = u16::from_le_bytes([data[6], data[7]]) as usize;
+                    let total = RASTER_HEADER_LEN + width_bytes * height;
+                    self.raster_bytes_pending = total.saturating_sub(data.len());
+                    log::info!("ESC/POS: Raster image {}x{} dots", width_bytes * 8, height);
+                    (total, None)
+                } else {
+                    (2, None)
+                }
+            }
             // GS ( k - Print 2D barcode (QR, PDF417, etc.)
             // This is complex, simplified handling
             _ => (2, None),
@@ -417,6 +440,17 @@ impl EscPosEmulator {
         (self.bytes_received, self.lines_printed)
     }
 
+    /// Human-readable summary of statistics and printer state
+    pub fn stats_summary(&self) -> String {
+        format!(
+            "ESC/POS: Bytes received: {}\r\nLines printed: {}\r\nPaper: {}\r\nError: {}",
+            self.bytes_received,
+            self.lines_printed,
+            if self.paper_present { "present" } else { "end" },
+            if self.has_error { "yes" } else { "no" }
+        )
+    }
+
     /// Set paper present state (for testing paper-out scenarios)
     pub fn set_paper_present(&mut self, present: bool) {
         self.paper_present = present;
@@ -490,6 +524,19 @@ mod tests {
         assert!(lines >= 2);
     }
 
+    #[test]
+    fn test_state_persists_across_calls() {
+        let mut emu = EscPosEmulator::new();
+        let sim_data = SimulatedData::default();
+
+        emu.process(b"First\n", &sim_data);
+        emu.process(b"Second\n", &sim_data);
+
+        let (bytes, lines) = emu.get_stats();
+        assert_eq!(bytes, 13);
+        assert_eq!(lines, 2);
+    }
+
     #[test]
     fn test_paper_status() {
         let mut emu = EscPosEmulator::new();
@@ -508,4 +555,37 @@ mod tests {
         assert!(response.is_some());
         assert_eq!(response.unwrap()[0], STATUS_PAPER_END);
     }
+
+    #[test]
+    fn test_raster_image_skipped() {
+        let mut emu = EscPosEmulator::new();
+        let sim_data = SimulatedData::default();
+
+        // GS v 0, 2 bytes wide, 3 dots high; image data contains a line feed
+        let mut data = vec![GS, b'v', b'0', 0, 2, 0, 3, 0];
+        data.extend_from_slice(&[0x0A; 6]);
+        data.extend_from_slice(b"After\n");
+        emu.process(&data, &sim_data);
+
+        let (_, lines) = emu.get_stats();
+        assert_eq!(lines, 1);
+    }
+
+    #[test]
+    fn test_raster_image_across_reads() {
+        let mut emu = EscPosEmulator::new();
+        let sim_data = SimulatedData::default();
+
+        // 4 x 4 image, only half of the data arrives in the first read
+        let mut first = vec![GS, b'v', b'0', 0, 4, 0, 4, 0];
+        first.extend_from_slice(&[0x0A; 8]);
+        emu.process(&first, &sim_data);
+
+        let mut second = vec![0x0A; 8];
+        second.extend_from_slice(b"After\n");
+        emu.process(&second, &sim_data);
+
+        let (_, lines) = emu.get_stats();
+        assert_eq!(lines, 1);
+    }
 }
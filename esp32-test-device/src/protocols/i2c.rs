@@ -0,0 +1,133 @@
+//! I2C master bridge command parser
+//!
+//! The bus itself is driven by `crate::i2c::I2cBridge`, this module only
+//! turns command lines into requests and formats the replies.
+
+/// Commands handled in I2C mode, reported by GET_PROTO
+pub const SUPPORTED_COMMANDS: &[&str] = &["I2C_WRITE", "I2C_READ", "I2C_SCAN"];
+
+/// Addresses probed by I2C_SCAN, the rest are reserved by the I2C spec
+pub const SCAN_ADDRESSES: std::ops::RangeInclusive<u8> = 0x03..=0x77;
+
+/// Largest I2C_READ or I2C_WRITE payload
+pub const MAX_TRANSFER_LEN: usize = 128;
+
+/// A parsed I2C mode command
+#[derive(Debug, PartialEq)]
+pub enum I2cCommand {
+    Write { addr: u8, data: Vec<u8> },
+    Read { addr: u8, len: usize },
+    Scan,
+}
+
+/// Parse `I2C_WRITE=<addr>:<hex_data>`, `I2C_READ=<addr>:<n_bytes>` or `I2C_SCAN`
+///
+/// Addresses are 7-bit hex, with or without `0x`. Spaces in the data are ignored.
+pub fn parse_i2c_command(line: &str) -> Result<I2cCommand, String> {
+    let line = line.trim();
+    let upper = line.to_uppercase();
+    if upper == "I2C_SCAN" {
+        return Ok(I2cCommand::Scan);
+    }
+    if upper.starts_with("I2C_WRITE=") {
+        let (addr, data) = split_args(&line[10..])?;
+        let data = parse_hex_bytes(data)?;
+        if data.is_empty() || data.len() > MAX_TRANSFER_LEN {
+            return Err(format!("Data must be 1-{} bytes", MAX_TRANSFER_LEN));
+        }
+        return Ok(I2cCommand::Write { addr, data });
+    }
+    if upper.starts_with("I2C_READ=") {
+        let (addr, len) = split_args(&line[9..])?;
+        return match len.trim().parse::<usize>() {
+            Ok(len) if (1..=MAX_TRANSFER_LEN).contains(&len) => Ok(I2cCommand::Read { addr, len }),
+            _ => Err(format!("Byte count must be 1-{}", MAX_TRANSFER_LEN)),
+        };
+    }
+    Err("Unknown I2C command. Use I2C_WRITE, I2C_READ or I2C_SCAN".to_string())
+}
+
+/// Format bytes as space-separated hex, the reply to I2C_READ
+pub fn format_hex(data: &[u8]) -> String {
+    data.iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Format the addresses that acknowledged, the reply to I2C_SCAN
+pub fn format_scan(addrs: &[u8]) -> String {
+    if addrs.is_empty() {
+        return "ADDR: (none)".to_string();
+    }
+    let addrs: Vec<String> = addrs.iter().map(|a| format!("0x{:02X}", a)).collect();
+    format!("ADDR: {}", addrs.join(" "))
+}
+
+fn split_args(args: &str) -> Result<(u8, &str), String> {
+    let (addr, rest) = args
+        .split_once(':')
+        .ok_or_else(|| "Expected <addr>:<value>".to_string())?;
+    Ok((parse_addr(addr)?, rest))
+}
+
+fn parse_addr(addr: &str) -> Result<u8, String> {
+    let addr = addr.trim();
+    let hex = addr
+        .strip_prefix("0x")
+        .or_else(|| addr.strip_prefix("0X"))
+        .unwrap_or(addr);
+    match u8::from_str_radix(hex, 16) {
+        Ok(addr) if addr <= 0x7F => Ok(addr),
+        _ => Err(format!("Invalid 7-bit address: {}", addr)),
+    }
+}
+
+fn parse_hex_bytes(data: &str) -> Result<Vec<u8>, String> {
+    let digits: String = data.chars().filter(|c| !c.is_whitespace()).collect();
+    if digits.len() % 2 != 0 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("Invalid hex data: {}", data.trim()));
+    }
+    Ok((0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).unwrap_or(0))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_i2c_commands() {
+        assert_eq!(parse_i2c_command("i2c_scan"), Ok(I2cCommand::Scan));
+        assert_eq!(
+            parse_i2c_command("I2C_WRITE=0x3C:00 AF"),
+            Ok(I2cCommand::Write {
+                addr: 0x3C,
+                data: vec![0x00, 0xAF]
+            })
+        );
+        assert_eq!(
+            parse_i2c_command("I2C_READ=48:2"),
+            Ok(I2cCommand::Read { addr: 0x48, len: 2 })
+        );
+    }
+
+    #[test]
+    fn test_parse_i2c_rejects_bad_arguments() {
+        assert!(parse_i2c_command("I2C_WRITE=0x80:00").is_err());
+        assert!(parse_i2c_command("I2C_WRITE=0x3C:ABC").is_err());
+        assert!(parse_i2c_command("I2C_WRITE=0x3C:").is_err());
+        assert!(parse_i2c_command("I2C_READ=0x48").is_err());
+        assert!(parse_i2c_command("I2C_READ=0x48:0").is_err());
+        assert!(parse_i2c_command("I2C_PROBE").is_err());
+    }
+
+    #[test]
+    fn test_format_replies() {
+        assert_eq!(format_hex(&[0xDE, 0x0A]), "DE 0A");
+        assert_eq!(format_scan(&[0x3C, 0x48]), "ADDR: 0x3C 0x48");
+        assert_eq!(format_scan(&[]), "ADDR: (none)");
+    }
+}
@@ -3,6 +3,7 @@
 pub mod at;
 pub mod elm327;
 pub mod escpos;
+pub mod i2c;
 pub mod marlin;
 pub mod modbus;
 pub mod nmea;
@@ -12,6 +13,8 @@ pub use at::process_at_command;
 pub use elm327::process_elm327_command;
 pub use escpos::{process_escpos_data, EscPosEmulator};
 pub use marlin::process_marlin_gcode;
-pub use modbus::{process_modbus_rtu, ModbusServer, SLAVE_ADDRESS};
+pub use modbus::{
+    dispatch_frame, process_modbus_rtu, sync_slaves, ModbusServer, MAX_SLAVES, SLAVE_ADDRESS,
+};
 pub use nmea::generate_nmea_sentence;
-pub use scpi::process_scpi_command;
+pub use scpi::{process_scpi_command, ScpiErrorQueue};
@@ -13,18 +13,46 @@ use std::sync::{Arc, Mutex};
 /// Default slave address
 pub const SLAVE_ADDRESS: u8 = 1;
 
+/// Broadcast address, writes are applied but never answered
+pub const BROADCAST_ADDRESS: u8 = 0;
+
+/// Maximum number of emulated slaves on the bus
+pub const MAX_SLAVES: usize = 8;
+
+/// Function codes answered by the slaves, reported by GET_PROTO
+pub const SUPPORTED_FUNCTION_CODES: &[&str] = &[
+    "FC01", "FC02", "FC03", "FC04", "FC05", "FC06", "FC0F", "FC10",
+];
+
 /// Modbus context with simulated data
 pub struct ModbusServer {
     context: Arc<Mutex<ModbusStorageSmall>>,
+    slave_address: u8,
 }
 
 impl ModbusServer {
     pub fn new() -> Self {
+        Self::with_address(SLAVE_ADDRESS)
+    }
+
+    /// Create a server answering on `slave_address` (1-247)
+    pub fn with_address(slave_address: u8) -> Self {
         Self {
             context: Arc::new(Mutex::new(ModbusStorageSmall::new())),
+            slave_address,
         }
     }
 
+    /// Slave address this server answers on
+    pub fn address(&self) -> u8 {
+        self.slave_address
+    }
+
+    /// Change the slave address, keeping register contents
+    pub fn set_address(&mut self, slave_address: u8) {
+        self.slave_address = slave_address;
+    }
+
     /// Update registers with simulated data
     pub fn update_from_sim_data(&self, sim_data: &SimulatedData) {
         let mut ctx = self.context.lock().unwrap();
@@ -46,7 +74,7 @@ impl ModbusServer {
             };
         }
 
-        // Input registers (read-only sensor data) - addresses 0-7
+        // Input registers (read-only sensor data) - addresses 0-9
         // Register 0: Temperature ×10 (e.g., 250 = 25.0°C)
         set_register!(set_input, 0, (sim_data.temperature * 10.0) as u16, "input");
         // Register 1: Humidity ×10 (e.g., 500 = 50.0%)
@@ -55,24 +83,30 @@ impl ModbusServer {
         set_register!(set_input, 2, sim_data.rpm, "input");
         // Register 3: Speed ×10 (e.g., 600 = 60.0 km/h)
         set_register!(set_input, 3, (sim_data.speed * 10.0) as u16, "input");
-        // Register 4: Voltage ×100 (e.g., 330 = 3.30V)
-        set_register!(set_input, 4, (sim_data.voltage * 100.0) as u16, "input");
+        // Register 4: Voltage under load ×100 (e.g., 330 = 3.30V)
+        set_register!(set_input, 4, (sim_data.effective_voltage() * 100.0) as u16, "input");
         // Register 5: Current ×1000 (e.g., 100 = 0.100A)
         set_register!(set_input, 5, (sim_data.current * 1000.0) as u16, "input");
         // Register 6: Pressure ×10 (e.g., 10132 = 1013.2 hPa)
         set_register!(set_input, 6, (sim_data.pressure * 10.0) as u16, "input");
         // Register 7: Altitude ×10 (e.g., 100 = 10.0m)
         set_register!(set_input, 7, (sim_data.altitude * 10.0) as u16, "input");
+        // Register 8: Battery voltage ×100 (e.g., 420 = 4.20V)
+        set_register!(set_input, 8, (sim_data.battery_voltage * 100.0).round() as u16, "input");
+        // Register 9: Battery charge in percent
+        set_register!(set_input, 9, sim_data.battery_percent as u16, "input");
 
         // Also mirror to holding registers for read/write access
         set_register!(set_holding, 0, (sim_data.temperature * 10.0) as u16, "holding");
         set_register!(set_holding, 1, (sim_data.humidity * 10.0) as u16, "holding");
         set_register!(set_holding, 2, sim_data.rpm, "holding");
         set_register!(set_holding, 3, (sim_data.speed * 10.0) as u16, "holding");
-        set_register!(set_holding, 4, (sim_data.voltage * 100.0) as u16, "holding");
+        set_register!(set_holding, 4, (sim_data.effective_voltage() * 100.0) as u16, "holding");
         set_register!(set_holding, 5, (sim_data.current * 1000.0) as u16, "holding");
         set_register!(set_holding, 6, (sim_data.pressure * 10.0) as u16, "holding");
         set_register!(set_holding, 7, (sim_data.altitude * 10.0) as u16, "holding");
+        set_register!(set_holding, 8, (sim_data.battery_voltage * 100.0).round() as u16, "holding");
+        set_register!(set_holding, 9, sim_data.battery_percent as u16, "holding");
 
         // Coils (digital outputs) - some example states
         set_bit!(set_coil, 0, true, "coil"); // Device online
@@ -97,23 +131,37 @@ impl ModbusServer {
 
         // Check if addressed to us
         let unit_id = request[0];
-        if unit_id != SLAVE_ADDRESS && unit_id != 0 {
+        let is_broadcast = unit_id == BROADCAST_ADDRESS;
+        if unit_id != self.slave_address && !is_broadcast {
             log::debug!("Modbus: Not for us (address {})", unit_id);
             return None;
         }
 
+        // Broadcasts only make sense for writes (FC06/FC16)
+        if is_broadcast && !matches!(request[1], 0x06 | 0x10) {
+            log::debug!("Modbus: Ignoring broadcast FC{:02X}", request[1]);
+            return None;
+        }
+
         // Prepare response buffer
         let mut response = ModbusFrameBuf::new();
 
         // Process the frame
         let ctx = self.context.lock().unwrap();
         match rmodbus::server::process_frame(
-            unit_id,
+            self.slave_address,
             request,
             &*ctx,
             ModbusProto::Rtu,
             &mut response,
         ) {
+            Ok(_) if is_broadcast => {
+                log::debug!(
+                    "Modbus: Applied broadcast FC{:02X}, no response",
+                    request[1]
+                );
+                None
+            }
             Ok(_) => {
                 log::debug!(
                     "Modbus: Processed FC{:02X}, response {} bytes",
@@ -136,6 +184,42 @@ impl Default for ModbusServer {
     }
 }
 
+/// Pass a frame to every slave and return the reply of the addressed one
+///
+/// All slaves see the frame so that broadcast writes reach each of them.
+pub fn dispatch_frame(
+    slaves: &[ModbusServer],
+    request: &[u8],
+    sim_data: &SimulatedData,
+) -> Option<Vec<u8>> {
+    let mut response = None;
+    for slave in slaves {
+        if let Some(resp) = slave.process_frame(request, sim_data) {
+            response = Some(resp);
+        }
+    }
+    response
+}
+
+/// Make `slaves` answer on `addresses`, keeping the registers of slaves that remain
+pub fn sync_slaves(slaves: &mut Vec<ModbusServer>, addresses: &[u8]) {
+    if slaves
+        .iter()
+        .map(ModbusServer::address)
+        .eq(addresses.iter().copied())
+    {
+        return;
+    }
+    let mut previous = std::mem::take(slaves);
+    for &addr in addresses {
+        let slave = match previous.iter().position(|s| s.address() == addr) {
+            Some(i) => previous.swap_remove(i),
+            None => ModbusServer::with_address(addr),
+        };
+        slaves.push(slave);
+    }
+}
+
 /// Simple wrapper for stateless processing (creates server per call)
 /// For better performance, use ModbusServer instance directly
 pub fn process_modbus_rtu(data: &[u8], sim_data: &SimulatedData) -> Option<Vec<u8>> {
@@ -215,4 +299,74 @@ mod tests {
         assert_eq!(resp[1], 0x03); // Function code
         assert_eq!(resp[2], 8); // Byte count (4 registers × 2 bytes)
     }
+
+    #[test]
+    fn test_custom_address() {
+        let server = ModbusServer::with_address(17);
+        let sim_data = SimulatedData::default();
+
+        let request = build_read_holding_request(17, 0, 1);
+        let resp = server.process_frame(&request, &sim_data).unwrap();
+        assert_eq!(resp[0], 17);
+
+        let request = build_read_holding_request(SLAVE_ADDRESS, 0, 1);
+        assert!(server.process_frame(&request, &sim_data).is_none());
+    }
+
+    #[test]
+    fn test_broadcast_gets_no_response() {
+        let server = ModbusServer::new();
+        let sim_data = SimulatedData::default();
+
+        // FC03 broadcast is not a valid request
+        let request = build_read_holding_request(BROADCAST_ADDRESS, 0, 1);
+        assert!(server.process_frame(&request, &sim_data).is_none());
+
+        // FC06 broadcast: write single register 10 = 0x1234
+        let mut request = vec![BROADCAST_ADDRESS, 0x06, 0x00, 0x0A, 0x12, 0x34];
+        let crc = calculate_crc16(&request);
+        request.push((crc & 0xFF) as u8);
+        request.push((crc >> 8) as u8);
+        assert!(server.process_frame(&request, &sim_data).is_none());
+    }
+
+    #[test]
+    fn test_battery_registers() {
+        let server = ModbusServer::new();
+        let sim_data = SimulatedData {
+            battery_voltage: 3.87,
+            battery_percent: 63,
+            ..Default::default()
+        };
+
+        let request = build_read_holding_request(SLAVE_ADDRESS, 8, 2);
+        let resp = server.process_frame(&request, &sim_data).unwrap();
+        assert_eq!(resp[2], 4);
+        assert_eq!(u16::from_be_bytes([resp[3], resp[4]]), 387);
+        assert_eq!(u16::from_be_bytes([resp[5], resp[6]]), 63);
+    }
+
+    #[test]
+    fn test_dispatch_to_matching_slave() {
+        let slaves = vec![ModbusServer::new(), ModbusServer::with_address(5)];
+        let sim_data = SimulatedData::default();
+
+        let request = build_read_holding_request(5, 0, 1);
+        let resp = dispatch_frame(&slaves, &request, &sim_data).unwrap();
+        assert_eq!(resp[0], 5);
+
+        let request = build_read_holding_request(9, 0, 1);
+        assert!(dispatch_frame(&slaves, &request, &sim_data).is_none());
+    }
+
+    #[test]
+    fn test_sync_slaves_keeps_existing() {
+        let mut slaves = vec![ModbusServer::new(), ModbusServer::with_address(5)];
+        let kept = Arc::clone(&slaves[1].context);
+
+        sync_slaves(&mut slaves, &[5, 7]);
+        let addresses: Vec<u8> = slaves.iter().map(ModbusServer::address).collect();
+        assert_eq!(addresses, vec![5, 7]);
+        assert!(Arc::ptr_eq(&slaves[0].context, &kept));
+    }
 }
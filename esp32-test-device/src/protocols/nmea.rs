@@ -1,16 +1,19 @@
 //! NMEA GPS sentence generator
 
-use crate::types::SimulatedData;
+use crate::types::{GpsState, SimulatedData};
 
-/// Generate a GPGGA NMEA sentence from simulated data
-pub fn generate_nmea_sentence(sim_data: &SimulatedData) -> Vec<u8> {
-    let lat_deg = sim_data.latitude.abs() as u32;
-    let lat_min = (sim_data.latitude.abs() - lat_deg as f64) * 60.0;
-    let lat_dir = if sim_data.latitude >= 0.0 { 'N' } else { 'S' };
+/// Advance the simulated position and generate a GPGGA NMEA sentence for it
+pub fn generate_nmea_sentence(sim_data: &SimulatedData, gps: &mut GpsState) -> Vec<u8> {
+    gps.speed_mps = sim_data.speed as f64 / 3.6;
+    gps.step();
 
-    let lon_deg = sim_data.longitude.abs() as u32;
-    let lon_min = (sim_data.longitude.abs() - lon_deg as f64) * 60.0;
-    let lon_dir = if sim_data.longitude >= 0.0 { 'E' } else { 'W' };
+    let lat_deg = gps.current_lat.abs() as u32;
+    let lat_min = (gps.current_lat.abs() - lat_deg as f64) * 60.0;
+    let lat_dir = if gps.current_lat >= 0.0 { 'N' } else { 'S' };
+
+    let lon_deg = gps.current_lon.abs() as u32;
+    let lon_min = (gps.current_lon.abs() - lon_deg as f64) * 60.0;
+    let lon_dir = if gps.current_lon >= 0.0 { 'E' } else { 'W' };
 
     let sentence = format!(
         "$GPGGA,120000.00,{:02}{:07.4},{},{:03}{:07.4},{},1,08,0.9,{:.1},M,0.0,M,,",
@@ -20,3 +23,40 @@ pub fn generate_nmea_sentence(sim_data: &SimulatedData) -> Vec<u8> {
     let checksum: u8 = sentence[1..].bytes().fold(0, |acc, b| acc ^ b);
     format!("{}*{:02X}", sentence, checksum).into_bytes()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stationary_when_speed_zero() {
+        let sim_data = SimulatedData::default();
+        let mut gps = GpsState::new(37.7749, -122.4194);
+        generate_nmea_sentence(&sim_data, &mut gps);
+        assert_eq!(gps.current_lat, 37.7749);
+        assert_eq!(gps.current_lon, -122.4194);
+    }
+
+    #[test]
+    fn test_moves_along_heading() {
+        let sim_data = SimulatedData {
+            speed: 36.0, // 10 m/s
+            ..Default::default()
+        };
+        let mut gps = GpsState::new(0.0, 0.0);
+        gps.heading_deg = 90.0;
+        generate_nmea_sentence(&sim_data, &mut gps);
+        assert!(gps.current_lat.abs() < 1e-9);
+        assert!((gps.current_lon - 10.0 / 111_320.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_wraps_over_pole() {
+        let mut gps = GpsState::new(89.99999, 10.0);
+        gps.speed_mps = 10.0;
+        gps.step();
+        assert!(gps.current_lat <= 90.0);
+        assert!((gps.current_lon - -170.0).abs() < 1e-6);
+        assert_eq!(gps.heading_deg, 180.0);
+    }
+}
@@ -1,19 +1,103 @@
 //! SCPI instrument emulator
 
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
 use crate::types::SimulatedData;
 
+/// Measurement queries, reported by GET_PROTO
+pub const SUPPORTED_MEASUREMENTS: &[&str] =
+    &["MEAS:VOLT:DC?", "MEAS:CURR:DC?", "MEAS:TEMP?", "MEAS:BATT?"];
+
+/// Number of errors kept, the oldest is dropped when the queue is full
+pub const ERROR_QUEUE_CAPACITY: usize = 16;
+
+/// Response to error queries when the queue is empty
+const NO_ERROR: &str = "0,\"No error\"";
+
+/// SCPI error queue, saved to NVS so it survives power cycles
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ScpiErrorQueue {
+    entries: VecDeque<(i16, String)>,
+    /// Set when the queue changes, cleared by `take_changed`
+    #[serde(skip)]
+    changed: bool,
+}
+
+impl ScpiErrorQueue {
+    /// Append an error, dropping the oldest past `ERROR_QUEUE_CAPACITY`
+    pub fn push(&mut self, code: i16, message: &str) {
+        if self.entries.len() >= ERROR_QUEUE_CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((code, message.to_string()));
+        self.changed = true;
+    }
+
+    /// Remove and format the oldest error, as SYST:ERR? does
+    pub fn pop(&mut self) -> String {
+        match self.entries.pop_front() {
+            Some(entry) => {
+                self.changed = true;
+                format_error(&entry)
+            }
+            None => NO_ERROR.to_string(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.changed = true;
+    }
+
+    /// All queued errors separated by `;`, without removing them
+    pub fn log(&self) -> String {
+        if self.entries.is_empty() {
+            return NO_ERROR.to_string();
+        }
+        self.entries
+            .iter()
+            .map(format_error)
+            .collect::<Vec<_>>()
+            .join(";")
+    }
+
+    /// Whether the queue changed since the last call and needs saving
+    pub fn take_changed(&mut self) -> bool {
+        std::mem::take(&mut self.changed)
+    }
+}
+
+fn format_error((code, message): &(i16, String)) -> String {
+    format!("{},\"{}\"", code, message)
+}
+
 /// Process a SCPI command and return the response
-pub fn process_scpi_command(line: &str, sim_data: &SimulatedData) -> String {
+pub fn process_scpi_command(
+    line: &str,
+    sim_data: &SimulatedData,
+    errors: &mut ScpiErrorQueue,
+) -> String {
     let cmd = line.trim().to_uppercase();
 
     match cmd.as_str() {
         "*IDN?" => "ESP32-SCPI-SIM,SerialTester,001,1.0.0".to_string(),
         "*RST" => String::new(),
+        "*CLS" => {
+            errors.clear();
+            String::new()
+        }
         "*OPC?" => "1".to_string(),
-        ":SYST:ERR?" | "SYST:ERR?" => "0,\"No error\"".to_string(),
-        ":MEAS:VOLT:DC?" | "MEAS:VOLT:DC?" => format!("{:.6}", sim_data.voltage),
+        ":SYST:ERR?" | "SYST:ERR?" => errors.pop(),
+        // Non-standard, lets test scripts review errors without consuming them
+        ":SCPI:LOG?" | "SCPI:LOG?" => errors.log(),
+        ":MEAS:VOLT:DC?" | "MEAS:VOLT:DC?" => format!("{:.6}", sim_data.effective_voltage()),
         ":MEAS:CURR:DC?" | "MEAS:CURR:DC?" => format!("{:.6}", sim_data.current),
         ":MEAS:TEMP?" | "MEAS:TEMP?" => format!("{:.2}", sim_data.temperature),
-        _ => "ERROR".to_string(),
+        ":MEAS:BATT?" | "MEAS:BATT?" => sim_data.battery_percent.to_string(),
+        _ => {
+            errors.push(-113, "Undefined header");
+            "ERROR".to_string()
+        }
     }
 }
@@ -0,0 +1,76 @@
+//! PWM output on the LEDC peripheral for hardware-in-the-loop testing
+
+use esp_idf_svc::hal::{
+    gpio::OutputPin,
+    ledc::{config::TimerConfig, LedcDriver, LedcTimerDriver, CHANNEL0, TIMER0},
+    peripheral::Peripheral,
+    units::Hertz,
+};
+use esp_idf_svc::sys::{esp, EspError};
+use log::*;
+
+/// PWM frequency after boot, the duty cycle starts at 0 so the pin stays low
+pub const PWM_DEFAULT_FREQ_HZ: u32 = 1_000;
+
+/// Frequencies accepted by PWM_FREQ=<hz>, 8-bit resolution cannot divide below 10 Hz
+pub const PWM_FREQ_RANGE: std::ops::RangeInclusive<u32> = 10..=80_000;
+
+/// Simulated RPM for a PWM frequency, 10 Hz per RPM
+pub fn rpm_for_freq(freq_hz: u32) -> u16 {
+    (freq_hz / 10).min(u16::MAX as u32) as u16
+}
+
+/// LEDC channel 0 driven by timer 0
+pub struct PwmOutput {
+    channel: LedcDriver<'static>,
+    freq_hz: u32,
+    duty_pct: u8,
+}
+
+impl PwmOutput {
+    pub fn new(
+        timer: TIMER0,
+        channel: CHANNEL0,
+        pin: impl Peripheral<P = impl OutputPin> + 'static,
+    ) -> anyhow::Result<Self> {
+        let config = TimerConfig::default().frequency(Hertz(PWM_DEFAULT_FREQ_HZ));
+        let timer = LedcTimerDriver::new(timer, &config)?;
+        let mut channel = LedcDriver::new(channel, timer, pin)?;
+        channel.set_duty(0)?;
+        info!("PWM output initialized at {} Hz", PWM_DEFAULT_FREQ_HZ);
+        Ok(Self {
+            channel,
+            freq_hz: PWM_DEFAULT_FREQ_HZ,
+            duty_pct: 0,
+        })
+    }
+
+    pub fn freq_hz(&self) -> u32 {
+        self.freq_hz
+    }
+
+    pub fn duty_pct(&self) -> u8 {
+        self.duty_pct
+    }
+
+    /// Change the frequency, the duty cycle in percent is kept
+    pub fn set_frequency(&mut self, freq_hz: u32) -> Result<(), EspError> {
+        // The channel owns timer 0, so it is retuned through the IDF directly
+        esp!(unsafe {
+            esp_idf_svc::sys::ledc_set_freq(
+                esp_idf_svc::sys::ledc_mode_t_LEDC_LOW_SPEED_MODE,
+                esp_idf_svc::sys::ledc_timer_t_LEDC_TIMER_0,
+                freq_hz,
+            )
+        })?;
+        self.freq_hz = freq_hz;
+        Ok(())
+    }
+
+    pub fn set_duty(&mut self, duty_pct: u8) -> Result<(), EspError> {
+        let duty = self.channel.get_max_duty() * duty_pct.min(100) as u32 / 100;
+        self.channel.set_duty(duty)?;
+        self.duty_pct = duty_pct.min(100);
+        Ok(())
+    }
+}
@@ -1,7 +1,20 @@
 //! Serial I/O functions for USB-JTAG-Serial on ESP32-S3
 
+use esp_idf_svc::hal::{
+    delay::NON_BLOCK,
+    gpio::{AnyIOPin, InputPin, OutputPin},
+    peripheral::Peripheral,
+    uart::{config::Config, Uart, UartDriver},
+    units::Hertz,
+};
 use log::*;
 
+/// Baud rate of the auxiliary UART after boot
+pub const AUX_UART_DEFAULT_BAUD: u32 = 115_200;
+
+/// Baud rates accepted by UART_BAUD=<rate>
+pub const AUX_UART_BAUD_RANGE: std::ops::RangeInclusive<u32> = 300..=5_000_000;
+
 /// Initialize the USB Serial JTAG driver
 pub fn init_usb_serial() -> Result<(), i32> {
     unsafe {
@@ -56,3 +69,45 @@ pub fn read_bytes(buf: &mut [u8]) -> i32 {
         )
     }
 }
+
+/// Milliseconds elapsed since boot
+pub fn uptime_ms() -> u64 {
+    unsafe { (esp_idf_svc::sys::esp_timer_get_time() / 1000) as u64 }
+}
+
+/// Initialize the auxiliary UART used to test host-side baud rate switching
+///
+/// Unlike USB Serial JTAG it has a real baud rate, changed with UART_BAUD=<rate>.
+pub fn init_aux_uart<UART: Uart>(
+    uart: impl Peripheral<P = UART> + 'static,
+    tx: impl Peripheral<P = impl OutputPin> + 'static,
+    rx: impl Peripheral<P = impl InputPin> + 'static,
+) -> anyhow::Result<UartDriver<'static>> {
+    let config = Config::default().baudrate(Hertz(AUX_UART_DEFAULT_BAUD));
+    let driver = UartDriver::new(
+        uart,
+        tx,
+        rx,
+        Option::<AnyIOPin>::None,
+        Option::<AnyIOPin>::None,
+        &config,
+    )?;
+    info!(
+        "Auxiliary UART initialized at {} baud",
+        AUX_UART_DEFAULT_BAUD
+    );
+    Ok(driver)
+}
+
+/// Echo bytes received on the auxiliary UART back, so the host can check the link
+pub fn echo_aux_uart(uart: &UartDriver<'static>, buf: &mut [u8]) {
+    match uart.read(buf, NON_BLOCK) {
+        Ok(0) => {}
+        Ok(n) => {
+            if let Err(e) = uart.write(&buf[..n]) {
+                warn!("Auxiliary UART write failed: {:?}", e);
+            }
+        }
+        Err(e) => warn!("Auxiliary UART read failed: {:?}", e),
+    }
+}
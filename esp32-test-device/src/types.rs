@@ -1,8 +1,30 @@
 //! Type definitions for the Serial Protocol Tester
 
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 use std::sync::{Arc, Mutex};
 
+use crate::protocols::ScpiErrorQueue;
+
+/// Maximum number of messages kept in the serial log
+pub const LOG_CAPACITY: usize = 50;
+
+/// Battery voltage at 0% and 100% charge (single Li-ion cell)
+const BATTERY_EMPTY_VOLTS: f32 = 3.3;
+const BATTERY_FULL_VOLTS: f32 = 4.2;
+
+/// Supply voltage sag at full engine speed, and the floor it never drops below
+const VOLTAGE_SAG_AT_MAX_RPM: f32 = 0.3;
+const MIN_SUPPLY_VOLTS: f32 = 2.7;
+const MAX_RPM: f32 = 8000.0;
+
+/// Number of received messages between battery level updates
+const BATTERY_STEP_MESSAGES: u32 = 10;
+
+/// Battery percent lost or gained per update
+const BATTERY_DISCHARGE_STEP: f32 = 0.1;
+const BATTERY_CHARGE_STEP: f32 = 0.5;
+
 /// Protocol modes supported by the tester
 #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
@@ -16,6 +38,7 @@ pub enum ProtocolMode {
     Marlin,     // 3D printer Marlin emulator
     Elm327,     // OBD-II ELM327 emulator
     EscPos,     // ESC/POS printer emulator
+    I2cMaster,  // USB-to-I2C bridge
 }
 
 impl Default for ProtocolMode {
@@ -25,6 +48,22 @@ impl Default for ProtocolMode {
 }
 
 impl ProtocolMode {
+    /// Name accepted by MODE=<mode>
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Setup => "SETUP",
+            Self::Echo => "ECHO",
+            Self::AtCommand => "AT",
+            Self::ModbusRtu => "MODBUS",
+            Self::NmeaGps => "GPS",
+            Self::Scpi => "SCPI",
+            Self::Marlin => "MARLIN",
+            Self::Elm327 => "ELM327",
+            Self::EscPos => "ESCPOS",
+            Self::I2cMaster => "I2C",
+        }
+    }
+
     pub fn from_str(s: &str) -> Option<Self> {
         match s.to_uppercase().as_str() {
             "SETUP" => Some(Self::Setup),
@@ -36,6 +75,7 @@ impl ProtocolMode {
             "MARLIN" | "3DPRINTER" => Some(Self::Marlin),
             "ELM327" | "OBD" | "OBD2" => Some(Self::Elm327),
             "ESCPOS" | "PRINTER" => Some(Self::EscPos),
+            "I2C" | "I2C_MASTER" => Some(Self::I2cMaster),
             _ => None,
         }
     }
@@ -62,7 +102,7 @@ impl Default for SerialConfig {
 }
 
 /// Simulated sensor data for protocols that need it
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SimulatedData {
     pub temperature: f32,
     pub humidity: f32,
@@ -74,6 +114,85 @@ pub struct SimulatedData {
     pub rpm: u16,
     pub voltage: f32,
     pub current: f32,
+    #[serde(default)]
+    pub battery_voltage: f32,
+    #[serde(default)]
+    pub battery_percent: u8,
+    #[serde(default)]
+    pub charging: bool,
+}
+
+impl Default for SimulatedData {
+    fn default() -> Self {
+        Self {
+            temperature: 25.0,
+            humidity: 50.0,
+            pressure: 1013.25,
+            latitude: 37.7749,
+            longitude: -122.4194,
+            altitude: 10.0,
+            speed: 0.0,
+            rpm: 0,
+            voltage: 3.3,
+            current: 0.1,
+            battery_voltage: BATTERY_FULL_VOLTS,
+            battery_percent: 100,
+            charging: false,
+        }
+    }
+}
+
+impl SimulatedData {
+    /// Supply voltage under load: `voltage` sags as RPM rises, like a real power rail
+    pub fn effective_voltage(&self) -> f32 {
+        (self.voltage - self.rpm as f32 / MAX_RPM * VOLTAGE_SAG_AT_MAX_RPM).max(MIN_SUPPLY_VOLTS)
+    }
+}
+
+/// Meters per degree of latitude
+const METERS_PER_DEGREE: f64 = 111_320.0;
+
+/// Simulated GPS receiver position, advanced by dead reckoning
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GpsState {
+    pub current_lat: f64,
+    pub current_lon: f64,
+    pub speed_mps: f64,
+    /// Direction of travel, 0 = north, 90 = east
+    pub heading_deg: f32,
+}
+
+impl GpsState {
+    pub fn new(lat: f64, lon: f64) -> Self {
+        Self {
+            current_lat: lat,
+            current_lon: lon,
+            speed_mps: 0.0,
+            heading_deg: 0.0,
+        }
+    }
+
+    /// Move one second along the current heading
+    pub fn step(&mut self) {
+        let heading = (self.heading_deg as f64).to_radians();
+        // Avoid dividing by zero right at the poles
+        let lat_scale = self.current_lat.to_radians().cos().abs().max(1e-6);
+
+        self.current_lat += self.speed_mps * heading.cos() / METERS_PER_DEGREE;
+        self.current_lon += self.speed_mps * heading.sin() / (METERS_PER_DEGREE * lat_scale);
+
+        // Crossing a pole: come back down on the opposite meridian heading the other way
+        if self.current_lat > 90.0 {
+            self.current_lat = 180.0 - self.current_lat;
+            self.current_lon += 180.0;
+            self.heading_deg = (self.heading_deg + 180.0) % 360.0;
+        } else if self.current_lat < -90.0 {
+            self.current_lat = -180.0 - self.current_lat;
+            self.current_lon += 180.0;
+            self.heading_deg = (self.heading_deg + 180.0) % 360.0;
+        }
+        self.current_lon = (self.current_lon + 180.0).rem_euclid(360.0) - 180.0;
+    }
 }
 
 /// WiFi configuration stored in NVS
@@ -83,6 +202,32 @@ pub struct WifiConfig {
     pub password: String,
 }
 
+/// Access point found during a WiFi scan
+#[derive(Clone, Debug)]
+pub struct NetworkInfo {
+    pub ssid: String,
+    pub rssi: i8,
+    pub channel: u8,
+    pub auth_method: String,
+}
+
+/// Direction of a logged serial message
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum LogDirection {
+    Rx,
+    Tx,
+}
+
+/// Serial message shown in the web dashboard log
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LogEntry {
+    pub direction: LogDirection,
+    pub data: String,
+    /// Milliseconds since boot
+    pub timestamp: u64,
+}
+
 /// Device state shared between tasks
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct DeviceState {
@@ -95,6 +240,66 @@ pub struct DeviceState {
     pub wifi_ssid: String,
     pub wifi_connected: bool,
     pub wifi_ip: String,
+    /// Simulated GPS position for NMEA output
+    pub gps: GpsState,
+    /// Emulated Modbus RTU slave addresses, the first one is set with MODBUS_ADDR=<n>
+    pub modbus_slaves: Vec<u8>,
+    /// Exact battery charge in percent, `simulated_data.battery_percent` is this rounded
+    pub battery_level: f32,
+    /// SCPI error queue, persisted in NVS
+    #[serde(skip)]
+    pub scpi_errors: ScpiErrorQueue,
+    /// Recent serial traffic, served by `/api/log`
+    #[serde(skip)]
+    pub log: VecDeque<LogEntry>,
+    /// Number of messages ever pushed to `log`, used to find entries not yet streamed
+    #[serde(skip)]
+    pub log_total: u64,
+}
+
+impl DeviceState {
+    /// Modbus slave addresses as a comma separated list
+    pub fn modbus_slave_list(&self) -> String {
+        self.modbus_slaves
+            .iter()
+            .map(|addr| addr.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Set the battery charge, updating the reported percent and voltage
+    pub fn set_battery_level(&mut self, percent: f32) {
+        self.battery_level = percent.clamp(0.0, 100.0);
+        self.simulated_data.battery_percent = self.battery_level.round() as u8;
+        self.simulated_data.battery_voltage = BATTERY_EMPTY_VOLTS
+            + (BATTERY_FULL_VOLTS - BATTERY_EMPTY_VOLTS) * self.battery_level / 100.0;
+    }
+
+    /// Count a received message, charging or draining the battery every few messages
+    pub fn record_message(&mut self) {
+        self.message_count += 1;
+        if self.message_count % BATTERY_STEP_MESSAGES == 0 {
+            let step = if self.simulated_data.charging {
+                BATTERY_CHARGE_STEP
+            } else {
+                -BATTERY_DISCHARGE_STEP
+            };
+            self.set_battery_level(self.battery_level + step);
+        }
+    }
+
+    /// Append a message to the serial log, dropping the oldest past `LOG_CAPACITY`
+    pub fn push_log(&mut self, direction: LogDirection, data: String, timestamp: u64) {
+        if self.log.len() >= LOG_CAPACITY {
+            self.log.pop_front();
+        }
+        self.log.push_back(LogEntry {
+            direction,
+            data,
+            timestamp,
+        });
+        self.log_total += 1;
+    }
 }
 
 impl Default for DeviceState {
@@ -102,24 +307,19 @@ impl Default for DeviceState {
         Self {
             mode: ProtocolMode::Setup,
             serial_config: SerialConfig::default(),
-            simulated_data: SimulatedData {
-                temperature: 25.0,
-                humidity: 50.0,
-                pressure: 1013.25,
-                latitude: 37.7749,
-                longitude: -122.4194,
-                altitude: 10.0,
-                speed: 0.0,
-                rpm: 0,
-                voltage: 3.3,
-                current: 0.1,
-            },
+            simulated_data: SimulatedData::default(),
             message_count: 0,
             last_received: String::new(),
             last_sent: String::new(),
             wifi_ssid: String::new(),
             wifi_connected: false,
             wifi_ip: String::new(),
+            gps: GpsState::new(37.7749, -122.4194),
+            modbus_slaves: vec![crate::protocols::SLAVE_ADDRESS],
+            battery_level: 100.0,
+            scpi_errors: ScpiErrorQueue::default(),
+            log: VecDeque::with_capacity(LOG_CAPACITY),
+            log_total: 0,
         }
     }
 }
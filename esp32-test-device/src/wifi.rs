@@ -6,17 +6,23 @@ use esp_idf_svc::{
 };
 use log::*;
 
-use crate::types::WifiConfig;
+use crate::types::{NetworkInfo, WifiConfig};
 
 /// NVS namespace for storing WiFi credentials
 pub const NVS_NAMESPACE: &str = "wifi_config";
 const NVS_KEY_SSID: &str = "ssid";
 const NVS_KEY_PASS: &str = "password";
+/// Maximum number of networks reported by a scan
+const MAX_SCAN_RESULTS: usize = 20;
 
 /// WiFi manager that handles connection and credential storage
 pub struct WifiManager<'a> {
     pub wifi: BlockingWifi<EspWifi<'a>>,
     pub nvs: EspNvs<NvsDefault>,
+    /// Persisted simulated data, see `nvs_config`
+    pub sim_nvs: EspNvs<NvsDefault>,
+    /// Persisted SCPI error queue, see `nvs_config`
+    pub scpi_nvs: EspNvs<NvsDefault>,
     pub pending_ssid: String,
     pub pending_pass: String,
 }
@@ -100,3 +106,36 @@ pub fn try_connect_wifi(
     info!("WiFi connected! IP: {}", ip);
     Ok(ip)
 }
+
+/// Scan for nearby access points, strongest signal first
+pub fn scan_networks(wifi: &mut BlockingWifi<EspWifi>) -> Result<Vec<NetworkInfo>, String> {
+    if !wifi
+        .is_started()
+        .map_err(|e| format!("Status error: {:?}", e))?
+    {
+        // Scanning needs the station interface up, even before any credentials are set
+        wifi.set_configuration(&Configuration::Client(ClientConfiguration::default()))
+            .map_err(|e| format!("Config error: {:?}", e))?;
+        wifi.start().map_err(|e| format!("Start error: {:?}", e))?;
+    }
+
+    let mut networks: Vec<NetworkInfo> = wifi
+        .scan()
+        .map_err(|e| format!("Scan error: {:?}", e))?
+        .into_iter()
+        .map(|ap| NetworkInfo {
+            ssid: ap.ssid.to_string(),
+            rssi: ap.signal_strength,
+            channel: ap.channel,
+            auth_method: match ap.auth_method {
+                Some(method) => format!("{:?}", method),
+                None => "Unknown".to_string(),
+            },
+        })
+        .collect();
+
+    networks.sort_by(|a, b| b.rssi.cmp(&a.rssi));
+    networks.truncate(MAX_SCAN_RESULTS);
+    info!("WiFi scan found {} networks", networks.len());
+    Ok(networks)
+}
@@ -0,0 +1,244 @@
+//! Application-level settings persisted as TOML.
+
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use rootcause::{prelude::ResultExt, report, Report};
+use tauri::{AppHandle, Manager};
+use tracing::Instrument;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+use crate::constants::{app_config, serial};
+use crate::state::AppState;
+
+/// Settings loaded from `{app_local_data_dir}/config.toml`.
+///
+/// Every field falls back to its compiled default, so a partial file is valid.
+/// `max_open_ports`, `port_scan_interval_ms` and `default_timeout_ms` are
+/// picked up while the app is running, the rest only at startup.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    /// Maximum number of ports that may be open at the same time.
+    pub max_open_ports: usize,
+    /// Interval between background port scans in milliseconds.
+    pub port_scan_interval_ms: u64,
+    /// Port timeout used when `open_port` is called without one.
+    pub default_timeout_ms: u64,
+    /// Logs older than this many days are deleted at startup, `None` keeps everything.
+    pub log_retention_days: Option<u64>,
+    /// Maximum tracing level, e.g. `"info"` or `"trace"`.
+    pub tracing_level: String,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            max_open_ports: app_config::DEFAULT_MAX_OPEN_PORTS,
+            port_scan_interval_ms: serial::PORT_SCAN_INTERVAL_MS,
+            default_timeout_ms: app_config::DEFAULT_TIMEOUT_MS,
+            log_retention_days: None,
+            tracing_level: app_config::DEFAULT_TRACING_LEVEL.to_string(),
+        }
+    }
+}
+
+impl AppConfig {
+    /// Reads the config at `path`, returning the defaults if the file does not exist.
+    pub fn load(path: &Path) -> Result<Self, Report> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)
+            .context("read config file")
+            .attach(path.display().to_string())?;
+        let config: Self = toml::from_str(&content)
+            .context("parse config file")
+            .attach(path.display().to_string())?;
+        config
+            .validate()
+            .context("validate config file")
+            .attach(path.display().to_string())?;
+        Ok(config)
+    }
+
+    /// Rejects values the app cannot run with, such as a zero scan interval.
+    pub fn validate(&self) -> Result<(), Report> {
+        if self.max_open_ports == 0 {
+            return Err(report!("max_open_ports must be at least 1"));
+        }
+        if self.port_scan_interval_ms < app_config::MIN_PORT_SCAN_INTERVAL_MS {
+            return Err(report!(
+                "port_scan_interval_ms must be at least {}, got {}",
+                app_config::MIN_PORT_SCAN_INTERVAL_MS,
+                self.port_scan_interval_ms
+            ));
+        }
+        Ok(())
+    }
+
+    /// Writes the config to `path` as TOML.
+    pub fn save(&self, path: &Path) -> Result<(), Report> {
+        let content = toml::to_string_pretty(self).context("serialize config")?;
+        std::fs::write(path, content)
+            .context("write config file")
+            .attach(path.display().to_string())?;
+        Ok(())
+    }
+
+    /// Parses `tracing_level`, falling back to the compiled default.
+    pub fn tracing_level(&self) -> tracing::Level {
+        self.tracing_level.parse().unwrap_or_else(|_| {
+            app_config::DEFAULT_TRACING_LEVEL
+                .parse()
+                .unwrap_or(tracing::Level::TRACE)
+        })
+    }
+}
+
+/// Path of the config file inside the app local data directory.
+pub fn config_path(app_local_data_dir: &Path) -> PathBuf {
+    app_local_data_dir.join(app_config::FILE_NAME)
+}
+
+fn modified_at(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Spawns the task that reloads the config whenever the file changes on disk.
+///
+/// `AppState` must already be managed by the app when this is called.
+pub fn spawn_config_watch_task(app: AppHandle) -> tauri::async_runtime::JoinHandle<()> {
+    tauri::async_runtime::spawn(
+        async move {
+            let path = app.state::<AppState>().config_path.as_ref().clone();
+            let mut last_modified = modified_at(&path);
+            let mut interval =
+                tokio::time::interval(Duration::from_millis(app_config::WATCH_INTERVAL_MS));
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                let modified = modified_at(&path);
+                if modified == last_modified {
+                    continue;
+                }
+                last_modified = modified;
+                match AppConfig::load(&path) {
+                    Ok(new_config) => {
+                        let state = app.state::<AppState>();
+                        let mut current = state.config.write();
+                        if *current != new_config {
+                            tracing::info!("config reloaded: {:?}", new_config);
+                            *current = new_config;
+                        }
+                    }
+                    Err(err) => tracing::warn!("reload config failed: {}", err),
+                }
+            }
+        }
+        .instrument(tracing::debug_span!("config watch")),
+    )
+}
+
+/// Handle to the level filter of the global subscriber.
+pub type LogLevelHandle = reload::Handle<EnvFilter, Registry>;
+
+/// Filter passing events of this crate at `level` and above.
+pub fn log_filter(level: tracing::Level) -> EnvFilter {
+    EnvFilter::new(format!("serialport_api_lib={}", level))
+}
+
+/// Changes the tracing level until the app exits, without touching the config file.
+///
+/// Accepts `error`, `warn`, `info`, `debug` and `trace`.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn set_log_level(state: tauri::State<'_, AppState>, level: String) -> Result<(), String> {
+    let level: tracing::Level = level.parse().map_err(|_| {
+        tracing::error!("invalid log level: {}", level);
+        format!("invalid log level: {}", level)
+    })?;
+    let handle = state.log_level.as_ref().ok_or_else(|| {
+        tracing::error!("set log level before logging is set up");
+        "logging is not set up".to_string()
+    })?;
+    handle.reload(log_filter(level)).map_err(|err| {
+        tracing::error!("reload log level failed: {}", err);
+        err.to_string()
+    })?;
+    tracing::info!("log level set to {}", level);
+    Ok(())
+}
+
+/// Returns the current application config.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn get_app_config(state: tauri::State<'_, AppState>) -> Result<AppConfig, String> {
+    Ok(state.config.read().clone())
+}
+
+/// Persists `config` and applies it to the running app.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn set_app_config(
+    state: tauri::State<'_, AppState>,
+    config: AppConfig,
+) -> Result<(), String> {
+    config.validate().map_err(|err| {
+        tracing::error!("invalid config: {}", err);
+        err.to_string()
+    })?;
+    config.save(&state.config_path).map_err(|err| {
+        tracing::error!("save config failed: {}", err);
+        err.to_string()
+    })?;
+    let mut current = state.config.write();
+    tracing::info!("config updated: {:?}", config);
+    *current = config;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_config(name: &str, content: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("config-{}-{}.toml", name, std::process::id()));
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn partial_file_loads_with_defaults() {
+        let path = write_config("partial", "max_open_ports = 4\n");
+        let config = AppConfig::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            config,
+            AppConfig {
+                max_open_ports: 4,
+                ..AppConfig::default()
+            }
+        );
+    }
+
+    #[test]
+    fn out_of_range_values_are_rejected() {
+        let path = write_config("zero-interval", "port_scan_interval_ms = 0\n");
+        let loaded = AppConfig::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(loaded.is_err());
+
+        let no_ports = AppConfig {
+            max_open_ports: 0,
+            ..AppConfig::default()
+        };
+        assert!(no_ports.validate().is_err());
+        let fast_scan = AppConfig {
+            port_scan_interval_ms: app_config::MIN_PORT_SCAN_INTERVAL_MS - 1,
+            ..AppConfig::default()
+        };
+        assert!(fast_scan.validate().is_err());
+        assert!(AppConfig::default().validate().is_ok());
+    }
+}
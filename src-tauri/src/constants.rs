@@ -4,11 +4,108 @@
 
 /// Serial port related constants.
 pub mod serial {
-    /// Size of the buffer for reading serial port data.
+    /// Default size of the buffer for reading serial port data, set per port by `open_port`.
     pub const READ_BUFFER_SIZE: usize = 1024;
 
+    /// Largest read buffer `open_port` accepts.
+    pub const MAX_READ_BUFFER_SIZE: usize = 1024 * 1024;
+
     /// Interval for polling modem status in milliseconds.
     pub const STATUS_POLL_INTERVAL_MS: u64 = 1000;
+
+    /// Interval between background scans for available ports in milliseconds.
+    pub const PORT_SCAN_INTERVAL_MS: u64 = 2000;
+
+    /// How long a closed port missing from scans is listed as disconnected before removal, in milliseconds.
+    pub const DISCONNECTED_PORT_RETENTION_MS: u128 = 60_000;
+
+    /// Delay between deasserting DTR and closing a port opened with `dtr_on_close`, in milliseconds.
+    pub const DTR_ON_CLOSE_DELAY_MS: u64 = 100;
+
+    /// How long a cancelled port task keeps writing queued messages, in milliseconds.
+    pub const CANCEL_FLUSH_TIMEOUT_MS: u64 = 500;
+
+    /// How long shutdown waits for all open ports to close, in milliseconds.
+    pub const SHUTDOWN_TOTAL_TIMEOUT_MS: u64 = 5000;
+
+    /// Lowest baud rate accepted by `open_port`.
+    pub const MIN_BAUD_RATE: u32 = 50;
+
+    /// Highest baud rate accepted by `open_port`.
+    pub const MAX_BAUD_RATE: u32 = 12_000_000;
+
+    /// Break reports in a row without data after which the port is treated as failed.
+    pub const MAX_CONSECUTIVE_BREAKS: u32 = 16;
+
+    /// Transient read errors in a row without data after which the port is treated as failed.
+    pub const MAX_CONSECUTIVE_TRANSIENT_ERRORS: u32 = 10;
+
+    /// Recently written message IDs remembered by `write_port`.
+    pub const SENT_MESSAGE_ID_CAPACITY: usize = 1000;
+
+    /// How long `write_port` ignores a repeated message ID.
+    pub const SENT_MESSAGE_ID_TTL_MS: u64 = 60_000;
+
+    /// Bytes written between two write progress events.
+    pub const WRITE_PROGRESS_BYTES: usize = 1024;
+
+    /// Time between two write progress events in milliseconds.
+    pub const WRITE_PROGRESS_INTERVAL_MS: u64 = 100;
+
+    /// How long a write that arrived ahead of its sequence number waits for
+    /// the missing ones, in milliseconds.
+    pub const WRITE_SEQUENCE_TIMEOUT_MS: u64 = 100;
+
+    /// Line ending appended by `write_port_text`.
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+    pub enum LineEnding {
+        #[default]
+        None,
+        Cr,
+        Lf,
+        CrLf,
+    }
+
+    impl LineEnding {
+        pub fn as_bytes(&self) -> &'static [u8] {
+            match self {
+                Self::None => b"",
+                Self::Cr => b"\r",
+                Self::Lf => b"\n",
+                Self::CrLf => b"\r\n",
+            }
+        }
+    }
+}
+
+/// Compiled defaults for the application config file.
+pub mod app_config {
+    /// Name of the config file inside the app local data directory.
+    pub const FILE_NAME: &str = "config.toml";
+
+    /// Default maximum number of simultaneously open ports.
+    pub const DEFAULT_MAX_OPEN_PORTS: usize = 32;
+
+    /// Default port timeout in milliseconds.
+    pub const DEFAULT_TIMEOUT_MS: u64 = 1000;
+
+    /// Shortest accepted interval between background port scans in milliseconds.
+    pub const MIN_PORT_SCAN_INTERVAL_MS: u64 = 100;
+
+    /// Default maximum tracing level.
+    pub const DEFAULT_TRACING_LEVEL: &str = "trace";
+
+    /// Interval for checking the config file for changes in milliseconds.
+    pub const WATCH_INTERVAL_MS: u64 = 2000;
+}
+
+/// Log storage constants.
+pub mod storage {
+    /// Interval for flushing buffered read log entries in milliseconds.
+    pub const LOG_BATCH_FLUSH_INTERVAL_MS: u64 = 50;
+
+    /// Maximum rows per batch `INSERT`, keeps the bound parameters under SQLite's limit.
+    pub const LOG_BATCH_MAX_ROWS: usize = 500;
 }
 
 /// Channel capacity constants.
@@ -19,6 +116,16 @@ pub mod channels {
     /// Capacity of the event channel.
     pub const EVENT_CAPACITY: usize = 32;
 
+    /// Free slots in the event channel below which back-pressure is reported.
+    pub const EVENT_BACKPRESSURE_THRESHOLD: usize = EVENT_CAPACITY / 4;
+
+    /// How long the port task waits for room in a full event channel before
+    /// dropping the event, in milliseconds.
+    pub const EVENT_SEND_TIMEOUT_MS: u64 = 100;
+
     /// Capacity of the write notification channel.
     pub const WRITE_NOTIFY_CAPACITY: usize = 10;
+
+    /// Capacity of the per-session broadcast channel used for log tailing.
+    pub const LOG_TAIL_CAPACITY: usize = 256;
 }
@@ -5,6 +5,8 @@
 
 use thiserror::Error;
 
+use crate::events::port_error::PortErrorCode;
+
 /// Serial port related errors
 #[derive(Debug, Error)]
 pub enum SerialError {
@@ -43,6 +45,91 @@ pub enum SerialError {
     /// Operation timed out
     #[error("Operation timed out for port '{0}'")]
     Timeout(String),
+
+    /// The OS denied access to the port, e.g. the user is not in the `dialout` group
+    #[error("Permission denied for port '{0}'")]
+    PermissionDenied(String),
+
+    /// The connection to the device broke, usually because it was unplugged
+    #[error("Port '{0}' disconnected")]
+    Disconnected(String),
+}
+
+impl SerialError {
+    /// Classifies an error from opening `port`, keeping the I/O error kind
+    /// so callers can tell a permission problem from a hardware one.
+    pub fn from_open_error(port: &str, source: tokio_serial::Error) -> Self {
+        match source.kind {
+            tokio_serial::ErrorKind::Io(std::io::ErrorKind::PermissionDenied) => {
+                Self::PermissionDenied(port.to_string())
+            }
+            tokio_serial::ErrorKind::Io(std::io::ErrorKind::NotFound) => {
+                Self::PortNotFound(port.to_string())
+            }
+            tokio_serial::ErrorKind::Io(std::io::ErrorKind::TimedOut) => {
+                Self::Timeout(port.to_string())
+            }
+            tokio_serial::ErrorKind::Io(std::io::ErrorKind::BrokenPipe) => {
+                Self::Disconnected(port.to_string())
+            }
+            _ => Self::OpenFailed {
+                port: port.to_string(),
+                source,
+            },
+        }
+    }
+}
+
+/// Explanation of a failure to open a port, for display next to the raw error.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct OpenFailureDiagnostic {
+    pub error_code: PortErrorCode,
+    pub human_readable: String,
+    pub likely_cause: String,
+    pub suggested_fix: String,
+}
+
+/// Maps an error from opening `port_name` to a likely cause and fix.
+pub fn diagnose_open_failure(
+    port_name: &str,
+    error: &tokio_serial::Error,
+) -> OpenFailureDiagnostic {
+    // EBUSY has no I/O error kind of its own in serialport, only its description
+    let busy = error.description.to_lowercase().contains("busy");
+    let (error_code, human_readable, likely_cause, suggested_fix) = match error.kind {
+        tokio_serial::ErrorKind::Io(std::io::ErrorKind::PermissionDenied) => (
+            PortErrorCode::PermissionDenied,
+            format!("Permission denied opening {}", port_name),
+            "Your user is not allowed to access serial devices",
+            "Add your user to the dialout group (uucp on some distributions), then log in again",
+        ),
+        // Windows reports a port held by another process as NoDevice too
+        tokio_serial::ErrorKind::Io(std::io::ErrorKind::NotFound)
+        | tokio_serial::ErrorKind::NoDevice => (
+            PortErrorCode::NotFound,
+            format!("{} was not found", port_name),
+            "The device was disconnected, or is already open in another program",
+            "Reconnect the device and refresh the port list",
+        ),
+        _ if busy => (
+            PortErrorCode::Busy,
+            format!("{} is busy", port_name),
+            "Another process has the port open",
+            "Close other programs using the port (check screen, minicom or a serial monitor)",
+        ),
+        _ => (
+            PortErrorCode::OpenFailed,
+            format!("Failed to open {}", port_name),
+            "The device or driver rejected the port settings",
+            "Check the port settings, then reconnect the device",
+        ),
+    };
+    OpenFailureDiagnostic {
+        error_code,
+        human_readable,
+        likely_cause: likely_cause.to_string(),
+        suggested_fix: suggested_fix.to_string(),
+    }
 }
 
 /// Application-level errors that can be returned from Tauri commands
@@ -97,3 +184,87 @@ impl<T, E: std::fmt::Display> IntoCommandResult<T> for Result<T, E> {
         self.map_err(|e| e.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn open_failures_are_diagnosed_by_kind() {
+        let diagnose = |kind, description| {
+            diagnose_open_failure("COM1", &tokio_serial::Error::new(kind, description)).error_code
+        };
+        assert_eq!(
+            diagnose(
+                tokio_serial::ErrorKind::Io(std::io::ErrorKind::PermissionDenied),
+                "Permission denied"
+            ),
+            PortErrorCode::PermissionDenied
+        );
+        assert_eq!(
+            diagnose(
+                tokio_serial::ErrorKind::Io(std::io::ErrorKind::NotFound),
+                "No such file or directory"
+            ),
+            PortErrorCode::NotFound
+        );
+        assert_eq!(
+            diagnose(tokio_serial::ErrorKind::NoDevice, "Access is denied."),
+            PortErrorCode::NotFound
+        );
+        assert_eq!(
+            diagnose(tokio_serial::ErrorKind::Unknown, "Device or resource busy"),
+            PortErrorCode::Busy
+        );
+        assert_eq!(
+            diagnose(tokio_serial::ErrorKind::InvalidInput, "Invalid baud rate"),
+            PortErrorCode::OpenFailed
+        );
+    }
+
+    #[test]
+    fn open_errors_convert_through_app_error_to_string() {
+        let source = tokio_serial::Error::new(tokio_serial::ErrorKind::Unknown, "boom");
+        let err = SerialError::from_open_error("COM1", source);
+        assert!(matches!(err, SerialError::OpenFailed { .. }));
+        // `Serial` is transparent, the message is passed through unchanged
+        let message: String = AppError::from(err).into();
+        assert_eq!(message, "Failed to open port 'COM1': boom");
+
+        let source = tokio_serial::Error::new(
+            tokio_serial::ErrorKind::Io(std::io::ErrorKind::PermissionDenied),
+            "Permission denied",
+        );
+        let message: String = AppError::from(SerialError::from_open_error("COM1", source)).into();
+        assert_eq!(message, "Permission denied for port 'COM1'");
+    }
+
+    #[test]
+    fn app_error_from_impls_display() {
+        let io = std::io::Error::other("disk full");
+        assert_eq!(AppError::from(io).to_string(), "IO error: disk full");
+
+        let json = serde_json::from_str::<u32>("x").unwrap_err();
+        let expected = format!("Serialization error: {}", json);
+        assert_eq!(AppError::from(json).to_string(), expected);
+
+        // The context chain is kept, outermost first
+        let anyhow = anyhow::anyhow!("no such device").context("open COM1");
+        assert_eq!(
+            AppError::from(anyhow).to_string(),
+            "open COM1: no such device"
+        );
+    }
+
+    #[test]
+    fn into_cmd_result_keeps_message() {
+        let ok: Result<u32, SerialError> = Ok(1);
+        assert_eq!(ok.into_cmd_result(), Ok(1));
+
+        let err: Result<u32, SerialError> = Err(SerialError::PortNotOpen("COM1".to_string()));
+        assert_eq!(
+            err.into_cmd_result(),
+            Err("Port 'COM1' is not open".to_string())
+        );
+    }
+}
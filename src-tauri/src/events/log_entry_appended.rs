@@ -0,0 +1,24 @@
+//! Event emitted when a new log entry is stored for a tailed session.
+
+use crate::serial_mgr::helpers::timestamp_now_ms;
+use crate::serial_mgr::log::LogEntryDto;
+
+/// Payload for log entry appended events.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogEntryAppendedEvent {
+    /// The stored log entry
+    pub entry: LogEntryDto,
+    /// Timestamp when the event was emitted (milliseconds since Unix epoch)
+    pub timestamp_ms: u128,
+}
+
+impl LogEntryAppendedEvent {
+    /// Create a new LogEntryAppendedEvent with current timestamp.
+    pub fn new(entry: LogEntryDto) -> Self {
+        Self {
+            entry,
+            timestamp_ms: timestamp_now_ms(),
+        }
+    }
+}
@@ -1,8 +1,16 @@
 //! Event definitions for the serial port manager.
 
+pub mod log_entry_appended;
 pub mod message_read;
+pub mod port_backpressure;
 pub mod port_closed;
+pub mod port_error;
+pub mod port_list_changed;
 pub mod port_opened;
+pub mod port_reconnected;
+pub mod port_removed;
+pub mod port_write;
+pub mod storage_corrupted;
 
 /// Type-safe event name constants.
 ///
@@ -19,9 +27,67 @@ pub mod event_names {
 
     /// Emitted when an error occurs on a serial port.
     pub const PORT_ERROR: &str = "port_error";
+
+    /// Emitted when a port whose reads failed was reopened by its reconnect policy.
+    pub const PORT_RECONNECTED: &str = "port_reconnected";
+
+    /// Emitted when a closed port is no longer reported by the system.
+    pub const PORT_REMOVED: &str = "port_removed";
+
+    /// Emitted once per scan that removed ports, with all of their names.
+    pub const PORT_LIST_CHANGED: &str = "port_list_changed";
+
+    /// Emitted right before data is written to a serial port.
+    pub const PORT_WRITE_SENDING: &str = "port_write_sending";
+
+    /// Emitted every few bytes or milliseconds while a write is in progress.
+    pub const PORT_WRITE_PROGRESS: &str = "port_write_progress";
+
+    /// Emitted after a write to a serial port has finished.
+    pub const PORT_WRITE_RESULT: &str = "port_write_result";
+
+    /// Emitted when the queue of a port's read events is nearly full.
+    pub const PORT_BACKPRESSURE: &str = "port_backpressure";
+
+    /// Emitted after read events of a port were dropped because their queue stayed full.
+    pub const PORT_EVENTS_DROPPED: &str = "port_events_dropped";
+
+    /// Emitted when a log entry is stored for a session being tailed.
+    pub const LOG_ENTRY_APPENDED: &str = "log_entry_appended";
+
+    /// Emitted when the log database fails its integrity check at startup.
+    pub const STORAGE_CORRUPTED: &str = "storage_corrupted";
+
+    /// Per-port variant of `event`, e.g. `port_read_COM3`.
+    ///
+    /// Port events are emitted both on the shared name and on this one, so a
+    /// listener interested in a single port does not receive traffic for all
+    /// of them. Characters Tauri does not accept in event names are replaced
+    /// with `_`.
+    pub fn for_port(event: &str, port_name: &str) -> String {
+        let port_name: String = port_name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || matches!(c, '-' | '/' | ':' | '_') {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        format!("{}_{}", event, port_name)
+    }
 }
 
 // Re-export event types for convenience
+pub use log_entry_appended::LogEntryAppendedEvent;
 pub use message_read::PortReadEvent;
+pub use port_backpressure::{PortBackpressureEvent, PortEventsDroppedEvent};
 pub use port_closed::PortClosedEvent;
+pub use port_error::PortErrorEvent;
+pub use port_list_changed::PortListChangedEvent;
 pub use port_opened::PortOpenedEvent;
+pub use port_reconnected::PortReconnectedEvent;
+pub use port_removed::PortRemovedEvent;
+pub use port_write::{PortWriteProgressEvent, PortWriteResultEvent, PortWriteSendingEvent};
+pub use storage_corrupted::StorageCorruptedEvent;
@@ -0,0 +1,52 @@
+//! Events emitted when the consumer of a port's read events falls behind.
+
+use crate::serial_mgr::helpers::timestamp_now_ms;
+
+/// Payload emitted when a port's event queue is nearly full.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortBackpressureEvent {
+    /// Name of the port whose events are queued
+    pub port_name: String,
+    /// Number of events waiting to be consumed
+    pub queue_depth: usize,
+    /// Size of the event queue
+    pub capacity: usize,
+    /// Timestamp when the queue crossed the threshold (milliseconds since Unix epoch)
+    pub timestamp_ms: u128,
+}
+
+impl PortBackpressureEvent {
+    /// Create a new PortBackpressureEvent with current timestamp.
+    pub fn new(port_name: String, queue_depth: usize, capacity: usize) -> Self {
+        Self {
+            port_name,
+            queue_depth,
+            capacity,
+            timestamp_ms: timestamp_now_ms(),
+        }
+    }
+}
+
+/// Payload emitted after read events were dropped because the queue stayed full.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortEventsDroppedEvent {
+    /// Name of the port whose events were dropped
+    pub port_name: String,
+    /// Number of events dropped since the previous report
+    pub dropped_count: u64,
+    /// Timestamp when the drop was reported (milliseconds since Unix epoch)
+    pub timestamp_ms: u128,
+}
+
+impl PortEventsDroppedEvent {
+    /// Create a new PortEventsDroppedEvent with current timestamp.
+    pub fn new(port_name: String, dropped_count: u64) -> Self {
+        Self {
+            port_name,
+            dropped_count,
+            timestamp_ms: timestamp_now_ms(),
+        }
+    }
+}
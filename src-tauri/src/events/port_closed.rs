@@ -48,4 +48,13 @@ impl PortClosedEvent {
             timestamp_ms: timestamp_now_ms(),
         }
     }
+
+    /// Create a close event for a port whose connection was lost.
+    pub fn connection_lost(port_name: String) -> Self {
+        Self {
+            port_name,
+            reason: PortCloseReason::ConnectionLost.to_string(),
+            timestamp_ms: timestamp_now_ms(),
+        }
+    }
 }
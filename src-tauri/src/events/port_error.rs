@@ -0,0 +1,77 @@
+//! Event emitted when a serial port reports an error.
+
+use crate::serial_mgr::helpers::timestamp_now_ms;
+
+/// Kind of error reported on a port.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum PortErrorCode {
+    /// I/O error, the port task has stopped
+    Io,
+    /// Break condition on the line, reading continues
+    Break,
+    /// The OS denied access when opening the port
+    PermissionDenied,
+    /// The port does not exist when opening it
+    NotFound,
+    /// Another process has the port open
+    Busy,
+    /// Opening the port failed for another reason
+    OpenFailed,
+    /// Read data could not be transformed and was forwarded raw, reading continues
+    ReadTransform,
+}
+
+/// Payload for port error events.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortErrorEvent {
+    /// Name of the port the error occurred on
+    pub port_name: String,
+    /// Human readable description
+    pub error: String,
+    pub code: PortErrorCode,
+    /// Whether the port is still open after this error
+    pub recoverable: bool,
+    /// Length of a break, if the platform reports it
+    pub duration_hint_ms: Option<u64>,
+    /// Timestamp of the error (milliseconds since Unix epoch)
+    pub timestamp_ms: u128,
+}
+
+impl PortErrorEvent {
+    /// Create an event for an I/O error that stopped the port task.
+    pub fn io(port_name: String, error: &std::io::Error) -> Self {
+        Self {
+            port_name,
+            error: error.to_string(),
+            code: PortErrorCode::Io,
+            recoverable: false,
+            duration_hint_ms: None,
+            timestamp_ms: timestamp_now_ms(),
+        }
+    }
+
+    /// Create an event for a break condition on the line.
+    pub fn break_condition(port_name: String, duration_hint_ms: Option<u64>) -> Self {
+        Self {
+            port_name,
+            error: "break condition detected".to_string(),
+            code: PortErrorCode::Break,
+            recoverable: true,
+            duration_hint_ms,
+            timestamp_ms: timestamp_now_ms(),
+        }
+    }
+
+    /// Create an event for read data the port's read transform rejected.
+    pub fn read_transform(port_name: String, error: String) -> Self {
+        Self {
+            port_name,
+            error,
+            code: PortErrorCode::ReadTransform,
+            recoverable: true,
+            duration_hint_ms: None,
+            timestamp_ms: timestamp_now_ms(),
+        }
+    }
+}
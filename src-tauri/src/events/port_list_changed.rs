@@ -0,0 +1,23 @@
+//! Event emitted when ports are dropped from the port list.
+
+use crate::serial_mgr::helpers::timestamp_now_ms;
+
+/// Payload for port list changed events.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortListChangedEvent {
+    /// Names of the ports removed by the scan
+    pub removed: Vec<String>,
+    /// Timestamp of the scan (milliseconds since Unix epoch)
+    pub timestamp_ms: u128,
+}
+
+impl PortListChangedEvent {
+    /// Create a new PortListChangedEvent with current timestamp.
+    pub fn new(removed: Vec<String>) -> Self {
+        Self {
+            removed,
+            timestamp_ms: timestamp_now_ms(),
+        }
+    }
+}
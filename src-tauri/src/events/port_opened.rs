@@ -8,15 +8,18 @@ use crate::serial_mgr::helpers::timestamp_now_ms;
 pub struct PortOpenedEvent {
     /// Name of the port that was opened
     pub port_name: String,
+    /// Session the port logs under, as returned by `open_port`
+    pub session_id: String,
     /// Timestamp when port was opened (milliseconds since Unix epoch)
     pub timestamp_ms: u128,
 }
 
 impl PortOpenedEvent {
     /// Create a new PortOpenedEvent with current timestamp.
-    pub fn new(port_name: String) -> Self {
+    pub fn new(port_name: String, session_id: String) -> Self {
         Self {
             port_name,
+            session_id,
             timestamp_ms: timestamp_now_ms(),
         }
     }
@@ -0,0 +1,26 @@
+//! Event emitted when a port whose reads failed was reopened.
+
+use crate::serial_mgr::helpers::timestamp_now_ms;
+
+/// Payload for port reconnected events.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortReconnectedEvent {
+    /// Name of the port that was reopened
+    pub port_name: String,
+    /// Attempt that succeeded, counted from 1
+    pub attempts: u32,
+    /// Timestamp when the port was reopened (milliseconds since Unix epoch)
+    pub timestamp_ms: u128,
+}
+
+impl PortReconnectedEvent {
+    /// Create a new PortReconnectedEvent with current timestamp.
+    pub fn new(port_name: String, attempts: u32) -> Self {
+        Self {
+            port_name,
+            attempts,
+            timestamp_ms: timestamp_now_ms(),
+        }
+    }
+}
@@ -0,0 +1,23 @@
+//! Event emitted when a serial port disappears from the system.
+
+use crate::serial_mgr::helpers::timestamp_now_ms;
+
+/// Payload for port removed events.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortRemovedEvent {
+    /// Name of the port that was removed
+    pub port_name: String,
+    /// Timestamp when the removal was detected (milliseconds since Unix epoch)
+    pub timestamp_ms: u128,
+}
+
+impl PortRemovedEvent {
+    /// Create a new PortRemovedEvent with current timestamp.
+    pub fn new(port_name: String) -> Self {
+        Self {
+            port_name,
+            timestamp_ms: timestamp_now_ms(),
+        }
+    }
+}
@@ -0,0 +1,113 @@
+//! Events emitted around writes to a serial port.
+
+use crate::serial_mgr::helpers::timestamp_now_ms;
+
+/// Payload emitted right before data is written to a port.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortWriteSendingEvent {
+    /// Name of the port being written to
+    pub port_name: String,
+    /// ID of the message supplied by the frontend
+    pub message_id: String,
+    /// Number of bytes about to be written
+    pub data_len: usize,
+    /// Timestamp when the write started (milliseconds since Unix epoch)
+    pub timestamp_ms: u128,
+}
+
+impl PortWriteSendingEvent {
+    /// Create a new PortWriteSendingEvent with current timestamp.
+    pub fn new(port_name: String, message_id: String, data_len: usize) -> Self {
+        Self {
+            port_name,
+            message_id,
+            data_len,
+            timestamp_ms: timestamp_now_ms(),
+        }
+    }
+}
+
+/// Payload emitted while a write to a port is in progress.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortWriteProgressEvent {
+    /// Name of the port being written to
+    pub port_name: String,
+    /// ID of the message supplied by the frontend
+    pub message_id: String,
+    /// Number of bytes accepted by the port so far
+    pub bytes_written_so_far: usize,
+    /// Number of bytes in the whole write
+    pub total_bytes: usize,
+    /// Share of the write done, from 0 to 100
+    pub percent_complete: f32,
+    /// Time left at the average speed of this write, `None` before it can be measured
+    pub estimated_remaining_ms: Option<u64>,
+    /// Timestamp of this progress report (milliseconds since Unix epoch)
+    pub timestamp_ms: u128,
+}
+
+impl PortWriteProgressEvent {
+    /// Create a new PortWriteProgressEvent with current timestamp.
+    pub fn new(
+        port_name: String,
+        message_id: String,
+        bytes_written_so_far: usize,
+        total_bytes: usize,
+        estimated_remaining_ms: Option<u64>,
+    ) -> Self {
+        let percent_complete = if total_bytes == 0 {
+            100.0
+        } else {
+            bytes_written_so_far as f32 * 100.0 / total_bytes as f32
+        };
+        Self {
+            port_name,
+            message_id,
+            bytes_written_so_far,
+            total_bytes,
+            percent_complete,
+            estimated_remaining_ms,
+            timestamp_ms: timestamp_now_ms(),
+        }
+    }
+}
+
+/// Payload emitted once a write to a port has finished.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortWriteResultEvent {
+    /// Name of the port that was written to
+    pub port_name: String,
+    /// ID of the message supplied by the frontend
+    pub message_id: String,
+    /// Whether all bytes were written
+    pub success: bool,
+    /// Time spent writing in milliseconds
+    pub latency_ms: f64,
+    /// Number of bytes accepted by the port
+    pub actual_bytes_written: usize,
+    /// Timestamp when the write finished (milliseconds since Unix epoch)
+    pub timestamp_ms: u128,
+}
+
+impl PortWriteResultEvent {
+    /// Create a new PortWriteResultEvent with current timestamp.
+    pub fn new(
+        port_name: String,
+        message_id: String,
+        success: bool,
+        latency_ms: f64,
+        actual_bytes_written: usize,
+    ) -> Self {
+        Self {
+            port_name,
+            message_id,
+            success,
+            latency_ms,
+            actual_bytes_written,
+            timestamp_ms: timestamp_now_ms(),
+        }
+    }
+}
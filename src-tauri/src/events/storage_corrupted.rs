@@ -0,0 +1,23 @@
+//! Event emitted when the log database fails its integrity check.
+
+use crate::serial_mgr::helpers::timestamp_now_ms;
+
+/// Payload for storage corrupted events.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageCorruptedEvent {
+    /// Problems reported by SQLite
+    pub issues: Vec<String>,
+    /// Timestamp when the check finished (milliseconds since Unix epoch)
+    pub timestamp_ms: u128,
+}
+
+impl StorageCorruptedEvent {
+    /// Create a new StorageCorruptedEvent with current timestamp.
+    pub fn new(issues: Vec<String>) -> Self {
+        Self {
+            issues,
+            timestamp_ms: timestamp_now_ms(),
+        }
+    }
+}
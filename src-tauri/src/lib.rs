@@ -1,6 +1,7 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod config;
 mod constants;
 pub mod error;
 mod events;
@@ -9,27 +10,50 @@ mod serial_mgr;
 mod state;
 mod util;
 
-use dashmap::DashMap;
+use config::{
+    get_app_config, log_filter, set_app_config, set_log_level, spawn_config_watch_task, AppConfig,
+    LogLevelHandle,
+};
 use serial_mgr::{
     close_port::close_port,
     execute_saved_command::execute_saved_command,
-    log::{debug, error, get_logs, info, log, warn},
+    helpers::{close_all_ports_blocking, timestamp_now_ms},
+    log::{
+        check_storage_integrity, check_storage_on_startup, debug, delete_session_logs, error,
+        get_audit_log, get_log_by_message_id, get_logs, get_logs_by_time_range,
+        get_session_summary, info, log, prune_logs_before, query_logs, repair_storage,
+        start_log_tail, stop_log_tail, warn,
+    },
+    modem_status::get_modem_status,
     open_port::open_port,
+    port_capabilities::get_port_capabilities,
+    port_permissions::check_port_permissions,
+    port_task_metrics::get_port_task_metrics,
+    prometheus::get_metrics_text,
+    server_time::get_server_timestamp,
+    session::rotate_session,
     storage::Storage,
-    update_ports::get_all_port_info,
-    write_port::{write_data_terminal_ready, write_port, write_request_to_send},
+    update_ports::{get_all_port_info, get_open_ports, spawn_port_scan_task},
+    write_port::{
+        clear_message_id_cache, set_write_transform, write_data_terminal_ready, write_port,
+        write_port_chunked, write_port_text, write_request_to_send,
+    },
 };
 use tauri::{self, Manager, WebviewUrl, WebviewWindowBuilder};
 use tauri_plugin_fs::FsExt;
 use time::macros::{format_description, offset};
 #[cfg(all(desktop, not(debug_assertions)))]
 use tracing_appender::rolling::Rotation;
-use tracing_subscriber::fmt::time::OffsetTime;
+use tracing_subscriber::{fmt::time::OffsetTime, prelude::*, reload};
 
+#[cfg(debug_assertions)]
+use crate::state::get_debug_dump;
 use crate::state::AppState;
+use crate::util::unwrap_lock;
 
+/// Installs the global subscriber, returning the handle to change its level at runtime.
 #[allow(unused_variables)]
-pub fn setup_logging(app: &tauri::App) {
+pub fn setup_logging(app: &tauri::App, max_level: tracing::Level) -> LogLevelHandle {
     let fmt = if cfg!(debug_assertions) {
         format_description!("[hour]:[minute]:[second].[subsecond digits:3]")
     } else {
@@ -53,20 +77,21 @@ pub fn setup_logging(app: &tauri::App) {
     let writer = std::io::stderr;
 
     let timer = OffsetTime::new(offset!(+8), fmt);
-    let builder = tracing_subscriber::fmt()
-        .with_max_level(tracing::Level::TRACE)
+    let layer = tracing_subscriber::fmt::layer()
         .with_file(true)
         .with_line_number(true)
         .with_target(false)
-        .with_env_filter("serialport_api_lib")
         .with_timer(timer)
         .with_writer(writer);
+    let (filter, handle) = reload::Layer::new(log_filter(max_level));
+    let registry = tracing_subscriber::registry().with(filter);
 
     if cfg!(debug_assertions) {
-        builder.init();
+        registry.with(layer).init();
     } else {
-        builder.json().init();
+        registry.with(layer.json()).init();
     }
+    handle
 }
 
 pub fn run() {
@@ -74,28 +99,65 @@ pub fn run() {
     builder
         .invoke_handler(tauri::generate_handler![
             get_all_port_info,
+            get_open_ports,
             open_port,
             close_port,
             execute_saved_command,
             write_data_terminal_ready,
             write_request_to_send,
             write_port,
+            write_port_text,
+            write_port_chunked,
+            clear_message_id_cache,
+            set_write_transform,
             debug,
             info,
             log,
             warn,
             error,
-            get_logs
+            get_logs,
+            get_logs_by_time_range,
+            get_session_summary,
+            delete_session_logs,
+            prune_logs_before,
+            get_audit_log,
+            get_log_by_message_id,
+            query_logs,
+            start_log_tail,
+            stop_log_tail,
+            check_storage_integrity,
+            repair_storage,
+            get_app_config,
+            set_app_config,
+            get_port_capabilities,
+            check_port_permissions,
+            get_port_task_metrics,
+            get_modem_status,
+            get_metrics_text,
+            get_server_timestamp,
+            #[cfg(debug_assertions)]
+            get_debug_dump,
+            set_log_level,
+            rotate_session
         ])
         .plugin(tauri_plugin_store::Builder::default().build())
         .plugin(tauri_plugin_fs::init())
         .setup(|app| {
-            setup_logging(app);
-            let scope = app.fs_scope();
             let app_local_data_dir = app
                 .path()
                 .app_local_data_dir()
                 .map_err(|e| format!("Failed to resolve app local data directory: {}", e))?;
+            // Loaded before logging so the configured level applies from the start
+            let config_path = config::config_path(&app_local_data_dir);
+            let (app_config, config_err) = match AppConfig::load(&config_path) {
+                Ok(app_config) => (app_config, None),
+                Err(err) => (AppConfig::default(), Some(err)),
+            };
+            let log_level = setup_logging(app, app_config.tracing_level());
+            if let Some(err) = config_err {
+                tracing::warn!("load config failed, using defaults: {}", err);
+            }
+            let scope = app.fs_scope();
             tracing::trace!(
                 "app local data dir: {}",
                 app_local_data_dir.to_string_lossy()
@@ -107,27 +169,52 @@ pub fn run() {
             let _ = scope.allow_directory(&app_local_data_dir, true);
 
             let db_path = app_local_data_dir.join("serial_logs.db");
+            let retention_days = app_config.log_retention_days;
             let (tx, rx) = std::sync::mpsc::channel();
             tauri::async_runtime::spawn(async move {
-                let storage = match Storage::new(&db_path).await {
-                    Ok(s) => s,
-                    Err(e) => {
-                        tracing::error!("Failed to initialize storage: {}", e);
-                        Storage::new_in_memory().await
+                let storage = Storage::new_app_storage(&db_path).await;
+                if let Some(days) = retention_days {
+                    let retention =
+                        std::time::Duration::from_secs(days.saturating_mul(24 * 60 * 60));
+                    let cutoff_ms =
+                        timestamp_now_ms().saturating_sub(retention.as_millis()) as i64;
+                    match storage.delete_before(cutoff_ms).await {
+                        Ok(removed) => {
+                            tracing::info!("removed {} logs older than {} days", removed, days)
+                        }
+                        Err(err) => tracing::warn!("log retention cleanup failed: {}", err),
                     }
-                };
+                }
                 let _ = tx.send(storage);
             });
             let storage = rx
                 .recv()
                 .map_err(|e| format!("Failed to receive storage from async task: {}", e))?;
             let app_state = AppState {
-                ports: DashMap::new(),
-                port_handles: DashMap::new(),
+                ports: Default::default(),
+                port_handles: Default::default(),
                 storage,
+                log_tails: Default::default(),
+                port_scan_task: Default::default(),
+                config: std::sync::Arc::new(parking_lot::RwLock::new(app_config)),
+                config_path: std::sync::Arc::new(config_path),
+                config_watch_task: Default::default(),
+                log_level: Some(log_level),
+                sent_message_ids: Default::default(),
             };
             app.manage(app_state);
 
+            let scan_task = spawn_port_scan_task(app.handle().clone());
+            *unwrap_lock(
+                app.state::<AppState>().port_scan_task.lock(),
+                "port scan task",
+            ) = Some(scan_task);
+            let config_watch_task = spawn_config_watch_task(app.handle().clone());
+            *unwrap_lock(
+                app.state::<AppState>().config_watch_task.lock(),
+                "config watch task",
+            ) = Some(config_watch_task);
+
             // Create main window with initialization script for text selection styling
             // This injects CSS before the page loads to work around WKWebView ::selection limitations
             let init_script = r#"
@@ -183,14 +270,47 @@ pub fn run() {
                 .initialization_script(init_script)
                 .build()?;
 
+            // After the window exists, so the frontend can receive `storage_corrupted`
+            tauri::async_runtime::spawn(check_storage_on_startup(app.handle().clone()));
+
             Ok(())
         })
         .build(tauri::generate_context!())
         .expect("error whiling running tauri application")
-        .run(|_app, event| match event {
+        .run(|app, event| match event {
             tauri::RunEvent::Ready => {
                 tracing::info!("App is running!");
             }
+            tauri::RunEvent::ExitRequested { .. } => {
+                // Release the ports while the runtime is still alive
+                if let Some(state) = app.try_state::<AppState>() {
+                    // A port that never answers must not keep the app from exiting
+                    let timeout =
+                        std::time::Duration::from_millis(constants::serial::SHUTDOWN_TOTAL_TIMEOUT_MS);
+                    if !close_all_ports_blocking(&state, timeout) {
+                        tracing::error!(
+                            "closing ports on shutdown timed out after {:?}, exiting anyway",
+                            timeout
+                        );
+                    }
+                }
+            }
+            tauri::RunEvent::Exit => {
+                if let Some(state) = app.try_state::<AppState>() {
+                    if let Some(task) =
+                        unwrap_lock(state.port_scan_task.lock(), "port scan task").take()
+                    {
+                        task.abort();
+                        tracing::info!("port scan task stopped");
+                    }
+                    if let Some(task) =
+                        unwrap_lock(state.config_watch_task.lock(), "config watch task").take()
+                    {
+                        task.abort();
+                        tracing::info!("config watch task stopped");
+                    }
+                }
+            }
             _ => {}
         });
 }
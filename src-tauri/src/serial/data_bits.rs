@@ -23,9 +23,28 @@ impl FromStr for DataBits {
     }
 }
 
+impl TryFrom<&str> for DataBits {
+    type Error = Report;
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl TryFrom<u8> for DataBits {
+    type Error = Report;
+    fn try_from(bits: u8) -> Result<Self, Self::Error> {
+        match bits {
+            5 => Ok(Self::Five),
+            6 => Ok(Self::Six),
+            7 => Ok(Self::Seven),
+            8 => Ok(Self::Eight),
+            _ => Err(report!("unsupported number of data bits: {}", bits)),
+        }
+    }
+}
+
 impl fmt::Display for DataBits {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str("DataBits: ")?;
         let str = match self {
             Self::Five => "five",
             Self::Six => "six",
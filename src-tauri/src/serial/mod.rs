@@ -1,9 +1,17 @@
 pub mod data_bits;
 pub mod flow_control;
 pub mod parity;
+pub mod port_capabilities;
+pub mod port_permissions;
 pub mod port_type;
+pub mod read_framing;
+pub mod read_strategy;
+pub mod read_transform;
+pub mod reconnect_policy;
 pub mod stop_bits;
 pub mod usb_port_info;
+pub mod write_mode;
+pub mod write_transform;
 
 impl From<data_bits::DataBits> for tokio_serial::DataBits {
     fn from(value: data_bits::DataBits) -> Self {
@@ -93,6 +101,7 @@ impl From<usb_port_info::UsbPortInfo> for tokio_serial::UsbPortInfo {
             serial_number: value.serial_number,
             manufacturer: value.manufacturer,
             product: value.product,
+            interface: value.interface,
         }
     }
 }
@@ -105,7 +114,8 @@ impl From<tokio_serial::UsbPortInfo> for usb_port_info::UsbPortInfo {
             serial_number: value.serial_number,
             manufacturer: value.manufacturer,
             product: value.product,
-            interface: None,
+            interface: value.interface,
+            hub_port: None,
         }
     }
 }
@@ -122,3 +132,137 @@ impl From<tokio_serial::SerialPortType> for port_type::PortType {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        data_bits::DataBits, flow_control::FlowControl, parity::Parity, stop_bits::StopBits,
+    };
+    use proptest::prelude::*;
+    use proptest::test_runner::TestCaseError;
+    use std::{fmt::Display, str::FromStr};
+
+    /// Display output parses back to the same value, in lower and capitalized form.
+    fn assert_round_trip<T>(values: &[T])
+    where
+        T: FromStr<Err = rootcause::Report> + Display + PartialEq + std::fmt::Debug + Copy,
+    {
+        for &value in values {
+            let lower = value.to_string();
+            assert_eq!(lower.parse::<T>().unwrap(), value);
+            let capitalized = lower[..1].to_uppercase() + &lower[1..];
+            assert_eq!(capitalized.parse::<T>().unwrap(), value);
+        }
+        assert!("".parse::<T>().is_err());
+        assert!("bogus".parse::<T>().is_err());
+    }
+
+    #[test]
+    fn from_str_round_trips() {
+        assert_round_trip(&[
+            DataBits::Five,
+            DataBits::Six,
+            DataBits::Seven,
+            DataBits::Eight,
+        ]);
+        assert_round_trip(&[
+            FlowControl::Hardware,
+            FlowControl::Software,
+            FlowControl::None,
+        ]);
+        assert_round_trip(&[Parity::Odd, Parity::Even, Parity::None]);
+        assert_round_trip(&[StopBits::One, StopBits::Two]);
+    }
+
+    const DATA_BITS: [DataBits; 4] = [
+        DataBits::Five,
+        DataBits::Six,
+        DataBits::Seven,
+        DataBits::Eight,
+    ];
+    const FLOW_CONTROLS: [FlowControl; 3] = [
+        FlowControl::Hardware,
+        FlowControl::Software,
+        FlowControl::None,
+    ];
+    const PARITIES: [Parity; 3] = [Parity::Odd, Parity::Even, Parity::None];
+    const STOP_BITS: [StopBits; 2] = [StopBits::One, StopBits::Two];
+
+    /// `s` parses exactly when it is the lower or capitalized name of one of `values`.
+    fn check_from_str<T>(s: &str, values: &[T]) -> Result<(), TestCaseError>
+    where
+        T: FromStr<Err = rootcause::Report> + Display + PartialEq + std::fmt::Debug + Copy,
+    {
+        let expected = values.iter().copied().find(|value| {
+            let lower = value.to_string();
+            s == lower || s == lower[..1].to_uppercase() + &lower[1..]
+        });
+        let parsed = s.parse::<T>().ok();
+        prop_assert_eq!(parsed, expected, "input {:?}", s);
+        Ok(())
+    }
+
+    /// Arbitrary strings, mixed with near misses of the valid names.
+    fn from_str_input(names: &'static str) -> impl Strategy<Value = String> {
+        prop_oneof![
+            any::<String>(),
+            proptest::string::string_regex(&format!("\\s?(?i:{})\\s?", names)).unwrap(),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn data_bits_from_str_accepts_only_known_names(
+            s in from_str_input("five|six|seven|eight")
+        ) {
+            check_from_str(&s, &DATA_BITS)?;
+        }
+
+        #[test]
+        fn flow_control_from_str_accepts_only_known_names(
+            s in from_str_input("hardware|software|none")
+        ) {
+            check_from_str(&s, &FLOW_CONTROLS)?;
+        }
+
+        #[test]
+        fn parity_from_str_accepts_only_known_names(s in from_str_input("odd|even|none")) {
+            check_from_str(&s, &PARITIES)?;
+        }
+
+        #[test]
+        fn stop_bits_from_str_accepts_only_known_names(s in from_str_input("one|two")) {
+            check_from_str(&s, &STOP_BITS)?;
+        }
+    }
+
+    #[test]
+    fn try_from_str_matches_from_str() {
+        assert_eq!(DataBits::try_from("seven").unwrap(), DataBits::Seven);
+        assert_eq!(
+            FlowControl::try_from("Hardware").unwrap(),
+            FlowControl::Hardware
+        );
+        assert_eq!(Parity::try_from("even").unwrap(), Parity::Even);
+        assert_eq!(StopBits::try_from("Two").unwrap(), StopBits::Two);
+        assert!(Parity::try_from("EVEN").is_err());
+    }
+
+    #[test]
+    fn data_bits_try_from_u8() {
+        assert_eq!(DataBits::try_from(5u8).unwrap(), DataBits::Five);
+        assert_eq!(DataBits::try_from(6u8).unwrap(), DataBits::Six);
+        assert_eq!(DataBits::try_from(7u8).unwrap(), DataBits::Seven);
+        assert_eq!(DataBits::try_from(8u8).unwrap(), DataBits::Eight);
+        assert!(DataBits::try_from(4u8).is_err());
+        assert!(DataBits::try_from(9u8).is_err());
+    }
+
+    #[test]
+    fn stop_bits_try_from_u8() {
+        assert_eq!(StopBits::try_from(1u8).unwrap(), StopBits::One);
+        assert_eq!(StopBits::try_from(2u8).unwrap(), StopBits::Two);
+        assert!(StopBits::try_from(0u8).is_err());
+        assert!(StopBits::try_from(3u8).is_err());
+    }
+}
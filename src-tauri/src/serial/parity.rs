@@ -21,6 +21,13 @@ impl FromStr for Parity {
     }
 }
 
+impl TryFrom<&str> for Parity {
+    type Error = Report;
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
 impl fmt::Display for Parity {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let s = match self {
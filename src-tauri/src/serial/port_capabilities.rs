@@ -0,0 +1,142 @@
+use crate::serial::{
+    data_bits::DataBits, parity::Parity, port_type::PortType, stop_bits::StopBits,
+};
+
+/// Settings a port is known to support.
+///
+/// Neither `tokio_serial` nor the platform APIs it wraps report driver limits,
+/// so these come from a table of common USB-serial bridges, keyed by VID/PID.
+/// Ports that are not recognized get conservative defaults.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PortCapabilities {
+    /// Highest baud rate the adapter is known to handle, `None` if unknown.
+    pub max_baud_rate: Option<u32>,
+    pub supported_data_bits: Vec<DataBits>,
+    pub supported_parity: Vec<Parity>,
+    pub supported_stop_bits: Vec<StopBits>,
+    pub supports_hardware_flow_control: bool,
+}
+
+/// A USB-serial bridge with known limits.
+struct KnownBridge {
+    vid: u16,
+    pid: u16,
+    max_baud_rate: u32,
+    data_bits: &'static [DataBits],
+    hardware_flow_control: bool,
+}
+
+const SEVEN_EIGHT: &[DataBits] = &[DataBits::Seven, DataBits::Eight];
+const FIVE_TO_EIGHT: &[DataBits] = &[
+    DataBits::Five,
+    DataBits::Six,
+    DataBits::Seven,
+    DataBits::Eight,
+];
+
+const KNOWN_BRIDGES: &[KnownBridge] = &[
+    // FTDI FT232R
+    KnownBridge {
+        vid: 0x0403,
+        pid: 0x6001,
+        max_baud_rate: 3_000_000,
+        data_bits: SEVEN_EIGHT,
+        hardware_flow_control: true,
+    },
+    // FTDI FT2232H
+    KnownBridge {
+        vid: 0x0403,
+        pid: 0x6010,
+        max_baud_rate: 12_000_000,
+        data_bits: SEVEN_EIGHT,
+        hardware_flow_control: true,
+    },
+    // FTDI FT4232H
+    KnownBridge {
+        vid: 0x0403,
+        pid: 0x6011,
+        max_baud_rate: 12_000_000,
+        data_bits: SEVEN_EIGHT,
+        hardware_flow_control: true,
+    },
+    // FTDI FT232H
+    KnownBridge {
+        vid: 0x0403,
+        pid: 0x6014,
+        max_baud_rate: 12_000_000,
+        data_bits: SEVEN_EIGHT,
+        hardware_flow_control: true,
+    },
+    // FTDI FT-X series
+    KnownBridge {
+        vid: 0x0403,
+        pid: 0x6015,
+        max_baud_rate: 3_000_000,
+        data_bits: SEVEN_EIGHT,
+        hardware_flow_control: true,
+    },
+    // Silicon Labs CP210x
+    KnownBridge {
+        vid: 0x10C4,
+        pid: 0xEA60,
+        max_baud_rate: 921_600,
+        data_bits: FIVE_TO_EIGHT,
+        hardware_flow_control: true,
+    },
+    // WCH CH340/CH341
+    KnownBridge {
+        vid: 0x1A86,
+        pid: 0x7523,
+        max_baud_rate: 2_000_000,
+        data_bits: FIVE_TO_EIGHT,
+        hardware_flow_control: true,
+    },
+    // Prolific PL2303
+    KnownBridge {
+        vid: 0x067B,
+        pid: 0x2303,
+        max_baud_rate: 1_228_800,
+        data_bits: FIVE_TO_EIGHT,
+        hardware_flow_control: true,
+    },
+];
+
+/// Highest baud rate of a standard 16550-compatible UART.
+const PCI_UART_MAX_BAUD_RATE: u32 = 115_200;
+
+impl PortCapabilities {
+    /// Settings any serial driver is expected to accept.
+    pub fn conservative() -> Self {
+        Self {
+            max_baud_rate: None,
+            supported_data_bits: SEVEN_EIGHT.to_vec(),
+            supported_parity: vec![Parity::None, Parity::Odd, Parity::Even],
+            supported_stop_bits: vec![StopBits::One, StopBits::Two],
+            supports_hardware_flow_control: false,
+        }
+    }
+
+    /// Best known capabilities for a port of the given type.
+    pub fn for_port_type(port_type: &PortType) -> Self {
+        let conservative = Self::conservative();
+        match port_type {
+            PortType::UsbPort(usb) => KNOWN_BRIDGES
+                .iter()
+                .find(|bridge| bridge.vid == usb.vid && bridge.pid == usb.pid)
+                .map(|bridge| Self {
+                    max_baud_rate: Some(bridge.max_baud_rate),
+                    supported_data_bits: bridge.data_bits.to_vec(),
+                    supports_hardware_flow_control: bridge.hardware_flow_control,
+                    ..conservative.clone()
+                })
+                .unwrap_or(conservative),
+            PortType::PciPort => Self {
+                max_baud_rate: Some(PCI_UART_MAX_BAUD_RATE),
+                supported_data_bits: FIVE_TO_EIGHT.to_vec(),
+                supports_hardware_flow_control: true,
+                ..conservative
+            },
+            PortType::BluetoothPort | PortType::Unknown => conservative,
+        }
+    }
+}
@@ -0,0 +1,231 @@
+//! Checks whether the current user is allowed to open a serial port.
+
+/// Outcome of a permission check, with hints the UI can show to the user.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PermissionCheckResult {
+    /// `false` if the port will certainly fail to open for permission reasons.
+    pub can_open: bool,
+    pub issues: Vec<String>,
+    pub suggestions: Vec<String>,
+}
+
+impl PermissionCheckResult {
+    fn ok() -> Self {
+        Self {
+            can_open: true,
+            ..Default::default()
+        }
+    }
+
+    fn issue(&mut self, issue: impl Into<String>, suggestion: impl Into<String>) {
+        self.can_open = false;
+        self.issues.push(issue.into());
+        self.suggestions.push(suggestion.into());
+    }
+}
+
+/// Runs the platform specific checks for `port_name`.
+///
+/// Only the checks that can be done without opening the port are performed,
+/// a port held by another process is not detected.
+pub fn check_port_permissions(port_name: &str) -> PermissionCheckResult {
+    #[cfg(target_os = "linux")]
+    return linux::check(port_name);
+
+    #[cfg(target_os = "macos")]
+    return macos::check(port_name);
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    return check_present(port_name);
+}
+
+/// Fallback for platforms without device files, only checks the port is enumerated.
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn check_present(port_name: &str) -> PermissionCheckResult {
+    let mut result = PermissionCheckResult::ok();
+    let present = tokio_serial::available_ports()
+        .map(|ports| ports.iter().any(|p| p.port_name == port_name))
+        .unwrap_or(false);
+    if !present {
+        result.issue(
+            format!("{} was not found", port_name),
+            "Check the device is connected and its driver is installed in Device Manager",
+        );
+        result.suggestions.push(
+            "Some device drivers require running the application as administrator".to_string(),
+        );
+    }
+    result
+}
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use super::PermissionCheckResult;
+
+    pub(super) fn check(port_name: &str) -> PermissionCheckResult {
+        let mut result = PermissionCheckResult::ok();
+        if !std::path::Path::new(port_name).exists() {
+            result.issue(
+                format!("{} does not exist", port_name),
+                "Check the device is connected and its driver is installed",
+            );
+            return result;
+        }
+        if port_name.starts_with("/dev/tty.") {
+            // Not a permission problem, but tty.* blocks on open until carrier detect
+            result.suggestions.push(format!(
+                "Use {} instead, tty.* devices wait for carrier detect when opened",
+                port_name.replacen("/dev/tty.", "/dev/cu.", 1)
+            ));
+        }
+        result
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::os::unix::fs::MetadataExt;
+    use std::path::PathBuf;
+
+    use super::PermissionCheckResult;
+
+    /// GID of `dialout` on Debian based systems, used if `/etc/group` has no entry.
+    const DIALOUT_GID: u32 = 20;
+
+    /// Effective UID and supplementary groups of this process.
+    struct Credentials {
+        uid: u32,
+        gid: u32,
+        groups: Vec<u32>,
+    }
+
+    impl Credentials {
+        fn current() -> Option<Self> {
+            let status = std::fs::read_to_string("/proc/self/status").ok()?;
+            // "Uid:" and "Gid:" list real, effective, saved and filesystem ids
+            let field = |name: &str| {
+                status
+                    .lines()
+                    .find_map(|line| line.strip_prefix(name))
+                    .map(|rest| {
+                        rest.split_whitespace()
+                            .filter_map(|id| id.parse().ok())
+                            .collect::<Vec<u32>>()
+                    })
+            };
+            Some(Self {
+                uid: *field("Uid:")?.get(1)?,
+                gid: *field("Gid:")?.get(1)?,
+                groups: field("Groups:").unwrap_or_default(),
+            })
+        }
+
+        fn in_group(&self, gid: u32) -> bool {
+            self.gid == gid || self.groups.contains(&gid)
+        }
+    }
+
+    /// Name and GID of every entry in `/etc/group`.
+    fn system_groups() -> Vec<(String, u32)> {
+        std::fs::read_to_string("/etc/group")
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split(':');
+                let name = fields.next()?;
+                let gid = fields.nth(1)?.parse().ok()?;
+                Some((name.to_string(), gid))
+            })
+            .collect()
+    }
+
+    pub(super) fn check(port_name: &str) -> PermissionCheckResult {
+        let mut result = PermissionCheckResult::ok();
+        let path = if port_name.starts_with('/') {
+            PathBuf::from(port_name)
+        } else {
+            PathBuf::from("/dev").join(port_name)
+        };
+
+        let metadata = match std::fs::metadata(&path) {
+            Ok(metadata) => metadata,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                result.issue(
+                    format!("{} does not exist", path.display()),
+                    "Check the device is connected, run `dmesg | tail` to see if the kernel detected it",
+                );
+                return result;
+            }
+            Err(err) => {
+                result.issue(
+                    format!("cannot access {}: {}", path.display(), err),
+                    "Check the permissions of the parent directory",
+                );
+                return result;
+            }
+        };
+
+        let Some(creds) = Credentials::current() else {
+            // Without credentials nothing more can be said, let open_port decide
+            return result;
+        };
+        if creds.uid == 0 {
+            return result;
+        }
+
+        let mode = metadata.mode();
+        let accessible = (metadata.uid() == creds.uid && mode & 0o600 == 0o600)
+            || (creds.in_group(metadata.gid()) && mode & 0o060 == 0o060)
+            || mode & 0o006 == 0o006;
+        if accessible {
+            return result;
+        }
+
+        let groups = system_groups();
+        let group_name = groups
+            .iter()
+            .find(|(_, gid)| *gid == metadata.gid())
+            .map(|(name, _)| name.clone())
+            .unwrap_or_else(|| metadata.gid().to_string());
+        let dialout_gid = groups
+            .iter()
+            .find(|(name, _)| name == "dialout")
+            .map(|(_, gid)| *gid)
+            .unwrap_or(DIALOUT_GID);
+
+        if creds.in_group(metadata.gid()) {
+            result.issue(
+                format!(
+                    "{} is not readable and writable by group {}",
+                    path.display(),
+                    group_name
+                ),
+                format!(
+                    "Fix the device permissions: sudo chmod g+rw {}",
+                    path.display()
+                ),
+            );
+        } else {
+            result.issue(
+                format!(
+                    "{} is owned by group {} and you are not a member",
+                    path.display(),
+                    group_name
+                ),
+                format!(
+                    "Add yourself to {} group: sudo usermod -aG {} $USER",
+                    group_name, group_name
+                ),
+            );
+            result
+                .suggestions
+                .push("Log out and back in for the group change to take effect".to_string());
+            if metadata.gid() != dialout_gid && !creds.in_group(dialout_gid) {
+                result
+                    .issues
+                    .push("you are not a member of the dialout group".to_string());
+            }
+        }
+        result
+    }
+}
@@ -11,3 +11,13 @@ pub enum PortType {
     /// It can't be determined how the serial port is connected
     Unknown,
 }
+
+impl PortType {
+    /// Converts the type the OS reports for `port_name`, adding the USB hub port.
+    pub fn from_system(port_name: &str, port_type: tokio_serial::SerialPortType) -> Self {
+        match Self::from(port_type) {
+            Self::UsbPort(usb) => Self::UsbPort(usb.with_hub_port(port_name)),
+            other => other,
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use std::fmt;
+
+/// How break conditions show up in the read data.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ReadFraming {
+    /// Breaks are only reported as `PortErrorCode::Break` events.
+    #[default]
+    Raw,
+    /// Breaks are also inserted into the data as `BREAK_SENTINEL`.
+    ///
+    /// For protocols that use breaks as frame delimiters, such as LIN bus.
+    BreakAware,
+}
+
+impl ReadFraming {
+    /// Bytes read events carry at the position of a break in `BreakAware` mode.
+    pub const BREAK_SENTINEL: &'static [u8] = &[0xFF, 0x00];
+}
+
+impl fmt::Display for ReadFraming {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Raw => f.write_str("raw"),
+            Self::BreakAware => f.write_str("break aware"),
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use std::fmt;
+
+/// How the port task waits for incoming data.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ReadStrategy {
+    /// Await reads on the OS-level async I/O (epoll/kqueue/IOCP).
+    #[default]
+    Async,
+    /// Check `bytes_to_read` every `interval_ms` milliseconds and read without blocking.
+    ///
+    /// Fallback for platforms or drivers where async serial reads are unreliable.
+    Polled { interval_ms: u64 },
+}
+
+impl fmt::Display for ReadStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Async => f.write_str("async"),
+            Self::Polled { interval_ms } => write!(f, "polled ({}ms)", interval_ms),
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use base64::Engine;
+use rootcause::{report, Report};
+
+/// Processing applied to read data before it is forwarded.
+///
+/// Each read is transformed on its own, so an encoded value split across two
+/// reads fails to decode and is forwarded raw.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ReadTransform {
+    /// Forward the data as read.
+    #[default]
+    None,
+    /// Remove ANSI escape sequences such as colors and cursor movement.
+    StripAnsiEscapes,
+    /// Decode hex text, ignoring whitespace.
+    HexDecode,
+    /// Decode standard Base64 text, ignoring whitespace.
+    Base64Decode,
+    /// Remove every `\r` and `\n`.
+    StripLineEndings,
+    /// Apply each transform in turn.
+    Chain(Vec<ReadTransform>),
+}
+
+impl ReadTransform {
+    pub fn is_identity(&self) -> bool {
+        match self {
+            Self::None => true,
+            Self::Chain(transforms) => transforms.iter().all(Self::is_identity),
+            _ => false,
+        }
+    }
+
+    /// Transforms `data`, failing if it cannot be decoded.
+    pub fn apply(&self, data: &[u8]) -> Result<Vec<u8>, Report> {
+        match self {
+            Self::None => Ok(data.to_vec()),
+            Self::StripAnsiEscapes => Ok(strip_ansi_escapes(data)),
+            Self::HexDecode => {
+                hex::decode(without_whitespace(data)).map_err(|e| report!("invalid hex: {}", e))
+            }
+            Self::Base64Decode => base64::engine::general_purpose::STANDARD
+                .decode(without_whitespace(data))
+                .map_err(|e| report!("invalid base64: {}", e)),
+            Self::StripLineEndings => Ok(data
+                .iter()
+                .copied()
+                .filter(|b| !matches!(b, b'\r' | b'\n'))
+                .collect()),
+            Self::Chain(transforms) => transforms
+                .iter()
+                .try_fold(data.to_vec(), |data, transform| transform.apply(&data)),
+        }
+    }
+}
+
+fn without_whitespace(data: &[u8]) -> Vec<u8> {
+    data.iter()
+        .copied()
+        .filter(|b| !b.is_ascii_whitespace())
+        .collect()
+}
+
+/// Removes CSI sequences (`ESC [ ... final`) and two byte `ESC x` sequences.
+fn strip_ansi_escapes(data: &[u8]) -> Vec<u8> {
+    const ESC: u8 = 0x1B;
+    let mut out = Vec::with_capacity(data.len());
+    let mut bytes = data.iter().copied();
+    while let Some(b) = bytes.next() {
+        if b != ESC {
+            out.push(b);
+            continue;
+        }
+        // Parameters and intermediates run until a final byte in 0x40..=0x7E
+        if bytes.next() == Some(b'[') {
+            for b in bytes.by_ref() {
+                if (0x40..=0x7E).contains(&b) {
+                    break;
+                }
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transforms_decode_and_strip() {
+        assert_eq!(
+            ReadTransform::StripAnsiEscapes
+                .apply(b"\x1b[1;31mERR\x1b[0m ok\x1b7")
+                .unwrap(),
+            b"ERR ok"
+        );
+        assert_eq!(
+            ReadTransform::HexDecode.apply(b"de ad\r\nBE EF").unwrap(),
+            [0xDE, 0xAD, 0xBE, 0xEF]
+        );
+        assert_eq!(
+            ReadTransform::Base64Decode.apply(b"aGVsbG8=\n").unwrap(),
+            b"hello"
+        );
+        assert_eq!(
+            ReadTransform::StripLineEndings.apply(b"a\r\nb\n").unwrap(),
+            b"ab"
+        );
+        assert!(ReadTransform::HexDecode.apply(b"abc").is_err());
+        assert!(ReadTransform::Base64Decode.apply(b"*").is_err());
+    }
+
+    #[test]
+    fn chain_applies_in_order() {
+        let chain = ReadTransform::Chain(vec![
+            ReadTransform::StripAnsiEscapes,
+            ReadTransform::HexDecode,
+        ]);
+        assert_eq!(chain.apply(b"\x1b[32m4f4b\x1b[0m").unwrap(), b"OK");
+        assert!(!chain.is_identity());
+        assert!(ReadTransform::Chain(vec![ReadTransform::None]).is_identity());
+    }
+}
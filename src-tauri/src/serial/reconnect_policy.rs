@@ -0,0 +1,87 @@
+use std::fmt;
+use std::time::Duration;
+
+/// Whether the port task reopens a port whose reads failed, and how often it tries.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ReconnectPolicy {
+    /// Close the port on the first failed read.
+    #[default]
+    Never,
+    /// Try up to `max_attempts` times, waiting `interval_ms` milliseconds before each one.
+    Attempts { max_attempts: u32, interval_ms: u64 },
+    /// Try every `interval_ms` milliseconds until the port is closed.
+    Infinite { interval_ms: u64 },
+}
+
+impl ReconnectPolicy {
+    /// Wait before reconnect attempt `attempt`, counted from 1.
+    ///
+    /// `None` once the policy allows no more attempts.
+    pub fn delay(&self, attempt: u32) -> Option<Duration> {
+        let interval_ms = match *self {
+            Self::Never => return None,
+            Self::Attempts {
+                max_attempts,
+                interval_ms,
+            } => {
+                if attempt > max_attempts {
+                    return None;
+                }
+                interval_ms
+            }
+            Self::Infinite { interval_ms } => interval_ms,
+        };
+        Some(Duration::from_millis(interval_ms.max(1)))
+    }
+}
+
+impl fmt::Display for ReconnectPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Never => f.write_str("never"),
+            Self::Attempts {
+                max_attempts,
+                interval_ms,
+            } => write!(f, "{} attempts ({}ms)", max_attempts, interval_ms),
+            Self::Infinite { interval_ms } => write!(f, "infinite ({}ms)", interval_ms),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delay_stops_after_max_attempts() {
+        assert_eq!(ReconnectPolicy::Never.delay(1), None);
+
+        let attempts = ReconnectPolicy::Attempts {
+            max_attempts: 2,
+            interval_ms: 500,
+        };
+        assert_eq!(attempts.delay(1), Some(Duration::from_millis(500)));
+        assert_eq!(attempts.delay(2), Some(Duration::from_millis(500)));
+        assert_eq!(attempts.delay(3), None);
+
+        let infinite = ReconnectPolicy::Infinite { interval_ms: 0 };
+        assert_eq!(infinite.delay(u32::MAX), Some(Duration::from_millis(1)));
+    }
+
+    #[test]
+    fn serializes_as_tagged_variants() {
+        let policy: ReconnectPolicy =
+            serde_json::from_str(r#"{"Attempts":{"max_attempts":3,"interval_ms":1000}}"#).unwrap();
+        assert_eq!(
+            policy,
+            ReconnectPolicy::Attempts {
+                max_attempts: 3,
+                interval_ms: 1000
+            }
+        );
+        assert_eq!(
+            serde_json::to_string(&ReconnectPolicy::Never).unwrap(),
+            r#""Never""#
+        );
+    }
+}
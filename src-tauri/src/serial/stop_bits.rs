@@ -19,6 +19,24 @@ impl FromStr for StopBits {
     }
 }
 
+impl TryFrom<&str> for StopBits {
+    type Error = Report;
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl TryFrom<u8> for StopBits {
+    type Error = Report;
+    fn try_from(bits: u8) -> Result<Self, Self::Error> {
+        match bits {
+            1 => Ok(Self::One),
+            2 => Ok(Self::Two),
+            _ => Err(report!("unsupported number of stop bits: {}", bits)),
+        }
+    }
+}
+
 impl fmt::Display for StopBits {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let s = match self {
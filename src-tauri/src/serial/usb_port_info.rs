@@ -14,4 +14,135 @@ pub struct UsbPortInfo {
     /// the communication interface (as is the case on Windows and Linux) or the data
     /// interface (as is the case on macOS), so you should recognize both interface numbers.
     pub interface: Option<u8>,
+    /// Port of the hub the device is plugged into, tells identical adapters apart.
+    /// Read from sysfs on Linux and from the device name on macOS, not available on Windows.
+    pub hub_port: Option<u8>,
+}
+
+impl UsbPortInfo {
+    /// Fills in `hub_port` for the device behind `port_name`.
+    pub fn with_hub_port(mut self, port_name: &str) -> Self {
+        self.hub_port = hub_port(port_name, self.serial_number.as_deref());
+        self
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn hub_port(port_name: &str, _serial_number: Option<&str>) -> Option<u8> {
+    hub_port_from_sysfs(std::path::Path::new("/sys/class/tty"), port_name)
+}
+
+#[cfg(target_os = "macos")]
+fn hub_port(port_name: &str, serial_number: Option<&str>) -> Option<u8> {
+    // With a serial number the name carries it instead of the location
+    match serial_number {
+        Some(_) => None,
+        None => hub_port_from_location_name(port_name),
+    }
+}
+
+/// The device instance path is not exposed through `serialport` on Windows.
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn hub_port(_port_name: &str, _serial_number: Option<&str>) -> Option<u8> {
+    None
+}
+
+/// Resolves `<tty_class>/<tty>/device` and finds the USB device above it,
+/// named like `1-1.3` for port 3 of the hub on root port 1 of bus 1.
+#[cfg(target_os = "linux")]
+fn hub_port_from_sysfs(tty_class: &std::path::Path, port_name: &str) -> Option<u8> {
+    let tty = std::path::Path::new(port_name).file_name()?;
+    let device = std::fs::canonicalize(tty_class.join(tty).join("device")).ok()?;
+    device
+        .ancestors()
+        .find_map(|dir| usb_device_port(dir.file_name()?.to_str()?))
+}
+
+/// Last port in a sysfs USB device name (`<bus>-<port>[.<port>...]`).
+#[cfg(any(target_os = "linux", test))]
+fn usb_device_port(name: &str) -> Option<u8> {
+    let (bus, path) = name.split_once('-')?;
+    bus.parse::<u8>().ok()?;
+    let ports = path
+        .split('.')
+        .map(|port| port.parse::<u8>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    ports.last().copied()
+}
+
+/// Hub port from a macOS name like `cu.usbserial-1432` or `cu.usbmodem14321`.
+///
+/// The suffix is the location ID in hex: two digits for the bus, then one per
+/// port along the path. `usbmodem` names append the interface number.
+#[cfg(any(target_os = "macos", test))]
+fn hub_port_from_location_name(port_name: &str) -> Option<u8> {
+    let name = port_name.rsplit('/').next()?;
+    let location = match name.split_once("usbserial-") {
+        Some((_, location)) => location,
+        None => {
+            let (_, rest) = name.split_once("usbmodem")?;
+            rest.get(..rest.len().checked_sub(1)?)?
+        }
+    };
+    if !location.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    location
+        .get(2..)?
+        .chars()
+        .rev()
+        .find(|&digit| digit != '0')
+        .and_then(|digit| digit.to_digit(16))
+        .map(|port| port as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn usb_device_names_give_last_port() {
+        assert_eq!(usb_device_port("1-1.3"), Some(3));
+        assert_eq!(usb_device_port("2-4"), Some(4));
+        assert_eq!(usb_device_port("1-1.3:1.0"), None);
+        assert_eq!(usb_device_port("usb1"), None);
+        assert_eq!(usb_device_port("ttyUSB0"), None);
+    }
+
+    #[test]
+    fn macos_location_names_give_last_port() {
+        assert_eq!(
+            hub_port_from_location_name("/dev/cu.usbserial-1432"),
+            Some(2)
+        );
+        assert_eq!(
+            hub_port_from_location_name("/dev/tty.usbmodem14301"),
+            Some(3)
+        );
+        assert_eq!(
+            hub_port_from_location_name("/dev/cu.usbserial-A50285BI"),
+            None
+        );
+        assert_eq!(
+            hub_port_from_location_name("/dev/cu.Bluetooth-Incoming-Port"),
+            None
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn sysfs_tty_resolves_to_hub_port() {
+        let root = std::env::temp_dir().join(format!("hub-port-{}", std::process::id()));
+        let interface = root.join("devices/usb1/1-1/1-1.3/1-1.3:1.0/ttyUSB0");
+        std::fs::create_dir_all(&interface).unwrap();
+        let tty_class = root.join("class/tty/ttyUSB0");
+        std::fs::create_dir_all(&tty_class).unwrap();
+        std::os::unix::fs::symlink(&interface, tty_class.join("device")).unwrap();
+
+        let hub_port = hub_port_from_sysfs(&root.join("class/tty"), "/dev/ttyUSB0");
+        let missing = hub_port_from_sysfs(&root.join("class/tty"), "/dev/ttyUSB1");
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(hub_port, Some(3));
+        assert_eq!(missing, None);
+    }
 }
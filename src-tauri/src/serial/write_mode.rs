@@ -0,0 +1,26 @@
+use std::fmt;
+
+/// How the port task pushes a message out to the port.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum WriteMode {
+    /// Write the whole message at once.
+    #[default]
+    Immediate,
+    /// Write one byte at a time, sleeping `delay_us` microseconds between bytes.
+    ByteByByte { delay_us: u64 },
+    /// Write `chunk_size` bytes at a time, sleeping `delay_ms` milliseconds between chunks.
+    ChunkedWithDelay { chunk_size: usize, delay_ms: u64 },
+}
+
+impl fmt::Display for WriteMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Immediate => f.write_str("immediate"),
+            Self::ByteByByte { delay_us } => write!(f, "byte by byte ({}us)", delay_us),
+            Self::ChunkedWithDelay {
+                chunk_size,
+                delay_ms,
+            } => write!(f, "chunked ({} bytes, {}ms)", chunk_size, delay_ms),
+        }
+    }
+}
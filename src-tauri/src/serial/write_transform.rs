@@ -0,0 +1,113 @@
+/// Checksum appended by a [`WriteTransform`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ChecksumAlgorithm {
+    /// XOR of all bytes, 1 byte.
+    Xor,
+    /// CRC-8 with polynomial 0x07 and initial value 0, 1 byte.
+    Crc8,
+    /// CRC-16/MODBUS, 2 bytes, low byte first.
+    Crc16Modbus,
+    /// Longitudinal redundancy check, the two's complement of the byte sum, 1 byte.
+    Lrc,
+}
+
+impl ChecksumAlgorithm {
+    /// Checksum of `data`, in the order it is sent.
+    pub fn compute(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Xor => vec![data.iter().fold(0, |acc, b| acc ^ b)],
+            Self::Crc8 => {
+                let crc = data.iter().fold(0u8, |mut crc, &b| {
+                    crc ^= b;
+                    for _ in 0..8 {
+                        crc = if crc & 0x80 != 0 {
+                            (crc << 1) ^ 0x07
+                        } else {
+                            crc << 1
+                        };
+                    }
+                    crc
+                });
+                vec![crc]
+            }
+            Self::Crc16Modbus => {
+                let crc = data.iter().fold(0xFFFFu16, |mut crc, &b| {
+                    crc ^= b as u16;
+                    for _ in 0..8 {
+                        crc = if crc & 1 != 0 {
+                            (crc >> 1) ^ 0xA001
+                        } else {
+                            crc >> 1
+                        };
+                    }
+                    crc
+                });
+                crc.to_le_bytes().to_vec()
+            }
+            Self::Lrc => vec![data
+                .iter()
+                .fold(0u8, |acc, &b| acc.wrapping_add(b))
+                .wrapping_neg()],
+        }
+    }
+}
+
+/// Bytes the port task adds around every message it writes.
+///
+/// For devices that expect a fixed station address or a checksum on every command.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct WriteTransform {
+    pub prefix: Vec<u8>,
+    pub suffix: Vec<u8>,
+    /// Computed over `prefix` and the message, inserted before `suffix`.
+    pub checksum_algorithm: Option<ChecksumAlgorithm>,
+}
+
+impl WriteTransform {
+    pub fn is_identity(&self) -> bool {
+        self.prefix.is_empty() && self.suffix.is_empty() && self.checksum_algorithm.is_none()
+    }
+
+    /// `data` as written to the port: prefix, data, checksum, suffix.
+    pub fn apply(&self, data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.prefix.len() + data.len() + self.suffix.len() + 2);
+        out.extend_from_slice(&self.prefix);
+        out.extend_from_slice(data);
+        if let Some(algorithm) = self.checksum_algorithm {
+            let checksum = algorithm.compute(&out);
+            out.extend(checksum);
+        }
+        out.extend_from_slice(&self.suffix);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksums_match_reference_values() {
+        let data = b"123456789";
+        assert_eq!(ChecksumAlgorithm::Xor.compute(data), [0x31]);
+        assert_eq!(ChecksumAlgorithm::Crc8.compute(data), [0xF4]);
+        assert_eq!(ChecksumAlgorithm::Crc16Modbus.compute(data), [0x37, 0x4B]);
+        assert_eq!(ChecksumAlgorithm::Lrc.compute(data), [0x23]);
+    }
+
+    #[test]
+    fn checksum_covers_prefix_but_not_suffix() {
+        let transform = WriteTransform {
+            prefix: vec![0x01],
+            suffix: b"\r\n".to_vec(),
+            checksum_algorithm: Some(ChecksumAlgorithm::Crc16Modbus),
+        };
+        // Modbus RTU read holding registers request for slave 1
+        let frame = transform.apply(&[0x03, 0x00, 0x00, 0x00, 0x0A]);
+        assert_eq!(
+            frame,
+            [0x01, 0x03, 0x00, 0x00, 0x00, 0x0A, 0xC5, 0xCD, b'\r', b'\n']
+        );
+        assert_eq!(WriteTransform::default().apply(b"abc"), b"abc");
+    }
+}
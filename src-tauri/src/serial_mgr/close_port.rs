@@ -1,25 +1,45 @@
 //! Close port operations.
 
+use std::time::Duration;
+
 use rootcause::prelude::ResultExt;
 use tauri::{AppHandle, Emitter};
 
+use crate::constants::serial;
 use crate::events::{event_names, PortClosedEvent};
 use crate::serial_mgr::helpers::{get_port_sender, send_command_with_ack};
-use crate::serial_mgr::port_task::WriteCmd;
-use crate::state::AppState;
+use crate::serial_mgr::port_task::{WriteCmd, WritePortDataTerminalReady};
+use crate::state::{AppState, PortStatus};
 
 /// Close an open serial port.
 #[tauri::command(rename_all = "camelCase")]
+#[tracing::instrument(name = "close_port", level = "debug", skip(app, state), fields(%port_name))]
 pub async fn close_port(
     app: AppHandle,
     state: tauri::State<'_, AppState>,
     port_name: String,
 ) -> Result<(), String> {
-    let span = tracing::debug_span!("close_port", %port_name);
-    let _guard = span.enter();
-
     let sender = get_port_sender(&state, &port_name).await?;
 
+    // Marked before the first command so a second close_port fails fast.
+    // The port task's cleanup sets the status to Closed once it exits.
+    let mut dtr_on_close = false;
+    if let Some(mut entry) = state.ports.get_mut(&port_name) {
+        match &entry.port_status {
+            PortStatus::Opened(profile) => {
+                dtr_on_close = profile.dtr_on_close;
+                entry.port_status = PortStatus::Closing;
+            }
+            PortStatus::Closing => return Err(format!("{} is already closing", port_name)),
+            _ => {}
+        }
+    }
+    if dtr_on_close {
+        let cmd = WriteCmd::Dtr(WritePortDataTerminalReady { dtr: false });
+        send_command_with_ack(&sender, cmd, "deassert DTR", &port_name).await?;
+        tokio::time::sleep(Duration::from_millis(serial::DTR_ON_CLOSE_DELAY_MS)).await;
+    }
+
     send_command_with_ack(&sender, WriteCmd::Close, "close port", &port_name).await?;
 
     tracing::info!("port closed successfully");
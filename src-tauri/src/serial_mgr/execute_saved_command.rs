@@ -12,6 +12,16 @@ use crate::state::AppState;
 /// the tracing span for debugging purposes but are not stored in the backend.
 /// Frontend manages the mapping between commands, logs, and their contexts.
 #[tauri::command(rename_all = "camelCase")]
+#[tracing::instrument(
+    name = "execute_saved_command",
+    level = "debug",
+    skip_all,
+    fields(
+        %port_name,
+        %message_id,
+        context_count = context_ids.as_ref().map(|c| c.len()).unwrap_or(0)
+    )
+)]
 pub async fn execute_saved_command(
     state: tauri::State<'_, AppState>,
     port_name: String,
@@ -19,14 +29,6 @@ pub async fn execute_saved_command(
     message_id: String,
     #[allow(unused_variables)] context_ids: Option<Vec<String>>,
 ) -> Result<(), String> {
-    let span = tracing::debug_span!(
-        "execute_saved_command",
-        %port_name,
-        %message_id,
-        context_count = context_ids.as_ref().map(|c| c.len()).unwrap_or(0)
-    );
-    let _guard = span.enter();
-
     tracing::debug!(
         "Executing saved command with {} bytes of data",
         command_data.len()
@@ -36,6 +38,7 @@ pub async fn execute_saved_command(
     let cmd = WriteCmd::Message(WritePortMessage {
         data: command_data,
         message_id,
+        sequence_number: None,
     });
 
     send_command_with_ack(&sender, cmd, "execute saved command", &port_name).await?;
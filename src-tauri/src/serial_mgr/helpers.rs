@@ -1,8 +1,11 @@
 //! Shared helper functions for serial port management operations.
 
+use crate::events::event_names;
 use crate::serial_mgr::port_task::{WriteCmd, WritePortSender};
 use crate::state::AppState;
+use crate::util::WriteAck;
 use rootcause::prelude::ResultExt;
+use tauri::{AppHandle, Emitter, Runtime};
 
 /// Retrieves the write channel sender for an open port.
 ///
@@ -14,6 +17,8 @@ use rootcause::prelude::ResultExt;
 /// * `Ok(WritePortSender)` - Clone of the sender channel
 /// * `Err(String)` - Error message if port is not open
 pub async fn get_port_sender(state: &AppState, port_name: &str) -> Result<WritePortSender, String> {
+    // `get` only takes a shard read lock, enough since cloning an `mpsc::Sender`
+    // takes `&self`. The guard is dropped before returning, never held across awaits.
     state
         .port_handles
         .get(port_name)
@@ -30,14 +35,15 @@ pub async fn get_port_sender(state: &AppState, port_name: &str) -> Result<WriteP
 /// * `port_name` - Name of the port (for error context)
 ///
 /// # Returns
-/// * `Ok(())` - Command was sent and acknowledged
-/// * `Err(String)` - Error message if send or ack failed
+/// * `Ok(WriteAck)` - Command was sent and acknowledged, with write metrics for messages
+/// * `Err(String)` - Error message if sending failed, the command was not
+///   acknowledged or the port task reported it failed
 pub async fn send_command_with_ack(
     sender: &WritePortSender,
     cmd: WriteCmd,
     operation: &str,
     port_name: &str,
-) -> Result<(), String> {
+) -> Result<WriteAck, String> {
     let (ack_tx, ack_rx) = tokio::sync::oneshot::channel();
     let operation = operation.to_string();
     let port_name = port_name.to_string();
@@ -59,9 +65,102 @@ pub async fn send_command_with_ack(
         .map_err(|err| {
             tracing::error!("wait {} ack failed: {}", operation, err);
             err.to_string()
-        })?;
+        })?
+        .map_err(|err| {
+            tracing::error!("{} failed: {}", operation, err);
+            err
+        })
+}
+
+/// Emits a port event on its shared name and on the per-port name.
+///
+/// See [`event_names::for_port`] for the per-port naming scheme.
+pub fn emit_port_event<R, S>(
+    app: &AppHandle<R>,
+    event: &str,
+    port_name: &str,
+    payload: S,
+) -> tauri::Result<()>
+where
+    R: Runtime,
+    S: serde::Serialize + Clone,
+{
+    app.emit(event, payload.clone())?;
+    app.emit(&event_names::for_port(event, port_name), payload)
+}
 
-    Ok(())
+/// Cancels every open port task and waits for each to release its port.
+///
+/// Used on shutdown so ports are released before the async runtime stops.
+/// The caller bounds the total wait.
+pub async fn close_all_ports(state: &AppState) {
+    // Collect first, DashMap guards must not be held across .await
+    let tasks: Vec<(String, Option<tokio::task::JoinHandle<()>>)> = state
+        .port_handles
+        .iter_mut()
+        .map(|mut entry| {
+            entry.shutdown.cancel();
+            (entry.key().clone(), entry.task.take())
+        })
+        .collect();
+    for (port_name, task) in tasks {
+        let Some(task) = task else {
+            continue;
+        };
+        match task.await {
+            Ok(()) => tracing::info!("closed port {} on shutdown", port_name),
+            Err(err) => tracing::warn!("port {} task failed on shutdown: {}", port_name, err),
+        }
+    }
+}
+
+/// Blocking [`close_all_ports`] for the exit handler, bounded by `timeout`.
+///
+/// `block_on` panics when called from inside a tokio runtime, so there the
+/// wait runs in a blocking section of the current (multi-threaded) runtime.
+/// Returns `false` if the ports did not close in time.
+pub fn close_all_ports_blocking(state: &AppState, timeout: std::time::Duration) -> bool {
+    let close = async {
+        tokio::time::timeout(timeout, close_all_ports(state))
+            .await
+            .is_ok()
+    };
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) => tokio::task::block_in_place(|| handle.block_on(close)),
+        Err(_) => tauri::async_runtime::block_on(close),
+    }
+}
+
+/// Encodes `text` as UTF-8, expanding `\r`, `\n`, `\t`, `\\` and `\xHH` escapes.
+///
+/// `\xHH` produces the raw byte, so it can express values that are not valid UTF-8.
+/// Unrecognized escapes are kept as written.
+pub fn unescape_text(text: &str) -> Vec<u8> {
+    let mut out = Vec::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(pos) = rest.find('\\') {
+        out.extend_from_slice(&rest.as_bytes()[..pos]);
+        let escape = &rest[pos + 1..];
+        let (byte, len) = match escape.as_bytes() {
+            [b'r', ..] => (b'\r', 1),
+            [b'n', ..] => (b'\n', 1),
+            [b't', ..] => (b'\t', 1),
+            [b'\\', ..] => (b'\\', 1),
+            [b'x', ..] => match escape
+                .get(1..3)
+                .filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit()))
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                Some(byte) => (byte, 3),
+                None => (b'\\', 0),
+            },
+            _ => (b'\\', 0),
+        };
+        out.push(byte);
+        rest = &escape[len..];
+    }
+    out.extend_from_slice(rest.as_bytes());
+    out
 }
 
 /// Get current timestamp in milliseconds since Unix epoch.
@@ -1,3 +1,14 @@
+use dashmap::mapref::entry::Entry;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use tokio::sync::broadcast::error::RecvError;
+use tracing::Instrument;
+
+use crate::events::{event_names, LogEntryAppendedEvent, StorageCorruptedEvent};
+use crate::serial_mgr::storage::{
+    AuditEntry, LogEntry, LogQuery, RepairReport, SessionSummary, StorageIntegrityReport,
+};
+use crate::state::AppState;
+
 #[tauri::command(rename_all = "camelCase")]
 #[inline]
 pub fn log(prefix: String, content: String) {
@@ -28,7 +39,7 @@ pub fn debug(prefix: String, content: String) {
     tracing::debug!("[WEB] {}: {}", prefix, content);
 }
 
-#[derive(serde::Serialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct LogEntryDto {
     pub id: i64,
     pub session_id: String,
@@ -37,11 +48,72 @@ pub struct LogEntryDto {
     pub direction: String,
     pub timestamp: i64,
     pub data: Vec<u8>,
+    pub repeat_count: i64,
+    pub message_id: Option<String>,
+}
+
+impl From<LogEntry> for LogEntryDto {
+    fn from(log: LogEntry) -> Self {
+        Self {
+            id: log.id,
+            session_id: log.session_id,
+            device_fingerprint: log.device_fingerprint,
+            port_name: log.port_name,
+            direction: log.direction,
+            timestamp: log.timestamp,
+            data: log.data,
+            repeat_count: log.repeat_count,
+            message_id: log.message_id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct AuditEntryDto {
+    pub id: i64,
+    pub timestamp: i64,
+    pub event_type: String,
+    pub port_name: String,
+    pub session_id: String,
+    pub details: serde_json::Value,
+}
+
+impl From<AuditEntry> for AuditEntryDto {
+    fn from(entry: AuditEntry) -> Self {
+        Self {
+            id: entry.id,
+            timestamp: entry.timestamp,
+            event_type: entry.event_type,
+            port_name: entry.port_name,
+            session_id: entry.session_id,
+            // Stored by `record_audit` from a JSON value, keep the text if that ever fails
+            details: serde_json::from_str(&entry.details)
+                .unwrap_or(serde_json::Value::String(entry.details)),
+        }
+    }
+}
+
+/// Returns the TX entry logged for the write with `message_id`, if any.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn get_log_by_message_id(
+    state: tauri::State<'_, AppState>,
+    message_id: String,
+) -> Result<Option<LogEntryDto>, String> {
+    let log = state
+        .storage
+        .get_by_message_id(&message_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("get log by message id failed: {}", e);
+            e
+        })?;
+
+    Ok(log.map(LogEntryDto::from))
 }
 
 #[tauri::command(rename_all = "camelCase")]
 pub async fn get_logs(
-    state: tauri::State<'_, crate::state::AppState>,
+    state: tauri::State<'_, AppState>,
     session_id: String,
     limit: usize,
     offset: usize,
@@ -52,16 +124,273 @@ pub async fn get_logs(
         .await
         .map_err(|e| e.to_string())?;
 
-    Ok(logs
-        .into_iter()
-        .map(|log| LogEntryDto {
-            id: log.id,
-            session_id: log.session_id,
-            device_fingerprint: log.device_fingerprint,
-            port_name: log.port_name,
-            direction: log.direction,
-            timestamp: log.timestamp,
-            data: log.data,
+    Ok(logs.into_iter().map(LogEntryDto::from).collect())
+}
+
+/// Returns the entries of a session logged between `start_ms` and `end_ms`, oldest first.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn get_logs_by_time_range(
+    state: tauri::State<'_, AppState>,
+    session_id: String,
+    start_ms: i64,
+    end_ms: i64,
+    limit: usize,
+    offset: usize,
+) -> Result<Vec<LogEntryDto>, String> {
+    let logs = state
+        .storage
+        .get_by_time_range(&session_id, start_ms, end_ms, limit, offset)
+        .await
+        .map_err(|e| {
+            tracing::error!("get logs by time range failed: {}", e);
+            e
+        })?;
+
+    Ok(logs.into_iter().map(LogEntryDto::from).collect())
+}
+
+/// Returns the message and byte counts of a session.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn get_session_summary(
+    state: tauri::State<'_, AppState>,
+    session_id: String,
+) -> Result<SessionSummary, String> {
+    state
+        .storage
+        .get_session_summary(&session_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("get session summary failed: {}", e);
+            e
         })
-        .collect())
+}
+
+/// Deletes a session and its log entries, returning how many entries were removed.
+///
+/// Fails for the current session of an open port, rotate it first.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn delete_session_logs(
+    state: tauri::State<'_, AppState>,
+    session_id: String,
+) -> Result<u64, String> {
+    let in_use = state
+        .port_handles
+        .iter()
+        .find(|handles| handles.session.current_id() == session_id)
+        .map(|handles| handles.key().clone());
+    if let Some(port_name) = in_use {
+        tracing::error!(
+            "delete session {} still in use by {}",
+            session_id,
+            port_name
+        );
+        return Err(format!("Session {} is in use by {}", session_id, port_name));
+    }
+
+    let removed = state
+        .storage
+        .delete_session(&session_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("delete session logs failed: {}", e);
+            e
+        })?;
+    tracing::info!("deleted session {} with {} logs", session_id, removed);
+    Ok(removed)
+}
+
+/// Deletes log entries of all sessions older than `cutoff_ms`, returning how many were removed.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn prune_logs_before(
+    state: tauri::State<'_, AppState>,
+    cutoff_ms: i64,
+) -> Result<u64, String> {
+    let removed = state.storage.delete_before(cutoff_ms).await.map_err(|e| {
+        tracing::error!("prune logs failed: {}", e);
+        e
+    })?;
+    tracing::info!("pruned {} logs older than {}", removed, cutoff_ms);
+    Ok(removed)
+}
+
+/// Returns operational events of a session, or of all sessions, newest first.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn get_audit_log(
+    state: tauri::State<'_, AppState>,
+    session_id: Option<String>,
+    limit: usize,
+) -> Result<Vec<AuditEntryDto>, String> {
+    let entries = state
+        .storage
+        .get_audit_log(session_id.as_deref(), limit)
+        .await
+        .map_err(|e| {
+            tracing::error!("get audit log failed: {}", e);
+            e
+        })?;
+
+    Ok(entries.into_iter().map(AuditEntryDto::from).collect())
+}
+
+/// Returns the entries of a session matching all filters set in `query`.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn query_logs(
+    state: tauri::State<'_, AppState>,
+    query: LogQuery,
+) -> Result<Vec<LogEntryDto>, String> {
+    let logs = state.storage.query_logs(&query).await.map_err(|e| {
+        tracing::error!("query logs failed: {}", e);
+        e
+    })?;
+
+    Ok(logs.into_iter().map(LogEntryDto::from).collect())
+}
+
+/// Start emitting `log_entry_appended` events for entries stored in a session.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn start_log_tail<R: Runtime>(
+    app: AppHandle<R>,
+    state: tauri::State<'_, AppState>,
+    session_id: String,
+) -> Result<(), String> {
+    let Entry::Vacant(slot) = state.log_tails.entry(session_id.clone()) else {
+        tracing::debug!("log tail for session {} already running", session_id);
+        return Ok(());
+    };
+
+    let mut rx = state.storage.subscribe_to_session(&session_id);
+    let log_tails = state.log_tails.clone();
+    let tail_session_id = session_id.clone();
+    let span = tracing::debug_span!("log tail", %session_id);
+    let task = tauri::async_runtime::spawn(
+        async move {
+            loop {
+                match rx.recv().await {
+                    Ok(entry) => {
+                        let event = LogEntryAppendedEvent::new(entry.into());
+                        if let Err(err) = app.emit(event_names::LOG_ENTRY_APPENDED, event) {
+                            tracing::error!("emit log entry appended failed: {}", err);
+                        }
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!("log tail lagged, skipped {} entries", skipped);
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+            // The session was deleted, a later start_log_tail must not find this tail running
+            log_tails.remove(&tail_session_id);
+            tracing::info!("log tail closed");
+        }
+        .instrument(span),
+    );
+    slot.insert(task);
+    tracing::info!("log tail started for session {}", session_id);
+    Ok(())
+}
+
+/// Stop a tail started with `start_log_tail`.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn stop_log_tail(
+    state: tauri::State<'_, AppState>,
+    session_id: String,
+) -> Result<(), String> {
+    match state.log_tails.remove(&session_id) {
+        Some((_, task)) => {
+            task.abort();
+            tracing::info!("log tail stopped for session {}", session_id);
+            Ok(())
+        }
+        None => {
+            tracing::error!("no log tail running for session {}", session_id);
+            Err(format!("no log tail running for session {}", session_id))
+        }
+    }
+}
+
+/// Checks the log database for corruption.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn check_storage_integrity(
+    state: tauri::State<'_, AppState>,
+) -> Result<StorageIntegrityReport, String> {
+    state.storage.integrity_check().await.map_err(|e| {
+        tracing::error!("storage integrity check failed: {}", e);
+        e
+    })
+}
+
+/// Rebuilds the log database, see [`crate::serial_mgr::storage::Storage::repair`].
+#[tauri::command(rename_all = "camelCase")]
+pub async fn repair_storage(state: tauri::State<'_, AppState>) -> Result<RepairReport, String> {
+    state.storage.repair().await.map_err(|e| {
+        tracing::error!("storage repair failed: {}", e);
+        e
+    })
+}
+
+/// Checks the log database once at startup, emitting `storage_corrupted` if it fails.
+pub async fn check_storage_on_startup(app: AppHandle) {
+    let state = app.state::<AppState>();
+    let report = match state.storage.integrity_check().await {
+        Ok(report) => report,
+        Err(e) => {
+            tracing::warn!("storage integrity check could not run: {}", e);
+            return;
+        }
+    };
+    if report.ok {
+        tracing::info!("storage integrity check passed");
+        return;
+    }
+    tracing::warn!(
+        "storage integrity check found {} issues: {:?}",
+        report.issues.len(),
+        report.issues
+    );
+    let event = StorageCorruptedEvent::new(report.issues);
+    if let Err(err) = app.emit(event_names::STORAGE_CORRUPTED, event) {
+        tracing::error!("emit storage corrupted failed: {}", err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tauri::Manager;
+
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn log_tail_is_removed_when_session_is_deleted() {
+        let app = tauri::test::mock_app();
+        app.manage(AppState::default());
+        let state = app.state::<AppState>();
+        state
+            .storage
+            .begin_session("s1", "COM1", "port:COM1", None, None, None, 9600, "{}")
+            .await
+            .unwrap();
+
+        start_log_tail(app.handle().clone(), app.state(), "s1".to_string())
+            .await
+            .unwrap();
+        assert!(state.log_tails.contains_key("s1"));
+
+        state.storage.delete_session("s1").await.unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while state.log_tails.contains_key("s1") {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("log tail was not removed");
+
+        // Restarting the tail subscribes again instead of finding the closed one
+        start_log_tail(app.handle().clone(), app.state(), "s1".to_string())
+            .await
+            .unwrap();
+        assert!(state.log_tails.contains_key("s1"));
+        stop_log_tail(app.state(), "s1".to_string()).await.unwrap();
+    }
 }
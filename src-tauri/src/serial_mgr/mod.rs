@@ -2,8 +2,18 @@ pub mod close_port;
 pub mod execute_saved_command;
 pub mod helpers;
 pub mod log;
+pub mod modem_status;
 pub mod open_port;
+pub mod open_port_params;
+pub mod port_capabilities;
+pub mod port_permissions;
 pub mod port_task;
+pub mod port_task_metrics;
+pub mod prometheus;
+pub mod server_time;
+pub mod session;
 pub mod storage;
 pub mod update_ports;
+#[cfg(test)]
+pub mod virtual_port;
 pub mod write_port;
@@ -0,0 +1,18 @@
+//! Modem status line queries.
+
+use crate::serial_mgr::port_task::ModemStatus;
+use crate::state::AppState;
+
+/// Returns the CTS/DSR/CD/RING state last polled from an open port.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn get_modem_status(
+    state: tauri::State<'_, AppState>,
+    port_name: String,
+) -> Result<ModemStatus, String> {
+    let handles = state.port_handles.get(&port_name).ok_or_else(|| {
+        tracing::error!("get modem status of port that is not open: {}", port_name);
+        format!("{} is not open", port_name)
+    })?;
+    let status = *handles.modem_status_rx.borrow();
+    Ok(status)
+}
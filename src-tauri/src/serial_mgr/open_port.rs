@@ -1,119 +1,430 @@
+use std::collections::VecDeque;
 use std::time::Duration;
 
 use rootcause::Report;
-use tauri::{AppHandle, Emitter, Manager};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use tokio_serial::SerialPort;
 use tokio_stream::{wrappers::WatchStream, StreamExt};
 use tracing::Instrument;
 
 use dashmap::mapref::entry::Entry;
 
 use crate::{
-    events::{event_names, PortOpenedEvent},
-    serial::{data_bits::DataBits, flow_control::FlowControl, parity::Parity, stop_bits::StopBits},
+    error::{diagnose_open_failure, OpenFailureDiagnostic, SerialError},
+    events::{
+        event_names, PortBackpressureEvent, PortClosedEvent, PortErrorEvent,
+        PortEventsDroppedEvent, PortOpenedEvent, PortReconnectedEvent,
+    },
+    serial::{
+        data_bits::DataBits, flow_control::FlowControl, parity::Parity, port_type::PortType,
+        read_framing::ReadFraming, read_strategy::ReadStrategy, read_transform::ReadTransform,
+        reconnect_policy::ReconnectPolicy, stop_bits::StopBits, write_mode::WriteMode,
+        write_transform::WriteTransform,
+    },
     serial_mgr::{
-        port_task::{spawn_serial_task, SerialEvent, WritePortSender},
-        storage::generate_device_fingerprint,
+        helpers::{emit_port_event, timestamp_now_ms},
+        open_port_params::ValidatedOpenPortParams,
+        port_task::{spawn_serial_task, PortIo, Reconnect, SerialEvent, WriteStatus},
+        port_task_metrics::PortTaskMetricsHandle,
+        session::{generate_session_id, PortSession},
+        storage::{AuditEventType, InsertParams, Storage},
         update_ports::update_available_ports,
     },
     state::{AppState, OpenedPortProfile, PortHandles, PortStatus},
 };
 
-fn generate_session_id() -> String {
-    uuid::Uuid::new_v4().to_string()
-}
-
 /// Result of opening a port, containing the session ID for log queries
 #[derive(serde::Serialize)]
 pub struct OpenPortResult {
     pub session_id: String,
+    /// Non-fatal issues found while validating the parameters
+    pub warnings: Vec<String>,
 }
 
-fn setup_port_task(
+/// Error returned by `open_port`.
+///
+/// `diagnostic` explains failures to open the port itself, `None` for other errors.
+#[derive(Debug, serde::Serialize)]
+pub struct OpenPortError {
+    pub error: String,
+    pub diagnostic: Option<OpenFailureDiagnostic>,
+}
+
+impl OpenPortError {
+    fn open_failed(port_name: &str, source: tokio_serial::Error) -> Self {
+        let diagnostic = diagnose_open_failure(port_name, &source);
+        Self {
+            error: SerialError::from_open_error(port_name, source).to_string(),
+            diagnostic: Some(diagnostic),
+        }
+    }
+}
+
+impl From<String> for OpenPortError {
+    fn from(error: String) -> Self {
+        Self {
+            error,
+            diagnostic: None,
+        }
+    }
+}
+
+impl From<Report> for OpenPortError {
+    fn from(report: Report) -> Self {
+        report.to_string().into()
+    }
+}
+
+/// Writes the buffered read entries in one batch, with one `read_received`
+/// audit entry per session. Failures are only logged.
+async fn flush_read_logs(storage: &Storage, port_name: &str, pending: &mut VecDeque<InsertParams>) {
+    if pending.is_empty() {
+        return;
+    }
+    let entries: Vec<InsertParams> = pending.drain(..).collect();
+    let count = entries.len();
+    // Message and byte counts per session, a flush spans a rotation at most once
+    let mut received: Vec<(String, usize, usize)> = Vec::new();
+    for entry in &entries {
+        match received.last_mut() {
+            Some((session_id, messages, bytes)) if *session_id == entry.session_id => {
+                *messages += 1;
+                *bytes += entry.data.len();
+            }
+            _ => received.push((entry.session_id.clone(), 1, entry.data.len())),
+        }
+    }
+    if let Err(e) = storage.insert_batch(entries).await {
+        tracing::error!("Failed to log {} reads: {}", count, e);
+        return;
+    }
+    for (session_id, messages, bytes) in received {
+        let details = serde_json::json!({ "messages": messages, "bytes": bytes });
+        if let Err(e) = storage
+            .record_audit(
+                AuditEventType::ReadReceived,
+                port_name,
+                &session_id,
+                details,
+            )
+            .await
+        {
+            tracing::error!("Failed to record read_received audit event: {}", e);
+        }
+    }
+}
+
+/// Records an audit event in the port's current session, failures are only logged.
+async fn record_audit(
+    storage: &Storage,
+    event_type: AuditEventType,
+    port_name: &str,
+    session: &PortSession,
+    details: serde_json::Value,
+) {
+    if let Err(e) = storage
+        .record_audit(event_type, port_name, &session.current_id(), details)
+        .await
+    {
+        tracing::error!(
+            "Failed to record {} audit event: {}",
+            event_type.as_str(),
+            e
+        );
+    }
+}
+
+/// Starts the port task and the tasks forwarding its events.
+///
+/// `reopen` opens the port again when the profile's reconnect policy allows it.
+pub(crate) fn setup_port_task<R, P, F>(
     port_name: String,
-    port: tokio_serial::SerialStream,
-    app: AppHandle,
-    device_fingerprint: String,
-) -> Result<(WritePortSender, String), Report> {
-    let session_id = generate_session_id();
+    port: P,
+    reopen: F,
+    app: AppHandle<R>,
+    port_type: PortType,
+    profile: OpenedPortProfile,
+) -> Result<(PortHandles, String), Report>
+where
+    R: Runtime,
+    P: PortIo,
+    F: FnMut() -> std::io::Result<P> + Send + 'static,
+{
+    let session_id = profile.session_id.clone();
+    let baud_rate = profile.baud_rate;
+    let session = std::sync::Arc::new(PortSession::new(port_name.clone(), port_type, &profile)?);
     let span = tracing::debug_span!("port name", port_name);
-    let (write_tx, mut read_rx, status_rx, mut write_notifier_rx) =
-        spawn_serial_task(port_name.clone(), port);
+    let metrics = PortTaskMetricsHandle::default();
+    let shutdown = tokio_util::sync::CancellationToken::new();
+    let (write_tx, mut read_rx, status_rx, mut write_notifier_rx) = spawn_serial_task(
+        port_name.clone(),
+        port,
+        profile.write_mode,
+        profile.rs485_mode,
+        profile.read_strategy,
+        profile.read_framing,
+        profile.read_transform.clone(),
+        profile.read_buffer_size,
+        Reconnect {
+            policy: profile.reconnect_policy,
+            open: reopen,
+        },
+        metrics.clone(),
+        shutdown.clone(),
+    );
+    // Log inserts must wait until the session row exists
+    let (session_ready_tx, session_ready_rx) = tokio::sync::oneshot::channel::<()>();
     let app_for_read = app.clone();
     let port_name_for_read = port_name.clone();
-    let session_id_for_read = session_id.clone();
-    let fingerprint_for_read = device_fingerprint.clone();
+    let session_for_read = session.clone();
     tokio::spawn(
         async move {
-            while let Some(message) = read_rx.recv().await {
+            let Some(state) = app_for_read.try_state::<AppState>() else {
+                tracing::warn!("app state gone, port read task exiting");
+                return;
+            };
+            let storage = state.storage.clone();
+            if let Err(e) = session_for_read
+                .begin(&storage, &session_for_read.current_id())
+                .await
+            {
+                tracing::error!("Failed to begin session: {}", e);
+            }
+            let _ = session_ready_tx.send(());
+            record_audit(
+                &storage,
+                AuditEventType::PortOpened,
+                &port_name_for_read,
+                &session_for_read,
+                serde_json::json!({ "baudRate": baud_rate }),
+            )
+            .await;
+
+            // Read entries are buffered and written in batches to keep up with fast ports
+            let mut pending: VecDeque<InsertParams> = VecDeque::new();
+            let mut flush_timer = tokio::time::interval(Duration::from_millis(
+                crate::constants::storage::LOG_BATCH_FLUSH_INTERVAL_MS,
+            ));
+            flush_timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            // Set aside while reconnecting, restored once the port is back
+            let mut suspended_profile: Option<OpenedPortProfile> = None;
+            loop {
+                let message = tokio::select! {
+                    message = read_rx.recv() => message,
+                    _ = flush_timer.tick(), if !pending.is_empty() => {
+                        flush_read_logs(&storage, &port_name_for_read, &mut pending).await;
+                        continue;
+                    }
+                };
+                let Some(message) = message else {
+                    break;
+                };
                 match message {
                     SerialEvent::Message(message) => {
                         let len = message.data.len();
                         let ts = message.timestamp_ms as i64;
-                        if let Err(err) = app_for_read.emit(event_names::PORT_READ, message.clone())
-                        {
+                        if let Err(err) = emit_port_event(
+                            &app_for_read,
+                            event_names::PORT_READ,
+                            &port_name_for_read,
+                            message.clone(),
+                        ) {
                             tracing::error!("emit port read failed: {}", err);
                         }
 
-                        let storage = app_for_read.state::<AppState>().storage.clone();
-                        let _ = storage
-                            .insert(
-                                &fingerprint_for_read,
-                                &session_id_for_read,
-                                None,
-                                None,
-                                None,
-                                &port_name_for_read,
-                                "RX",
-                                message.data.as_slice(),
-                                Some(ts),
-                            )
-                            .await
-                            .map_err(|e| tracing::error!("Failed to log read: {}", e));
-
-                        if let Some(mut entry) = app_for_read
-                            .state::<AppState>()
-                            .ports
-                            .get_mut(&port_name_for_read)
-                        {
+                        pending.push_back(InsertParams {
+                            device_fingerprint: session_for_read.device_fingerprint.clone(),
+                            session_id: session_for_read.current_id(),
+                            vid: None,
+                            pid: None,
+                            serial_number: None,
+                            port_name: port_name_for_read.clone(),
+                            direction: "RX".to_string(),
+                            data: message.data,
+                            timestamp_ms: Some(ts),
+                            message_id: None,
+                        });
+                        if pending.len() >= crate::constants::storage::LOG_BATCH_MAX_ROWS {
+                            flush_read_logs(&storage, &port_name_for_read, &mut pending).await;
+                        }
+
+                        if let Some(mut entry) = state.ports.get_mut(&port_name_for_read) {
                             entry.bytes_read += len as u128;
                             tracing::debug!(
                                 "update bytes read: {}, total: {}",
                                 len,
                                 entry.bytes_read
                             );
+                        };
+                    }
+                    SerialEvent::Break { duration_hint_ms } => {
+                        if let Err(emit_err) = emit_port_event(
+                            &app_for_read,
+                            event_names::PORT_ERROR,
+                            &port_name_for_read,
+                            PortErrorEvent::break_condition(
+                                port_name_for_read.clone(),
+                                duration_hint_ms,
+                            ),
+                        ) {
+                            tracing::error!("emit port break failed: {}", emit_err);
+                        }
+                    }
+                    SerialEvent::TransformFailed(error) => {
+                        record_audit(
+                            &storage,
+                            AuditEventType::Error,
+                            &port_name_for_read,
+                            &session_for_read,
+                            serde_json::json!({ "code": "readTransform", "message": error }),
+                        )
+                        .await;
+                        if let Err(emit_err) = emit_port_event(
+                            &app_for_read,
+                            event_names::PORT_ERROR,
+                            &port_name_for_read,
+                            PortErrorEvent::read_transform(port_name_for_read.clone(), error),
+                        ) {
+                            tracing::error!("emit read transform error failed: {}", emit_err);
+                        }
+                    }
+                    SerialEvent::Backpressure {
+                        queue_depth,
+                        capacity,
+                    } => {
+                        if let Err(emit_err) = emit_port_event(
+                            &app_for_read,
+                            event_names::PORT_BACKPRESSURE,
+                            &port_name_for_read,
+                            PortBackpressureEvent::new(
+                                port_name_for_read.clone(),
+                                queue_depth,
+                                capacity,
+                            ),
+                        ) {
+                            tracing::error!("emit port backpressure failed: {}", emit_err);
+                        }
+                    }
+                    SerialEvent::EventsDropped { dropped_count } => {
+                        record_audit(
+                            &storage,
+                            AuditEventType::EventsDropped,
+                            &port_name_for_read,
+                            &session_for_read,
+                            serde_json::json!({ "droppedCount": dropped_count }),
+                        )
+                        .await;
+                        if let Err(emit_err) = emit_port_event(
+                            &app_for_read,
+                            event_names::PORT_EVENTS_DROPPED,
+                            &port_name_for_read,
+                            PortEventsDroppedEvent::new(port_name_for_read.clone(), dropped_count),
+                        ) {
+                            tracing::error!("emit port events dropped failed: {}", emit_err);
+                        }
+                    }
+                    SerialEvent::Reconnecting {
+                        attempt,
+                        next_attempt_at_ms,
+                    } => {
+                        tracing::warn!("port connection lost, reconnect attempt {}", attempt);
+                        let Some(state) = app_for_read.try_state::<AppState>() else {
+                            continue;
+                        };
+                        if let Some(mut entry) = state.ports.get_mut(&port_name_for_read) {
+                            let reconnecting = PortStatus::Reconnecting {
+                                attempt,
+                                next_attempt_at_ms,
+                            };
+                            match std::mem::replace(&mut entry.port_status, reconnecting) {
+                                PortStatus::Opened(profile) => suspended_profile = Some(profile),
+                                PortStatus::Reconnecting { .. } => {}
+                                // Closing, leave it to the port task's cleanup
+                                previous => entry.port_status = previous,
+                            }
+                        };
+                    }
+                    SerialEvent::Reconnected { attempts } => {
+                        record_audit(
+                            &storage,
+                            AuditEventType::PortReconnected,
+                            &port_name_for_read,
+                            &session_for_read,
+                            serde_json::json!({ "attempts": attempts }),
+                        )
+                        .await;
+                        let state = app_for_read.try_state::<AppState>();
+                        if let (Some(state), Some(profile)) = (&state, suspended_profile.take()) {
+                            if let Some(mut entry) = state.ports.get_mut(&port_name_for_read) {
+                                if matches!(entry.port_status, PortStatus::Reconnecting { .. }) {
+                                    entry.port_status = PortStatus::Opened(profile);
+                                }
+                            }
+                        }
+                        if let Err(emit_err) = emit_port_event(
+                            &app_for_read,
+                            event_names::PORT_RECONNECTED,
+                            &port_name_for_read,
+                            PortReconnectedEvent::new(port_name_for_read.clone(), attempts),
+                        ) {
+                            tracing::error!("emit port reconnected failed: {}", emit_err);
                         }
                     }
                     SerialEvent::Error(err) => {
-                        if let Err(emit_err) =
-                            app_for_read.emit(event_names::PORT_ERROR, err.to_string())
-                        {
+                        record_audit(
+                            &storage,
+                            AuditEventType::Error,
+                            &port_name_for_read,
+                            &session_for_read,
+                            serde_json::json!({ "code": "io", "message": err.to_string() }),
+                        )
+                        .await;
+                        if let Err(emit_err) = emit_port_event(
+                            &app_for_read,
+                            event_names::PORT_ERROR,
+                            &port_name_for_read,
+                            PortErrorEvent::io(port_name_for_read.clone(), &err),
+                        ) {
                             tracing::error!("emit port error failed: {}", emit_err);
                         }
                         tracing::error!("serial port error: {}", err);
+                        // The port task exits after an IO error
+                        if let Err(emit_err) = app_for_read.emit(
+                            event_names::PORT_CLOSED,
+                            PortClosedEvent::connection_lost(port_name_for_read.clone()),
+                        ) {
+                            tracing::error!("emit port closed event failed: {}", emit_err);
+                        }
                     }
                 }
             }
+            flush_read_logs(&storage, &port_name_for_read, &mut pending).await;
             tracing::info!("port read closed");
         }
         .instrument(span.clone()),
     );
+    let modem_status_rx = status_rx.clone();
     let app_for_status = app.clone();
     let port_name_for_read = port_name.clone();
     tokio::spawn(
         async move {
             let mut rx = WatchStream::new(status_rx);
             while let Some(status) = rx.next().await {
-                if let Some(mut entry) = app_for_status
-                    .state::<AppState>()
-                    .ports
-                    .get_mut(&port_name_for_read)
-                {
+                let Some(state) = app_for_status.try_state::<AppState>() else {
+                    break;
+                };
+                if let Some(mut entry) = state.ports.get_mut(&port_name_for_read) {
                     match &mut entry.port_status {
-                        PortStatus::Closed => {
+                        PortStatus::Closed | PortStatus::Disconnected => {
                             tracing::error!(
                                 "invalid port state, got status update while port is closed"
                             );
                         }
+                        PortStatus::Opening
+                        | PortStatus::Closing
+                        | PortStatus::Reconnecting { .. } => {
+                            tracing::trace!("skip status update, port is not settled");
+                        }
                         PortStatus::Opened(port_status) => {
                             let changed = port_status.carrier_detect != status.cd
                                 || port_status.clear_to_send != status.cts
@@ -134,7 +445,7 @@ fn setup_port_task(
                             }
                         }
                     }
-                }
+                };
             }
             tracing::info!("status update closed");
         }
@@ -142,16 +453,70 @@ fn setup_port_task(
     );
     let app_for_write = app.clone();
     let port_name_for_write = port_name.clone();
-    let session_id_for_write = session_id.clone();
-    let fingerprint_for_write = device_fingerprint.clone();
-    tokio::spawn(
+    let session_for_write = session.clone();
+    let task = tokio::spawn(
         async move {
-            while let Some(len) = write_notifier_rx.recv().await {
-                if let Some(mut entry) = app_for_write
-                    .state::<AppState>()
-                    .ports
-                    .get_mut(&port_name_for_write)
-                {
+            // Resolves with an error if the read task is gone, either way the session is settled
+            let _ = session_ready_rx.await;
+            while let Some(status) = write_notifier_rx.recv().await {
+                let (result, data) = match status {
+                    WriteStatus::Sending(event) => {
+                        if let Some(state) = app_for_write.try_state::<AppState>() {
+                            record_audit(
+                                &state.storage,
+                                AuditEventType::WriteStarted,
+                                &port_name_for_write,
+                                &session_for_write,
+                                serde_json::json!({
+                                    "messageId": event.message_id,
+                                    "bytes": event.data_len,
+                                }),
+                            )
+                            .await;
+                        }
+                        if let Err(err) = emit_port_event(
+                            &app_for_write,
+                            event_names::PORT_WRITE_SENDING,
+                            &port_name_for_write,
+                            event,
+                        ) {
+                            tracing::error!("emit port write sending failed: {}", err);
+                        }
+                        continue;
+                    }
+                    WriteStatus::Progress(event) => {
+                        if let Err(err) = emit_port_event(
+                            &app_for_write,
+                            event_names::PORT_WRITE_PROGRESS,
+                            &port_name_for_write,
+                            event,
+                        ) {
+                            tracing::error!("emit port write progress failed: {}", err);
+                        }
+                        continue;
+                    }
+                    WriteStatus::Done { result, data } => (result, data),
+                };
+                let len = result.actual_bytes_written;
+                let message_id = result.message_id.clone();
+                let Some(state) = app_for_write.try_state::<AppState>() else {
+                    tracing::warn!("app state gone, dropping write result");
+                    continue;
+                };
+                record_audit(
+                    &state.storage,
+                    AuditEventType::WriteCompleted,
+                    &port_name_for_write,
+                    &session_for_write,
+                    serde_json::json!({
+                        "messageId": message_id,
+                        "success": result.success,
+                        "latencyMs": result.latency_ms,
+                        "bytes": len,
+                    }),
+                )
+                .await;
+                if let Some(mut entry) = state.ports.get_mut(&port_name_for_write) {
                     entry.bytes_write += len as u128;
                     tracing::debug!(
                         "update port bytes written: {}, total: {}",
@@ -159,83 +524,195 @@ fn setup_port_task(
                         entry.bytes_write
                     );
                 }
+                if let Err(err) = emit_port_event(
+                    &app_for_write,
+                    event_names::PORT_WRITE_RESULT,
+                    &port_name_for_write,
+                    result,
+                ) {
+                    tracing::error!("emit port write result failed: {}", err);
+                }
 
-                let storage = app_for_write.state::<AppState>().storage.clone();
-                let msg = format!("<{} bytes written>", len).into_bytes();
-                let _ = storage
+                let _ = state
+                    .storage
                     .insert(
-                        &fingerprint_for_write,
-                        &session_id_for_write,
+                        &session_for_write.device_fingerprint,
+                        &session_for_write.current_id(),
                         None,
                         None,
                         None,
                         &port_name_for_write,
                         "TX",
-                        &msg,
+                        &data,
                         None,
+                        Some(&message_id),
                     )
                     .await
                     .map_err(|e| tracing::error!("Failed to log write: {}", e));
             }
             // Port closed: update status and remove handle
-            if let Some(mut entry) = app_for_write
-                .state::<AppState>()
-                .ports
-                .get_mut(&port_name_for_write)
-            {
+            let Some(state) = app_for_write.try_state::<AppState>() else {
+                tracing::warn!("app state gone, skip port cleanup");
+                return;
+            };
+            if let Some(mut entry) = state.ports.get_mut(&port_name_for_write) {
                 entry.port_status = PortStatus::Closed;
             }
             tracing::info!("reset port state to closed");
-            app_for_write
-                .state::<AppState>()
-                .port_handles
-                .remove(&port_name_for_write);
+            state.port_handles.remove(&port_name_for_write);
             tracing::info!("remove port handle, port write closed");
+            record_audit(
+                &state.storage,
+                AuditEventType::PortClosed,
+                &port_name_for_write,
+                &session_for_write,
+                serde_json::json!({}),
+            )
+            .await;
+            if let Err(e) = state
+                .storage
+                .end_session(&session_for_write.current_id())
+                .await
+            {
+                tracing::error!("Failed to end session: {}", e);
+            }
         }
         .instrument(span),
     );
 
-    Ok((write_tx, session_id))
+    Ok((
+        PortHandles {
+            write_port_tx: write_tx,
+            metrics,
+            modem_status_rx,
+            session,
+            opened_at_ms: timestamp_now_ms(),
+            shutdown,
+            task: Some(task),
+        },
+        session_id,
+    ))
+}
+
+/// Settings a port is opened with, kept by the port task to reopen it.
+#[derive(Debug, Clone)]
+pub struct PortOpenParams {
+    pub port_name: String,
+    pub baud_rate: u32,
+    pub data_bits: DataBits,
+    pub flow_control: FlowControl,
+    pub parity: Parity,
+    pub stop_bits: StopBits,
+    pub timeout: Duration,
+    pub data_terminal_ready: bool,
+    pub rts_on_open: bool,
+}
+
+impl PortOpenParams {
+    pub fn new(
+        port_name: String,
+        profile: &OpenedPortProfile,
+        data_terminal_ready: bool,
+        rts_on_open: bool,
+    ) -> Self {
+        Self {
+            port_name,
+            baud_rate: profile.baud_rate,
+            data_bits: profile.data_bits,
+            flow_control: profile.flow_control,
+            parity: profile.parity,
+            stop_bits: profile.stop_bits,
+            timeout: Duration::from_millis(profile.timeout_ms),
+            data_terminal_ready,
+            rts_on_open,
+        }
+    }
+
+    /// Opens the port with these settings.
+    pub fn open(&self) -> tokio_serial::Result<tokio_serial::SerialStream> {
+        let builder = tokio_serial::new(self.port_name.clone(), self.baud_rate)
+            .data_bits(self.data_bits.into())
+            .flow_control(self.flow_control.into())
+            .parity(self.parity.into())
+            .stop_bits(self.stop_bits.into())
+            .dtr_on_open(self.data_terminal_ready)
+            .timeout(self.timeout);
+        let mut port = tokio_serial::SerialStream::open(&builder)?;
+        if self.rts_on_open {
+            // Some RS-485 adapters need RTS set before the first transmission
+            port.write_request_to_send(true)?;
+        }
+        Ok(port)
+    }
 }
 
+#[tracing::instrument(name = "port name", level = "debug", skip_all, fields(%port_name))]
 pub fn open_port_unchecked(
     port_name: String,
-    baud_rate: u32,
-    data_bits: DataBits,
-    flow_control: FlowControl,
-    parity: Parity,
-    stop_bits: StopBits,
+    profile: OpenedPortProfile,
     data_terminal_ready: bool,
-    timeout: Duration,
+    rts_on_open: bool,
+    port_type: PortType,
     app: AppHandle,
-    device_fingerprint: String,
-) -> Result<(WritePortSender, String), Report> {
-    let span = tracing::debug_span!("port name", port_name);
-    let _guard = span.enter();
-    let builder = tokio_serial::new(port_name.clone(), baud_rate)
-        .data_bits(data_bits.into())
-        .flow_control(flow_control.into())
-        .parity(parity.into())
-        .stop_bits(stop_bits.into())
-        .dtr_on_open(data_terminal_ready)
-        .timeout(timeout);
-    let port = tokio_serial::SerialStream::open(&builder)?;
-    tracing::info!("serial port: {} opened with baud_rate: {}, flow_control: {}, parity: {}, stop_bits: {}, timeout_nanos: {}", port_name, baud_rate, flow_control, parity, stop_bits, timeout.as_nanos());
-    let (write_tx, session_id) =
-        setup_port_task(port_name.clone(), port, app.clone(), device_fingerprint)?;
-    if let Err(err) = app.emit(
-        event_names::PORT_OPENED,
-        PortOpenedEvent::new(port_name.clone()),
-    ) {
-        tracing::error!("emit port opened event failed: {}", err);
-        return Err(err.into());
+) -> Result<(PortHandles, String), OpenPortError> {
+    let params = PortOpenParams::new(
+        port_name.clone(),
+        &profile,
+        data_terminal_ready,
+        rts_on_open,
+    );
+    let port = params
+        .open()
+        .map_err(|err| OpenPortError::open_failed(&port_name, err))?;
+    tracing::info!("serial port: {} opened with baud_rate: {}, flow_control: {}, parity: {}, stop_bits: {}, timeout_nanos: {}, write_mode: {}, read_strategy: {}, read_framing: {}, reconnect_policy: {}", port_name, profile.baud_rate, profile.flow_control, profile.parity, profile.stop_bits, params.timeout.as_nanos(), profile.write_mode, profile.read_strategy, profile.read_framing, profile.reconnect_policy);
+    let reopen = move || params.open().map_err(std::io::Error::from);
+    let (handles, session_id) = setup_port_task(port_name, port, reopen, app, port_type, profile)?;
+    Ok((handles, session_id))
+}
+
+/// Moves a closed port to `Opening`, returning the status it had.
+///
+/// Fails if the port is open or another open or close is in progress.
+fn begin_opening(state: &AppState, port_name: &str) -> Result<PortStatus, String> {
+    let Some(mut entry) = state.ports.get_mut(port_name) else {
+        return Ok(PortStatus::Closed);
+    };
+    match &entry.port_status {
+        PortStatus::Closed | PortStatus::Disconnected => Ok(std::mem::replace(
+            &mut entry.port_status,
+            PortStatus::Opening,
+        )),
+        PortStatus::Opening => Err(format!("{} is already opening", port_name)),
+        PortStatus::Closing => Err(format!("{} is still closing", port_name)),
+        PortStatus::Opened(_) | PortStatus::Reconnecting { .. } => {
+            Err(format!("{} already opened", port_name))
+        }
     }
+}
 
-    Ok((write_tx, session_id))
+/// Returns the type of `port_name` if the OS currently reports it.
+///
+/// The port list is rescanned first: the background scan may not have picked
+/// up a freshly plugged device yet, or may still list one that was unplugged.
+/// A missing port fails here with `PortNotFound` instead of a cryptic OS error
+/// from opening it.
+async fn available_port_type(
+    app: &AppHandle,
+    state: &tauri::State<'_, AppState>,
+    port_name: &str,
+) -> Result<PortType, Report> {
+    update_available_ports(app, state).await?;
+    state
+        .ports
+        .get(port_name)
+        .map(|entry| entry.port_type.clone())
+        .ok_or_else(|| SerialError::PortNotFound(port_name.to_string()).into())
 }
 
 // remember to call `.manage(MyState::default())`
 #[tauri::command(rename_all = "camelCase")]
+#[tracing::instrument(name = "open port", level = "debug", skip_all, fields(%port_name))]
+#[allow(clippy::too_many_arguments)]
 pub async fn open_port(
     state: tauri::State<'_, AppState>,
     app: AppHandle,
@@ -246,92 +723,483 @@ pub async fn open_port(
     parity: String,
     stop_bits: String,
     data_terminal_ready: bool,
-    timeout_ms: u64,
-) -> Result<OpenPortResult, String> {
-    let span = tracing::debug_span!("open port", port_name);
-    let _guard = span.enter();
+    timeout_ms: Option<u64>,
+    write_mode: Option<WriteMode>,
+    read_strategy: Option<ReadStrategy>,
+    read_framing: Option<ReadFraming>,
+    read_transform: Option<ReadTransform>,
+    dtr_on_close: Option<bool>,
+    rts_on_open: Option<bool>,
+    rs485_mode: Option<bool>,
+    read_buffer_size: Option<usize>,
+    reconnect_policy: Option<ReconnectPolicy>,
+) -> Result<OpenPortResult, OpenPortError> {
+    let write_mode = write_mode.unwrap_or_default();
+    let (default_timeout_ms, max_open_ports) = {
+        let config = state.config.read();
+        (config.default_timeout_ms, config.max_open_ports)
+    };
+    let timeout_ms = timeout_ms.unwrap_or(default_timeout_ms);
+    let read_strategy = read_strategy.unwrap_or_default();
+    let read_framing = read_framing.unwrap_or_default();
+    let read_buffer_size = read_buffer_size.unwrap_or(crate::constants::serial::READ_BUFFER_SIZE);
+    if !(1..=crate::constants::serial::MAX_READ_BUFFER_SIZE).contains(&read_buffer_size) {
+        tracing::error!("invalid read buffer size: {}", read_buffer_size);
+        return Err(format!(
+            "read buffer size must be 1-{} bytes, got {}",
+            crate::constants::serial::MAX_READ_BUFFER_SIZE,
+            read_buffer_size
+        )
+        .into());
+    }
     tracing::info!(
         "open port request, baud rate: {}, data bits: {}, flow control: {}, parity: {}, stop_bits: {}, data treminal ready: {}, timeout: {}",
         baud_rate, data_bits, flow_control, parity, stop_bits, data_terminal_ready, timeout_ms);
-    let data_bits: DataBits = data_bits.parse().map_err(|err: Report| {
-        tracing::error!("invalid data bits: {}", err);
-        err.to_string()
-    })?;
-    let flow_control: FlowControl = flow_control.parse().map_err(|err: Report| {
-        tracing::error!("invalid flow control: {}", err);
-        err.to_string()
-    })?;
-    let parity: Parity = parity.parse().map_err(|err: Report| {
-        tracing::error!("invalid parity: {}", err);
-        err.to_string()
-    })?;
-    let stop_bits: StopBits = stop_bits.parse().map_err(|err: Report| {
-        tracing::error!("invalid stop bits: {}", err);
-        err.to_string()
-    })?;
-    update_available_ports(&state).await.map_err(|err| {
-        tracing::error!("update available ports failed: {}", err);
+    let (
+        ValidatedOpenPortParams {
+            baud_rate,
+            data_bits,
+            flow_control,
+            parity,
+            stop_bits,
+        },
+        warnings,
+    ) = ValidatedOpenPortParams::from_raw(
+        baud_rate,
+        &data_bits,
+        &flow_control,
+        &parity,
+        &stop_bits,
+    )
+    .map_err(|err| {
+        tracing::error!("invalid open port params: {}", err);
         err.to_string()
     })?;
+    for warning in &warnings {
+        tracing::warn!("open port params: {}", warning);
+    }
+    // Check port exists and get its type for the device fingerprint
+    let port_type = available_port_type(&app, &state, &port_name)
+        .await
+        .map_err(|err| {
+            tracing::error!("port not available: {}", err);
+            err.to_string()
+        })?;
 
-    // Check port exists and get device fingerprint
-    let device_fingerprint = state
-        .ports
-        .get(&port_name)
-        .map(|entry| generate_device_fingerprint(&port_name, &entry.port_type))
-        .ok_or_else(|| format!("no such port: {}", port_name))?;
-
-    // Use DashMap's entry() API for atomic check-and-insert to prevent TOCTOU race.
-    // The shard lock is held from the contains_key check through the insert, so two
-    // concurrent open_port calls on the same port cannot both pass the guard.
-    // open_port_unchecked is synchronous, so the guard is not held across .await points.
-    let vacant = match state.port_handles.entry(port_name.clone()) {
-        Entry::Occupied(_) => {
-            return Err(format!("{} already opened", port_name));
-        }
-        Entry::Vacant(entry) => entry,
-    };
-
-    // Open port (synchronous — safe to call while holding DashMap entry guard)
-    let (write_tx, session_id) = open_port_unchecked(
-        port_name.clone(),
+    let profile = OpenedPortProfile {
         baud_rate,
         data_bits,
-        flow_control,
-        parity,
         stop_bits,
-        data_terminal_ready,
-        std::time::Duration::from_millis(timeout_ms),
-        app,
-        device_fingerprint,
-    )
-    .map_err(|err| {
-        tracing::error!("open port failed with err: {}", err);
-        err.to_string()
-    })?;
-    tracing::info!("open port succeed");
+        parity,
+        flow_control,
+        carrier_detect: false,
+        clear_to_send: false,
+        data_set_ready: false,
+        ring_indicator: false,
+        timeout_ms,
+        write_mode,
+        read_strategy,
+        read_framing,
+        read_transform: read_transform.unwrap_or_default(),
+        write_transform: WriteTransform::default(),
+        read_buffer_size,
+        reconnect_policy: reconnect_policy.unwrap_or_default(),
+        dtr_on_close: dtr_on_close.unwrap_or(false),
+        rs485_mode: rs485_mode.unwrap_or(false),
+        session_id: generate_session_id(),
+    };
+
+    // A concurrent open or close of the same port now fails fast
+    let previous_status = begin_opening(&state, &port_name)?;
+
+    let opened = (|| -> Result<String, OpenPortError> {
+        // Checked before taking the entry guard below, len() locks every shard
+        if state.port_handles.len() >= max_open_ports {
+            tracing::error!("max open ports reached: {}", max_open_ports);
+            return Err(format!(
+                "cannot open {}: limit of {} open ports reached",
+                port_name, max_open_ports
+            )
+            .into());
+        }
+
+        // Use DashMap's entry() API for atomic check-and-insert to prevent TOCTOU race.
+        // The shard lock is held from the contains_key check through the insert, so two
+        // concurrent open_port calls on the same port cannot both pass the guard.
+        // open_port_unchecked is synchronous, so the guard is not held across .await points.
+        let vacant = match state.port_handles.entry(port_name.clone()) {
+            Entry::Occupied(_) => {
+                return Err(format!("{} already opened", port_name).into());
+            }
+            Entry::Vacant(entry) => entry,
+        };
+
+        // Open port (synchronous — safe to call while holding DashMap entry guard)
+        let (handles, session_id) = open_port_unchecked(
+            port_name.clone(),
+            profile.clone(),
+            data_terminal_ready,
+            rts_on_open.unwrap_or(false),
+            port_type,
+            app.clone(),
+        )
+        .inspect_err(|err| tracing::error!("open port failed with err: {}", err.error))?;
+        tracing::info!("open port succeed");
 
-    // Insert handle atomically (still holding the shard lock)
-    vacant.insert(PortHandles {
-        write_port_tx: write_tx,
-    });
-    tracing::info!("insert new port handle");
+        // Insert handle atomically (still holding the shard lock)
+        vacant.insert(handles);
+        tracing::info!("insert new port handle");
+        Ok(session_id)
+    })();
+    let session_id = match opened {
+        Ok(session_id) => session_id,
+        Err(err) => {
+            if let Some(mut entry) = state.ports.get_mut(&port_name) {
+                if matches!(entry.port_status, PortStatus::Opening) {
+                    entry.port_status = previous_status;
+                }
+            }
+            return Err(err);
+        }
+    };
 
     // Update port status
     if let Some(mut entry) = state.ports.get_mut(&port_name) {
-        entry.port_status = PortStatus::Opened(OpenedPortProfile {
-            baud_rate,
-            data_bits,
-            stop_bits,
-            parity,
-            flow_control,
-            carrier_detect: false,
-            clear_to_send: false,
-            data_set_ready: false,
-            ring_indicator: false,
-            timeout_ms,
-        });
+        entry.port_status = PortStatus::Opened(profile);
     }
     tracing::info!("set port state to opened");
-    Ok(OpenPortResult { session_id })
+
+    // Emitted before returning, so the frontend sees the event before it can use
+    // the session ID, and only once the port is registered as open
+    if let Err(err) = app.emit(
+        event_names::PORT_OPENED,
+        PortOpenedEvent::new(port_name.clone(), session_id.clone()),
+    ) {
+        // The port is open either way, failing the command would leak it
+        tracing::error!("emit port opened event failed: {}", err);
+    }
+    Ok(OpenPortResult {
+        session_id,
+        warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serial_mgr::helpers::{close_all_ports_blocking, send_command_with_ack};
+    use crate::serial_mgr::port_task::{WriteCmd, WritePortMessage};
+    use crate::serial_mgr::virtual_port::{create_virtual_port_pair, VirtualPort};
+    use crate::serial_mgr::write_port::write_port;
+    use crate::state::PortInfo;
+    use tauri::Listener;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Fails the test instead of hanging if `fut` does not complete.
+    async fn within<F: std::future::Future>(fut: F) -> F::Output {
+        tokio::time::timeout(Duration::from_secs(5), fut)
+            .await
+            .expect("timed out")
+    }
+
+    fn test_profile() -> OpenedPortProfile {
+        OpenedPortProfile {
+            baud_rate: 9600,
+            ..Default::default()
+        }
+    }
+
+    /// Runs a port task on a virtual port and registers it the way `open_port`
+    /// does, returning the other end of the pair.
+    fn register_virtual_port<R: Runtime>(
+        app: &tauri::App<R>,
+        state: &AppState,
+        port_name: &str,
+        profile: OpenedPortProfile,
+    ) -> VirtualPort {
+        let (port, peer) = create_virtual_port_pair();
+        let (handles, _) = setup_port_task(
+            port_name.to_string(),
+            port,
+            || Err(std::io::ErrorKind::NotFound.into()),
+            app.handle().clone(),
+            PortType::Unknown,
+            profile,
+        )
+        .unwrap();
+        state.port_handles.insert(port_name.to_string(), handles);
+        peer
+    }
+
+    // Multi-threaded because the default `Storage` blocks in place while it connects
+    #[tokio::test(flavor = "multi_thread")]
+    async fn virtual_port_traffic_is_emitted_and_logged() {
+        let app = tauri::test::mock_app();
+        app.manage(AppState::default());
+        let state = app.state::<AppState>();
+        let profile = test_profile();
+        state.ports.insert(
+            "virtual-a".to_string(),
+            PortInfo {
+                port_name: "virtual-a".to_string(),
+                port_type: PortType::Unknown,
+                port_status: PortStatus::Opened(profile.clone()),
+                bytes_read: 0,
+                bytes_write: 0,
+                last_seen_ms: None,
+            },
+        );
+        let (read_tx, mut read_rx) = tokio::sync::mpsc::unbounded_channel();
+        app.listen(event_names::PORT_READ, move |event| {
+            let _ = read_tx.send(event.payload().to_string());
+        });
+
+        let (port, mut peer) = create_virtual_port_pair();
+        let (handles, session_id) = setup_port_task(
+            "virtual-a".to_string(),
+            port,
+            || Err(std::io::ErrorKind::NotFound.into()),
+            app.handle().clone(),
+            PortType::Unknown,
+            profile,
+        )
+        .unwrap();
+
+        let cmd = WriteCmd::Message(WritePortMessage {
+            message_id: "m1".to_string(),
+            data: b"ping".to_vec(),
+            sequence_number: None,
+        });
+        within(send_command_with_ack(
+            &handles.write_port_tx,
+            cmd,
+            "write",
+            "virtual-a",
+        ))
+        .await
+        .unwrap();
+        let mut buf = [0u8; 4];
+        within(peer.read_exact(&mut buf)).await.unwrap();
+        assert_eq!(&buf, b"ping");
+
+        within(peer.write_all(b"pong")).await.unwrap();
+        let payload = within(read_rx.recv()).await.unwrap();
+        assert!(payload.contains("virtual-a"));
+
+        // Entries and counters are updated after the events are emitted
+        let logs = within(async {
+            loop {
+                let logs = state
+                    .storage
+                    .get_by_session(&session_id, 10, 0)
+                    .await
+                    .unwrap();
+                let counted = state
+                    .ports
+                    .get("virtual-a")
+                    .is_some_and(|entry| entry.bytes_read == 4 && entry.bytes_write == 4);
+                if logs.len() == 2 && counted {
+                    break logs;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await;
+        let rx = logs.iter().find(|entry| entry.direction == "RX").unwrap();
+        assert_eq!(rx.data, b"pong");
+        let tx = logs.iter().find(|entry| entry.direction == "TX").unwrap();
+        assert_eq!(tx.message_id.as_deref(), Some("m1"));
+        assert_eq!(tx.data, b"ping");
+
+        // Reads are audited per flushed batch, not per message
+        let received = within(async {
+            loop {
+                let audit = state
+                    .storage
+                    .get_audit_log(Some(&session_id), 10)
+                    .await
+                    .unwrap();
+                if let Some(entry) = audit
+                    .into_iter()
+                    .find(|entry| entry.event_type == "read_received")
+                {
+                    break entry;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await;
+        let details: serde_json::Value = serde_json::from_str(&received.details).unwrap();
+        assert_eq!(details, serde_json::json!({ "messages": 1, "bytes": 4 }));
+
+        within(send_command_with_ack(
+            &handles.write_port_tx,
+            WriteCmd::Close,
+            "close",
+            "virtual-a",
+        ))
+        .await
+        .unwrap();
+        within(async {
+            while !matches!(
+                state.ports.get("virtual-a").unwrap().port_status,
+                PortStatus::Closed
+            ) {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn begin_opening_rejects_ports_in_transition() {
+        let state = AppState::default();
+        state.ports.insert(
+            "COM1".to_string(),
+            PortInfo {
+                port_name: "COM1".to_string(),
+                port_type: PortType::Unknown,
+                port_status: PortStatus::Disconnected,
+                bytes_read: 0,
+                bytes_write: 0,
+                last_seen_ms: None,
+            },
+        );
+
+        let previous = begin_opening(&state, "COM1").unwrap();
+        assert!(matches!(previous, PortStatus::Disconnected));
+        assert!(matches!(
+            state.ports.get("COM1").unwrap().port_status,
+            PortStatus::Opening
+        ));
+        assert!(begin_opening(&state, "COM1").is_err());
+
+        state.ports.get_mut("COM1").unwrap().port_status = PortStatus::Closing;
+        assert!(begin_opening(&state, "COM1").is_err());
+        // Ports unknown to the scan, such as virtual ones, are not tracked
+        assert!(matches!(
+            begin_opening(&state, "virtual-a").unwrap(),
+            PortStatus::Closed
+        ));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn shutdown_from_async_context_releases_ports() {
+        let app = tauri::test::mock_app();
+        app.manage(AppState::default());
+        let state = app.state::<AppState>();
+        let profile = test_profile();
+        state.ports.insert(
+            "virtual-a".to_string(),
+            PortInfo {
+                port_name: "virtual-a".to_string(),
+                port_type: PortType::Unknown,
+                port_status: PortStatus::Opened(profile.clone()),
+                bytes_read: 0,
+                bytes_write: 0,
+                last_seen_ms: None,
+            },
+        );
+        let _peer = register_virtual_port(&app, &state, "virtual-a", profile);
+
+        // Must not panic with "Cannot start a runtime from within a runtime"
+        assert!(close_all_ports_blocking(&state, Duration::from_secs(5)));
+        assert!(state.port_handles.is_empty());
+        assert!(matches!(
+            state.ports.get("virtual-a").unwrap().port_status,
+            PortStatus::Closed
+        ));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn write_port_sends_in_sequence_and_skips_duplicates() {
+        let app = tauri::test::mock_app();
+        app.manage(AppState::default());
+        let state = app.state::<AppState>();
+        let profile = test_profile();
+        state.ports.insert(
+            "virtual-a".to_string(),
+            PortInfo {
+                port_name: "virtual-a".to_string(),
+                port_type: PortType::Unknown,
+                port_status: PortStatus::Opened(profile.clone()),
+                bytes_read: 0,
+                bytes_write: 0,
+                last_seen_ms: None,
+            },
+        );
+        let mut peer = register_virtual_port(&app, &state, "virtual-a", profile);
+        let write = |data: &[u8], message_id: &str, sequence_number: Option<u64>| {
+            write_port(
+                app.state(),
+                "virtual-a".to_string(),
+                data.to_vec(),
+                message_id.to_string(),
+                sequence_number,
+            )
+        };
+
+        // The second write arrives first and is held until the first one is written
+        let (second, first) = within(async {
+            tokio::join!(
+                write(b"world", "m1", Some(1)),
+                write(b"hello ", "m0", Some(0))
+            )
+        })
+        .await;
+        assert_eq!(first.unwrap().bytes_written, 6);
+        assert_eq!(second.unwrap().bytes_written, 5);
+        let mut buf = [0u8; 11];
+        within(peer.read_exact(&mut buf)).await.unwrap();
+        assert_eq!(&buf, b"hello world");
+
+        // A retried message ID is acknowledged without reaching the port
+        let retried = within(write(b"hello ", "m0", Some(0))).await.unwrap();
+        assert_eq!(retried.bytes_written, 0);
+        let next = within(write(b"!", "m2", None)).await.unwrap();
+        assert_eq!(next.bytes_written, 1);
+        let mut buf = [0u8; 1];
+        within(peer.read_exact(&mut buf)).await.unwrap();
+        assert_eq!(&buf, b"!");
+
+        // A write that fails can be retried with the same message ID
+        state.port_handles.clear();
+        assert!(within(write(b"?", "m3", None)).await.is_err());
+        assert!(state
+            .sent_message_ids
+            .lock()
+            .insert("m3", std::time::Instant::now()));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn failed_write_can_be_retried_with_same_message_id() {
+        let app = tauri::test::mock_app();
+        app.manage(AppState::default());
+        let state = app.state::<AppState>();
+        // Kept open after a failed write, as while waiting for a reconnect
+        let profile = OpenedPortProfile {
+            reconnect_policy: ReconnectPolicy::Infinite { interval_ms: 1000 },
+            ..test_profile()
+        };
+        let mut peer = register_virtual_port(&app, &state, "virtual-a", profile);
+        let write = || {
+            write_port(
+                app.state(),
+                "virtual-a".to_string(),
+                b"ping".to_vec(),
+                "m1".to_string(),
+                None,
+            )
+        };
+
+        peer.fail_writes(true);
+        assert!(within(write()).await.is_err());
+
+        peer.fail_writes(false);
+        let retried = within(write()).await.unwrap();
+        assert_eq!(retried.bytes_written, 4);
+        let mut buf = [0u8; 4];
+        within(peer.read_exact(&mut buf)).await.unwrap();
+        assert_eq!(&buf, b"ping");
+    }
 }
@@ -0,0 +1,162 @@
+//! Validation of `open_port` parameters before the OS is involved.
+
+use rootcause::Report;
+use thiserror::Error;
+
+use crate::{
+    constants::serial,
+    serial::{data_bits::DataBits, flow_control::FlowControl, parity::Parity, stop_bits::StopBits},
+};
+
+/// Reasons the parameters of an `open_port` request were rejected
+#[derive(Debug, Error)]
+pub enum OpenPortValidationError {
+    /// A string parameter could not be parsed
+    #[error("invalid {field}: {message}")]
+    Parse {
+        field: &'static str,
+        message: String,
+    },
+
+    /// Baud rate is outside the supported range
+    #[error(
+        "baud rate {0} out of range ({}..={})",
+        serial::MIN_BAUD_RATE,
+        serial::MAX_BAUD_RATE
+    )]
+    BaudRate(u32),
+
+    /// 5 data bits cannot be combined with 2 stop bits
+    #[error("five data bits cannot be used with two stop bits")]
+    FiveDataBitsTwoStopBits,
+}
+
+/// `open_port` parameters, parsed and checked for consistency
+#[derive(Debug, Clone, Copy)]
+pub struct ValidatedOpenPortParams {
+    pub baud_rate: u32,
+    pub data_bits: DataBits,
+    pub flow_control: FlowControl,
+    pub parity: Parity,
+    pub stop_bits: StopBits,
+}
+
+fn parse_field<T>(field: &'static str, raw: &str) -> Result<T, OpenPortValidationError>
+where
+    T: std::str::FromStr<Err = Report>,
+{
+    raw.parse()
+        .map_err(|err: Report| OpenPortValidationError::Parse {
+            field,
+            message: err.to_string(),
+        })
+}
+
+impl ValidatedOpenPortParams {
+    /// Parses the raw command arguments and rejects invalid combinations.
+    ///
+    /// Suspicious but valid combinations are returned as warnings.
+    pub fn from_raw(
+        baud_rate: u32,
+        data_bits: &str,
+        flow_control: &str,
+        parity: &str,
+        stop_bits: &str,
+    ) -> Result<(Self, Vec<String>), OpenPortValidationError> {
+        let data_bits: DataBits = parse_field("data bits", data_bits)?;
+        let flow_control: FlowControl = parse_field("flow control", flow_control)?;
+        let parity: Parity = parse_field("parity", parity)?;
+        let stop_bits: StopBits = parse_field("stop bits", stop_bits)?;
+
+        if !(serial::MIN_BAUD_RATE..=serial::MAX_BAUD_RATE).contains(&baud_rate) {
+            return Err(OpenPortValidationError::BaudRate(baud_rate));
+        }
+        if data_bits == DataBits::Five && stop_bits == StopBits::Two {
+            return Err(OpenPortValidationError::FiveDataBitsTwoStopBits);
+        }
+
+        let mut warnings = Vec::new();
+        if data_bits == DataBits::Seven && parity == Parity::None {
+            warnings
+                .push("seven data bits without parity is unusual, expected 7E1 or 7O1".to_string());
+        }
+
+        Ok((
+            Self {
+                baud_rate,
+                data_bits,
+                flow_control,
+                parity,
+                stop_bits,
+            },
+            warnings,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_out_of_range_baud_rate() {
+        let err = ValidatedOpenPortParams::from_raw(
+            serial::MAX_BAUD_RATE + 1,
+            "eight",
+            "none",
+            "none",
+            "one",
+        )
+        .unwrap_err();
+        assert!(
+            matches!(err, OpenPortValidationError::BaudRate(rate) if rate == serial::MAX_BAUD_RATE + 1)
+        );
+        assert!(matches!(
+            ValidatedOpenPortParams::from_raw(0, "eight", "none", "none", "one"),
+            Err(OpenPortValidationError::BaudRate(0))
+        ));
+    }
+
+    #[test]
+    fn rejects_five_data_bits_with_two_stop_bits() {
+        assert!(matches!(
+            ValidatedOpenPortParams::from_raw(9600, "five", "none", "none", "two"),
+            Err(OpenPortValidationError::FiveDataBitsTwoStopBits)
+        ));
+    }
+
+    #[test]
+    fn warns_about_seven_data_bits_without_parity() {
+        let (params, warnings) =
+            ValidatedOpenPortParams::from_raw(9600, "seven", "none", "none", "one").unwrap();
+        assert_eq!(params.data_bits, DataBits::Seven);
+        assert_eq!(warnings.len(), 1);
+
+        let (params, warnings) =
+            ValidatedOpenPortParams::from_raw(9600, "seven", "none", "even", "one").unwrap();
+        assert_eq!(params.parity, Parity::Even);
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn parse_error_names_the_field() {
+        let err =
+            ValidatedOpenPortParams::from_raw(9600, "eight", "none", "mark", "one").unwrap_err();
+        assert!(matches!(
+            err,
+            OpenPortValidationError::Parse {
+                field: "parity",
+                ..
+            }
+        ));
+        let err =
+            ValidatedOpenPortParams::from_raw(9600, "eight", "rts", "none", "one").unwrap_err();
+        assert!(matches!(
+            err,
+            OpenPortValidationError::Parse {
+                field: "flow control",
+                ..
+            }
+        ));
+    }
+}
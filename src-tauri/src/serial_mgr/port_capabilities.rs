@@ -0,0 +1,21 @@
+//! Port capability queries.
+
+use crate::serial::port_capabilities::PortCapabilities;
+use crate::state::AppState;
+
+/// Returns the settings the given port is known to support.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn get_port_capabilities(
+    state: tauri::State<'_, AppState>,
+    port_name: String,
+) -> Result<PortCapabilities, String> {
+    let port_type = state
+        .ports
+        .get(&port_name)
+        .map(|entry| entry.port_type.clone())
+        .ok_or_else(|| {
+            tracing::error!("get capabilities of unknown port: {}", port_name);
+            format!("no such port: {}", port_name)
+        })?;
+    Ok(PortCapabilities::for_port_type(&port_type))
+}
@@ -0,0 +1,20 @@
+//! Port permission checks.
+
+use crate::serial::port_permissions::{self, PermissionCheckResult};
+
+/// Reports whether the current user may open `port_name`, with suggested fixes.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn check_port_permissions(port_name: String) -> Result<PermissionCheckResult, String> {
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        port_permissions::check_port_permissions(&port_name)
+    })
+    .await
+    .map_err(|err| {
+        tracing::error!("check port permissions failed: {}", err);
+        err.to_string()
+    })?;
+    if !result.can_open {
+        tracing::warn!("port permission issues: {:?}", result.issues);
+    }
+    Ok(result)
+}
@@ -1,5 +1,11 @@
 use crate::constants::{channels, serial};
-use crate::util::AckSender;
+use crate::serial::{
+    read_framing::ReadFraming, read_strategy::ReadStrategy, read_transform::ReadTransform,
+    reconnect_policy::ReconnectPolicy, write_mode::WriteMode, write_transform::WriteTransform,
+};
+use crate::serial_mgr::helpers::timestamp_now_ms;
+use crate::serial_mgr::port_task_metrics::PortTaskMetricsHandle;
+use crate::util::{AckReceiver, AckSender, AckTx, WriteAck};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio_serial::SerialPort;
 
@@ -7,6 +13,8 @@ pub enum WriteCmd {
     Message(WritePortMessage),
     Rts(WritePortRequestToSend),
     Dtr(WritePortDataTerminalReady),
+    /// Replace the transform applied to subsequent messages.
+    SetTransform(WriteTransform),
     Close,
 }
 
@@ -14,6 +22,10 @@ pub enum WriteCmd {
 pub struct WritePortMessage {
     pub message_id: String,
     pub data: Vec<u8>,
+    /// Position in the order writes must reach the port, counted from 0 for
+    /// each opened port. `None` writes go out in arrival order.
+    #[serde(default)]
+    pub sequence_number: Option<u64>,
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
@@ -28,13 +40,386 @@ pub struct WritePortDataTerminalReady {
 
 pub type WritePortSender = AckSender<WriteCmd>;
 
-use crate::events::PortReadEvent;
+use crate::events::{
+    PortReadEvent, PortWriteProgressEvent, PortWriteResultEvent, PortWriteSendingEvent,
+};
 
 pub enum SerialEvent {
     Message(PortReadEvent),
+    /// Break condition on the line, the port stays open.
+    Break {
+        duration_hint_ms: Option<u64>,
+    },
+    /// The read transform rejected the data, which is forwarded raw next.
+    TransformFailed(String),
+    /// Fewer than [`channels::EVENT_BACKPRESSURE_THRESHOLD`] slots are left in
+    /// the event channel, reported once each time it fills up.
+    Backpressure {
+        queue_depth: usize,
+        capacity: usize,
+    },
+    /// Events dropped because the channel stayed full, reported once there is room again.
+    EventsDropped {
+        dropped_count: u64,
+    },
+    /// A read failed and the port is reopened at `next_attempt_at_ms`.
+    Reconnecting {
+        attempt: u32,
+        next_attempt_at_ms: u128,
+    },
+    /// The port was reopened on attempt `attempts`.
+    Reconnected {
+        attempts: u32,
+    },
     Error(std::io::Error),
 }
 
+/// Progress of a single write, reported by the port task.
+pub enum WriteStatus {
+    /// The write is about to start.
+    Sending(PortWriteSendingEvent),
+    /// Part of the write has been accepted by the port.
+    Progress(PortWriteProgressEvent),
+    /// The write has finished, successfully or not. `data` holds the bytes
+    /// the port accepted, after the write transform.
+    Done {
+        result: PortWriteResultEvent,
+        data: Vec<u8>,
+    },
+}
+
+/// Decides when a write reports progress, and estimates how long it has left.
+struct WriteProgress {
+    port_name: String,
+    message_id: String,
+    total_bytes: usize,
+    started: std::time::Instant,
+    reported_bytes: usize,
+    reported_at: std::time::Instant,
+}
+
+impl WriteProgress {
+    fn new(port_name: String, message_id: String, total_bytes: usize) -> Self {
+        let now = std::time::Instant::now();
+        Self {
+            port_name,
+            message_id,
+            total_bytes,
+            started: now,
+            reported_bytes: 0,
+            reported_at: now,
+        }
+    }
+
+    /// Event for `written` bytes done, if enough bytes or time passed since the last one.
+    fn update(&mut self, written: usize) -> Option<PortWriteProgressEvent> {
+        let now = std::time::Instant::now();
+        let interval = std::time::Duration::from_millis(serial::WRITE_PROGRESS_INTERVAL_MS);
+        if written - self.reported_bytes < serial::WRITE_PROGRESS_BYTES
+            && now - self.reported_at < interval
+        {
+            return None;
+        }
+        self.reported_bytes = written;
+        self.reported_at = now;
+        let remaining = self.total_bytes - written;
+        let elapsed_ms = (now - self.started).as_secs_f64() * 1000.0;
+        let estimated_remaining_ms = if remaining == 0 {
+            Some(0)
+        } else if written > 0 && elapsed_ms > 0.0 {
+            Some((remaining as f64 * elapsed_ms / written as f64) as u64)
+        } else {
+            None
+        };
+        Some(PortWriteProgressEvent::new(
+            self.port_name.clone(),
+            self.message_id.clone(),
+            written,
+            self.total_bytes,
+            estimated_remaining_ms,
+        ))
+    }
+}
+
+/// Writes all of `data`, returning how many bytes were accepted even on failure.
+///
+/// `on_progress` is called with the number of bytes written so far after each write.
+async fn write_counted<W>(
+    writer: &mut W,
+    data: &[u8],
+    on_progress: &mut impl FnMut(usize),
+) -> (usize, std::io::Result<()>)
+where
+    W: tokio::io::AsyncWrite + Unpin,
+{
+    let mut written = 0;
+    while written < data.len() {
+        match writer.write(&data[written..]).await {
+            Ok(0) => return (written, Err(std::io::ErrorKind::WriteZero.into())),
+            Ok(n) => {
+                written += n;
+                on_progress(written);
+            }
+            Err(e) => return (written, Err(e)),
+        }
+    }
+    (written, Ok(()))
+}
+
+/// Writes `data` in `chunk_size` pieces, flushing each one and sleeping `delay` in between.
+async fn write_chunked<W>(
+    writer: &mut W,
+    data: &[u8],
+    chunk_size: usize,
+    delay: std::time::Duration,
+    on_progress: &mut impl FnMut(usize),
+) -> (usize, std::io::Result<()>)
+where
+    W: tokio::io::AsyncWrite + Unpin,
+{
+    let mut written = 0;
+    for (i, chunk) in data.chunks(chunk_size).enumerate() {
+        if i > 0 {
+            tokio::time::sleep(delay).await;
+        }
+        let (n, res) = write_counted(writer, chunk, &mut |n| on_progress(written + n)).await;
+        written += n;
+        if let Err(e) = res {
+            return (written, Err(e));
+        }
+        if let Err(e) = writer.flush().await {
+            return (written, Err(e));
+        }
+    }
+    (written, Ok(()))
+}
+
+/// Writes `data` according to `mode`.
+async fn write_with_mode<W>(
+    writer: &mut W,
+    data: &[u8],
+    mode: WriteMode,
+    on_progress: &mut impl FnMut(usize),
+) -> (usize, std::io::Result<()>)
+where
+    W: tokio::io::AsyncWrite + Unpin,
+{
+    match mode {
+        WriteMode::Immediate
+        | WriteMode::ByteByByte { delay_us: 0 }
+        | WriteMode::ChunkedWithDelay { chunk_size: 0, .. } => {
+            write_counted(writer, data, on_progress).await
+        }
+        WriteMode::ByteByByte { delay_us } => {
+            write_chunked(
+                writer,
+                data,
+                1,
+                std::time::Duration::from_micros(delay_us),
+                on_progress,
+            )
+            .await
+        }
+        WriteMode::ChunkedWithDelay {
+            chunk_size,
+            delay_ms,
+        } => {
+            write_chunked(
+                writer,
+                data,
+                chunk_size,
+                std::time::Duration::from_millis(delay_ms),
+                on_progress,
+            )
+            .await
+        }
+    }
+}
+
+/// Whether a failed read may succeed if retried, as opposed to a failed port.
+fn is_transient_io_error(e: &std::io::Error) -> bool {
+    matches!(
+        e.kind(),
+        std::io::ErrorKind::WouldBlock
+            | std::io::ErrorKind::Interrupted
+            | std::io::ErrorKind::TimedOut
+    )
+}
+
+/// Hands read results from the port task to the event channel.
+struct ReadForwarder {
+    port_name: String,
+    event_tx: tokio::sync::mpsc::Sender<SerialEvent>,
+    metrics: PortTaskMetricsHandle,
+    framing: ReadFraming,
+    transform: ReadTransform,
+    consecutive_breaks: u32,
+    consecutive_transient_errors: u32,
+    /// Set while the channel is above the back-pressure threshold.
+    backpressure_reported: bool,
+    /// Events dropped and not yet reported.
+    dropped_events: u64,
+}
+
+impl ReadForwarder {
+    /// Sends an event without blocking the port task on a stalled consumer.
+    ///
+    /// Waits up to [`channels::EVENT_SEND_TIMEOUT_MS`] for room, then drops
+    /// the event. Back-pressure and drops are reported on the channel itself,
+    /// ahead of the next event that fits.
+    async fn send_event(&mut self, event: SerialEvent) {
+        let capacity = self.event_tx.max_capacity();
+        if self.event_tx.capacity() < channels::EVENT_BACKPRESSURE_THRESHOLD {
+            if !self.backpressure_reported {
+                let queue_depth = capacity - self.event_tx.capacity();
+                tracing::warn!(
+                    "port {} event queue at {}/{}",
+                    self.port_name,
+                    queue_depth,
+                    capacity
+                );
+                let report = SerialEvent::Backpressure {
+                    queue_depth,
+                    capacity,
+                };
+                self.backpressure_reported = self.event_tx.try_send(report).is_ok();
+            }
+        } else {
+            self.backpressure_reported = false;
+        }
+        if self.dropped_events > 0 {
+            let report = SerialEvent::EventsDropped {
+                dropped_count: self.dropped_events,
+            };
+            if self.event_tx.try_send(report).is_ok() {
+                self.dropped_events = 0;
+            }
+        }
+
+        let timeout = std::time::Duration::from_millis(channels::EVENT_SEND_TIMEOUT_MS);
+        if let Err(tokio::sync::mpsc::error::SendTimeoutError::Timeout(_)) =
+            self.event_tx.send_timeout(event, timeout).await
+        {
+            self.dropped_events += 1;
+            self.metrics.record_dropped_event();
+            tracing::warn!(
+                "port {} event queue full, dropped an event ({} unreported)",
+                self.port_name,
+                self.dropped_events
+            );
+        }
+    }
+
+    async fn send_data(&mut self, data: Vec<u8>) {
+        let event = SerialEvent::Message(PortReadEvent::new(self.port_name.clone(), data));
+        self.send_event(event).await;
+    }
+
+    /// Applies the read transform, falling back to the raw data if it fails.
+    async fn transform_data(&mut self, data: &[u8]) -> Vec<u8> {
+        if self.transform.is_identity() {
+            return data.to_vec();
+        }
+        match self.transform.apply(data) {
+            Ok(transformed) => {
+                tracing::info!(
+                    "transformed {} bytes read from port {} into {} bytes",
+                    data.len(),
+                    self.port_name,
+                    transformed.len()
+                );
+                transformed
+            }
+            Err(e) => {
+                tracing::warn!("read transform on port {} failed: {}", self.port_name, e);
+                self.send_event(SerialEvent::TransformFailed(e.to_string()))
+                    .await;
+                data.to_vec()
+            }
+        }
+    }
+
+    /// Forwards the outcome of a read, returning `false` once the port is done.
+    ///
+    /// The port task goes through `handle_read` instead, which may reopen the port.
+    #[cfg(test)]
+    async fn forward(&mut self, res: std::io::Result<usize>, buf: &[u8]) -> bool {
+        match self.handle(res, buf).await {
+            ReadOutcome::Continue => true,
+            ReadOutcome::Eof => false,
+            ReadOutcome::Failed(e) => {
+                self.fail(e).await;
+                false
+            }
+        }
+    }
+
+    /// Forwards data and recoverable errors, leaving a failed port to the caller.
+    async fn handle(&mut self, res: std::io::Result<usize>, buf: &[u8]) -> ReadOutcome {
+        match res {
+            Ok(0) => ReadOutcome::Eof,
+            Ok(n) => {
+                tracing::info!("read {} bytes from port {}", n, self.port_name);
+                self.consecutive_breaks = 0;
+                self.consecutive_transient_errors = 0;
+                let started = std::time::Instant::now();
+                let data = self.transform_data(&buf[..n]).await;
+                self.send_data(data).await;
+                self.metrics.record_read(started.elapsed());
+                ReadOutcome::Continue
+            }
+            // A break is reported as a failed read but leaves the port usable
+            Err(e)
+                if e.kind() == std::io::ErrorKind::BrokenPipe
+                    && self.consecutive_breaks < serial::MAX_CONSECUTIVE_BREAKS =>
+            {
+                tracing::warn!("break condition on port {}", self.port_name);
+                self.consecutive_breaks += 1;
+                self.send_event(SerialEvent::Break {
+                    duration_hint_ms: None,
+                })
+                .await;
+                if self.framing == ReadFraming::BreakAware {
+                    self.send_data(ReadFraming::BREAK_SENTINEL.to_vec()).await;
+                }
+                ReadOutcome::Continue
+            }
+            // A non-blocking port reports "no data yet" as EAGAIN, which is not a failure
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                tokio::task::yield_now().await;
+                ReadOutcome::Continue
+            }
+            Err(e)
+                if is_transient_io_error(&e)
+                    && self.consecutive_transient_errors
+                        < serial::MAX_CONSECUTIVE_TRANSIENT_ERRORS =>
+            {
+                tracing::warn!("transient read error on port {}: {}", self.port_name, e);
+                self.consecutive_transient_errors += 1;
+                ReadOutcome::Continue
+            }
+            Err(e) => ReadOutcome::Failed(e),
+        }
+    }
+
+    /// Reports a failed port, after which the port task stops.
+    async fn fail(&mut self, e: std::io::Error) {
+        self.metrics.record_error();
+        // Not dropped on a full channel, the consumer must learn the port is gone
+        let _ = self.event_tx.send(SerialEvent::Error(e)).await;
+    }
+}
+
+/// Result of [`ReadForwarder::handle`].
+enum ReadOutcome {
+    /// Data or a recoverable error was forwarded, keep reading.
+    Continue,
+    /// The port reached EOF.
+    Eof,
+    /// The port failed, not reported yet.
+    Failed(std::io::Error),
+}
+
 #[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
 pub struct ModemStatus {
     pub cts: bool,
@@ -43,19 +428,127 @@ pub struct ModemStatus {
     pub ring: bool,
 }
 
-pub fn spawn_serial_task(
+/// A serial stream the port task can drive.
+///
+/// Its `std::io::Read` impl, required by `SerialPort`, must not block: it backs
+/// polled reads once `bytes_to_read` reports data. Implemented by
+/// `tokio_serial::SerialStream`, and by a virtual port in tests.
+pub trait PortIo:
+    tokio::io::AsyncRead + tokio::io::AsyncWrite + SerialPort + Unpin + 'static
+{
+}
+
+impl<T> PortIo for T where
+    T: tokio::io::AsyncRead + tokio::io::AsyncWrite + SerialPort + Unpin + 'static
+{
+}
+
+/// Serial stream shared by the read and write tasks of a port.
+///
+/// Like the halves of `tokio::io::split`, each poll locks the stream only for
+/// its own duration, so a pending read never holds up a write. Unlike them, the
+/// stream stays reachable for modem control lines and `bytes_to_read`, which
+/// `split` hides and tokio-serial cannot provide through `try_clone`.
+struct SharedPort<P>(std::sync::Arc<parking_lot::Mutex<P>>);
+
+// Derived `Clone` would require `P: Clone`
+impl<P> Clone for SharedPort<P> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<P: PortIo> SharedPort<P> {
+    fn new(port: P) -> Self {
+        Self(std::sync::Arc::new(parking_lot::Mutex::new(port)))
+    }
+
+    /// Runs a synchronous operation on the port, which must not block.
+    fn with<R>(&self, f: impl FnOnce(&mut P) -> R) -> R {
+        f(&mut self.0.lock())
+    }
+}
+
+impl<P: PortIo> tokio::io::AsyncRead for SharedPort<P> {
+    fn poll_read(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        std::pin::Pin::new(&mut *self.0.lock()).poll_read(cx, buf)
+    }
+}
+
+impl<P: PortIo> tokio::io::AsyncWrite for SharedPort<P> {
+    fn poll_write(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        std::pin::Pin::new(&mut *self.0.lock()).poll_write(cx, buf)
+    }
+
+    fn poll_flush(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        std::pin::Pin::new(&mut *self.0.lock()).poll_flush(cx)
+    }
+
+    fn poll_shutdown(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        std::pin::Pin::new(&mut *self.0.lock()).poll_shutdown(cx)
+    }
+}
+
+/// How the port task reopens a port whose reads failed.
+pub struct Reconnect<F> {
+    pub policy: ReconnectPolicy,
+    /// Opens the port again with its original settings.
+    pub open: F,
+}
+
+/// Spawns the tasks driving an open port.
+///
+/// Reading and writing run in separate tasks, so a slow write does not delay
+/// reads. The write task also sets control lines and polls the modem status.
+/// When either task stops, it cancels the other and the port is released.
+/// Cancelling `shutdown` stops both, after flushing queued writes for up to
+/// [`serial::CANCEL_FLUSH_TIMEOUT_MS`].
+///
+/// With `rs485_mode`, RTS is asserted for the duration of each write to drive
+/// a half-duplex RS-485 transceiver.
+///
+/// A failed read stops the port unless `reconnect` allows reopening it. While
+/// it is reopened, failed writes are reported but leave the port running.
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_serial_task<P, F>(
     port_name: String,
-    mut port: tokio_serial::SerialStream,
+    port: P,
+    write_mode: WriteMode,
+    rs485_mode: bool,
+    read_strategy: ReadStrategy,
+    read_framing: ReadFraming,
+    read_transform: ReadTransform,
+    read_buffer_size: usize,
+    reconnect: Reconnect<F>,
+    metrics: PortTaskMetricsHandle,
+    shutdown: tokio_util::sync::CancellationToken,
 ) -> (
     WritePortSender,
     tokio::sync::mpsc::Receiver<SerialEvent>,
     tokio::sync::watch::Receiver<ModemStatus>,
-    tokio::sync::mpsc::Receiver<usize>,
-) {
-    let (write_tx, mut write_rx) = tokio::sync::mpsc::channel::<(
-        WriteCmd,
-        Option<tokio::sync::oneshot::Sender<()>>,
-    )>(channels::WRITE_CMD_CAPACITY);
+    tokio::sync::mpsc::Receiver<WriteStatus>,
+)
+where
+    P: PortIo,
+    F: FnMut() -> std::io::Result<P> + Send + 'static,
+{
+    let reconnects = reconnect.policy != ReconnectPolicy::Never;
+    let (write_tx, write_rx): (WritePortSender, AckReceiver<WriteCmd>) =
+        tokio::sync::mpsc::channel(channels::WRITE_CMD_CAPACITY);
     let (event_tx, event_rx) = tokio::sync::mpsc::channel(channels::EVENT_CAPACITY);
     let (status_tx, status_rx) = tokio::sync::watch::channel(ModemStatus {
         cts: false,
@@ -66,93 +559,1099 @@ pub fn spawn_serial_task(
     let (write_notifier_tx, write_notifier_rx) =
         tokio::sync::mpsc::channel(channels::WRITE_NOTIFY_CAPACITY);
 
-    tokio::spawn(async move {
-        let mut read_buf = [0u8; serial::READ_BUFFER_SIZE];
-        let mut poll_timer = tokio::time::interval(std::time::Duration::from_millis(
-            serial::STATUS_POLL_INTERVAL_MS,
-        ));
+    let port = SharedPort::new(port);
+    // A child token, so the tasks stopping each other leaves `shutdown` alone
+    let cancel = shutdown.child_token();
+    let reads = ReadForwarder {
+        port_name: port_name.clone(),
+        event_tx,
+        metrics: metrics.clone(),
+        framing: read_framing,
+        transform: read_transform,
+        consecutive_breaks: 0,
+        consecutive_transient_errors: 0,
+        backpressure_reported: false,
+        dropped_events: 0,
+    };
+    tokio::spawn(read_task(
+        port.clone(),
+        reads,
+        read_strategy,
+        read_buffer_size,
+        reconnect,
+        cancel.clone(),
+    ));
+    tokio::spawn(write_task(
+        port_name,
+        port,
+        write_rx,
+        write_mode,
+        rs485_mode,
+        reconnects,
+        write_notifier_tx,
+        status_tx,
+        metrics,
+        cancel,
+    ));
 
-        loop {
-            tokio::select! {
-                // ── Reading ───────────────────────
-                res = port.read(&mut read_buf) => {
-                    match res {
-                        Ok(0) => break,
-                        Ok(n) => {
-                            tracing::info!("read {} bytes from port {}", n, port_name);
-                            let _ = event_tx
-                                .send(SerialEvent::Message(PortReadEvent::new(
-                                    port_name.clone(),
-                                    read_buf[..n].to_vec()
-                                ))).await;
-                        }
-                        Err(e) => {
-                            let _ = event_tx.send(SerialEvent::Error(e)).await;
+    (write_tx, event_rx, status_rx, write_notifier_rx)
+}
+
+/// Reads from the port until it fails, reaches EOF or the write task stops.
+///
+/// A failed port is reopened as long as `reconnect` allows it.
+async fn read_task<P, F>(
+    mut port: SharedPort<P>,
+    mut reads: ReadForwarder,
+    read_strategy: ReadStrategy,
+    read_buffer_size: usize,
+    mut reconnect: Reconnect<F>,
+    cancel: tokio_util::sync::CancellationToken,
+) where
+    P: PortIo,
+    F: FnMut() -> std::io::Result<P>,
+{
+    let mut read_buf = vec![0u8; read_buffer_size];
+    let (polled, read_poll_interval_ms) = match read_strategy {
+        ReadStrategy::Async => (false, serial::STATUS_POLL_INTERVAL_MS),
+        ReadStrategy::Polled { interval_ms } => (true, interval_ms.max(1)),
+    };
+    let mut read_poll_timer =
+        tokio::time::interval(std::time::Duration::from_millis(read_poll_interval_ms));
+
+    loop {
+        reads.metrics.record_loop();
+        tokio::select! {
+            _ = cancel.cancelled() => break,
+
+            res = port.read(&mut read_buf), if !polled => {
+                if !handle_read(&port, &mut reads, &mut reconnect, res, &read_buf, &cancel).await {
+                    break;
+                }
+            }
+
+            _ = read_poll_timer.tick(), if polled => {
+                let res = port.with(|port| match port.bytes_to_read() {
+                    Ok(0) => None,
+                    Ok(_) => match std::io::Read::read(port, &mut read_buf) {
+                        Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => None,
+                        res => Some(res),
+                    },
+                    Err(e) => Some(Err(e.into())),
+                });
+                let Some(res) = res else {
+                    continue;
+                };
+                if !handle_read(&port, &mut reads, &mut reconnect, res, &read_buf, &cancel).await {
+                    break;
+                }
+            }
+        }
+    }
+    cancel.cancel();
+}
+
+/// Forwards a read result, reopening the port if it failed.
+///
+/// Returns `false` once the port is done.
+async fn handle_read<P, F>(
+    port: &SharedPort<P>,
+    reads: &mut ReadForwarder,
+    reconnect: &mut Reconnect<F>,
+    res: std::io::Result<usize>,
+    buf: &[u8],
+    cancel: &tokio_util::sync::CancellationToken,
+) -> bool
+where
+    P: PortIo,
+    F: FnMut() -> std::io::Result<P>,
+{
+    let err = match reads.handle(res, buf).await {
+        ReadOutcome::Continue => return true,
+        ReadOutcome::Eof => return false,
+        ReadOutcome::Failed(e) => e,
+    };
+    match reopen(port, reads, reconnect, err, cancel).await {
+        Ok(()) => true,
+        // Closed while reconnecting, nothing failed
+        Err(_) if cancel.is_cancelled() => false,
+        Err(e) => {
+            reads.fail(e).await;
+            false
+        }
+    }
+}
+
+/// Reopens a failed port as often as the policy allows.
+///
+/// Returns the last error if every attempt failed or the port was closed meanwhile.
+async fn reopen<P, F>(
+    port: &SharedPort<P>,
+    reads: &mut ReadForwarder,
+    reconnect: &mut Reconnect<F>,
+    mut err: std::io::Error,
+    cancel: &tokio_util::sync::CancellationToken,
+) -> std::io::Result<()>
+where
+    P: PortIo,
+    F: FnMut() -> std::io::Result<P>,
+{
+    let mut attempt = 1;
+    while let Some(delay) = reconnect.policy.delay(attempt) {
+        tracing::warn!(
+            "port {} failed: {}, reconnect attempt {} in {:?}",
+            reads.port_name,
+            err,
+            attempt,
+            delay
+        );
+        // Not dropped on a full channel, the port status depends on them
+        let event = SerialEvent::Reconnecting {
+            attempt,
+            next_attempt_at_ms: timestamp_now_ms() + delay.as_millis(),
+        };
+        let _ = reads.event_tx.send(event).await;
+        tokio::select! {
+            _ = cancel.cancelled() => return Err(err),
+            _ = tokio::time::sleep(delay) => {}
+        }
+        match (reconnect.open)() {
+            Ok(new_port) => {
+                tracing::info!(
+                    "port {} reconnected on attempt {}",
+                    reads.port_name,
+                    attempt
+                );
+                port.with(|port| *port = new_port);
+                reads.consecutive_breaks = 0;
+                reads.consecutive_transient_errors = 0;
+                let event = SerialEvent::Reconnected { attempts: attempt };
+                let _ = reads.event_tx.send(event).await;
+                return Ok(());
+            }
+            Err(e) => err = e,
+        }
+        attempt += 1;
+    }
+    Err(err)
+}
+
+/// Restores the order of sequenced writes sent concurrently.
+///
+/// A write ahead of the expected sequence number is held until the missing
+/// ones arrive, for at most [`serial::WRITE_SEQUENCE_TIMEOUT_MS`]. After that
+/// the held writes go out in arrival order and the gap is skipped.
+#[derive(Default)]
+struct WriteSequencer {
+    next: u64,
+    /// Held writes in arrival order.
+    pending: Vec<(WritePortMessage, Option<AckTx>)>,
+    deadline: Option<tokio::time::Instant>,
+}
+
+impl WriteSequencer {
+    /// Queues a write, returning the writes that can go out now, in order.
+    fn push(
+        &mut self,
+        msg: WritePortMessage,
+        ack_tx: Option<AckTx>,
+    ) -> Vec<(WritePortMessage, Option<AckTx>)> {
+        match msg.sequence_number {
+            // Late arrivals of a skipped gap are not held either
+            Some(seq) if seq > self.next => {
+                self.pending.push((msg, ack_tx));
+                self.deadline.get_or_insert_with(|| {
+                    tokio::time::Instant::now()
+                        + std::time::Duration::from_millis(serial::WRITE_SEQUENCE_TIMEOUT_MS)
+                });
+                Vec::new()
+            }
+            Some(seq) if seq == self.next => {
+                self.next += 1;
+                let mut ready = vec![(msg, ack_tx)];
+                while let Some(i) = self
+                    .pending
+                    .iter()
+                    .position(|(held, _)| held.sequence_number == Some(self.next))
+                {
+                    ready.push(self.pending.remove(i));
+                    self.next += 1;
+                }
+                if self.pending.is_empty() {
+                    self.deadline = None;
+                }
+                ready
+            }
+            _ => vec![(msg, ack_tx)],
+        }
+    }
+
+    /// When the oldest held write stops waiting for the missing ones.
+    fn deadline(&self) -> Option<tokio::time::Instant> {
+        self.deadline
+    }
+
+    /// Releases all held writes in arrival order, skipping the gaps.
+    fn release(&mut self) -> Vec<(WritePortMessage, Option<AckTx>)> {
+        self.deadline = None;
+        let held = std::mem::take(&mut self.pending);
+        if let Some(last) = held.iter().filter_map(|(m, _)| m.sequence_number).max() {
+            self.next = self.next.max(last + 1);
+        }
+        held
+    }
+}
+
+/// Settings shared by all writes of a port task.
+struct MessageWriter<'a> {
+    port_name: &'a str,
+    write_mode: WriteMode,
+    rs485_mode: bool,
+    /// Failed writes leave the port to the read task, which reopens it.
+    reconnects: bool,
+    write_notifier_tx: &'a tokio::sync::mpsc::Sender<WriteStatus>,
+    metrics: &'a PortTaskMetricsHandle,
+}
+
+impl MessageWriter<'_> {
+    /// Writes one message, reporting its progress and acknowledging it.
+    ///
+    /// Returns whether the port can keep going, a failed write stops it
+    /// unless it is reopened on failure.
+    async fn write<P: PortIo>(
+        &self,
+        port: &mut SharedPort<P>,
+        write_transform: &WriteTransform,
+        msg: WritePortMessage,
+        ack_tx: Option<AckTx>,
+    ) -> bool {
+        let data = if write_transform.is_identity() {
+            std::borrow::Cow::Borrowed(&msg.data[..])
+        } else {
+            std::borrow::Cow::Owned(write_transform.apply(&msg.data))
+        };
+        tracing::info!("write {} bytes to port {}", data.len(), self.port_name);
+        let _ = self
+            .write_notifier_tx
+            .send(WriteStatus::Sending(PortWriteSendingEvent::new(
+                self.port_name.to_string(),
+                msg.message_id.clone(),
+                data.len(),
+            )))
+            .await;
+        let started = std::time::Instant::now();
+        let mut progress = WriteProgress::new(
+            self.port_name.to_string(),
+            msg.message_id.clone(),
+            data.len(),
+        );
+        // Progress is best effort, a full channel must not stall the write
+        let mut on_progress = |written| {
+            if let Some(event) = progress.update(written) {
+                let _ = self
+                    .write_notifier_tx
+                    .try_send(WriteStatus::Progress(event));
+            }
+        };
+        if self.rs485_mode {
+            // Enable the transceiver's driver before the first byte
+            if let Err(e) = port.with(|port| port.write_request_to_send(true)) {
+                self.metrics.record_error();
+                tracing::warn!("Failed to assert RTS for RS-485 write: {}", e);
+            }
+        }
+        let (written, res) = write_with_mode(port, &data, self.write_mode, &mut on_progress).await;
+        if self.rs485_mode {
+            // Release the bus only once the last byte has left the UART
+            let _ = port.flush().await;
+            if let Err(e) = port.with(|port| port.write_request_to_send(false)) {
+                self.metrics.record_error();
+                tracing::warn!("Failed to clear RTS after RS-485 write: {}", e);
+            }
+        }
+        let elapsed = started.elapsed();
+        self.metrics.record_write(elapsed);
+        let latency_ms = elapsed.as_secs_f64() * 1000.0;
+        if let Some(tx) = ack_tx {
+            let _ = tx.send(match &res {
+                Ok(()) => Ok(WriteAck {
+                    bytes_written: written,
+                    elapsed_ns: elapsed.as_nanos() as u64,
+                }),
+                Err(e) => Err(format!("write failed after {} bytes: {}", written, e)),
+            });
+        }
+        if let Err(e) = &res {
+            self.metrics.record_error();
+            tracing::error!(
+                "write to port {} failed after {} bytes: {}",
+                self.port_name,
+                written,
+                e
+            );
+        }
+        let mut data = data.into_owned();
+        data.truncate(written);
+        let _ = self
+            .write_notifier_tx
+            .send(WriteStatus::Done {
+                result: PortWriteResultEvent::new(
+                    self.port_name.to_string(),
+                    msg.message_id,
+                    res.is_ok(),
+                    latency_ms,
+                    written,
+                ),
+                data,
+            })
+            .await;
+        res.is_ok() || self.reconnects
+    }
+
+    /// Writes messages in order, stopping at the first failure.
+    async fn write_all<P: PortIo>(
+        &self,
+        port: &mut SharedPort<P>,
+        write_transform: &WriteTransform,
+        messages: Vec<(WritePortMessage, Option<AckTx>)>,
+    ) -> bool {
+        for (msg, ack_tx) in messages {
+            if !self.write(port, write_transform, msg, ack_tx).await {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Runs write and control commands and polls the modem status, until the port
+/// is closed, a write fails or the read task stops.
+#[allow(clippy::too_many_arguments)]
+async fn write_task<P: PortIo>(
+    port_name: String,
+    mut port: SharedPort<P>,
+    mut write_rx: AckReceiver<WriteCmd>,
+    write_mode: WriteMode,
+    rs485_mode: bool,
+    reconnects: bool,
+    write_notifier_tx: tokio::sync::mpsc::Sender<WriteStatus>,
+    status_tx: tokio::sync::watch::Sender<ModemStatus>,
+    metrics: PortTaskMetricsHandle,
+    cancel: tokio_util::sync::CancellationToken,
+) {
+    let mut poll_timer = tokio::time::interval(std::time::Duration::from_millis(
+        serial::STATUS_POLL_INTERVAL_MS,
+    ));
+    let mut write_transform = WriteTransform::default();
+    let mut sequencer = WriteSequencer::default();
+    let writer = MessageWriter {
+        port_name: &port_name,
+        write_mode,
+        rs485_mode,
+        reconnects,
+        write_notifier_tx: &write_notifier_tx,
+        metrics: &metrics,
+    };
+    // Set once cancelled, queued commands still run until then
+    let mut flush_deadline: Option<tokio::time::Instant> = None;
+
+    loop {
+        metrics.record_loop();
+        tokio::select! {
+            _ = cancel.cancelled(), if flush_deadline.is_none() => {
+                tracing::info!("port {} cancelled, flushing queued writes", port_name);
+                // Refuses new commands, `recv` returns None once the queue is empty
+                write_rx.close();
+                flush_deadline = Some(
+                    tokio::time::Instant::now()
+                        + std::time::Duration::from_millis(serial::CANCEL_FLUSH_TIMEOUT_MS),
+                );
+            }
+
+            _ = tokio::time::sleep_until(flush_deadline.unwrap_or_else(tokio::time::Instant::now)),
+                if flush_deadline.is_some() => {
+                tracing::warn!("port {} did not flush queued writes in time", port_name);
+                break;
+            }
+
+            // ── Writing / control ─────────────
+            cmd = write_rx.recv() => {
+                match cmd {
+                    Some((WriteCmd::Message(msg), ack_tx)) => {
+                        let ready = sequencer.push(msg, ack_tx);
+                        if !writer.write_all(&mut port, &write_transform, ready).await {
                             break;
                         }
                     }
-                }
-
-                // ── Writing / control ─────────────
-                cmd = write_rx.recv() => {
-                    match cmd {
-                        Some((WriteCmd::Message(data), ack_tx)) => {
-                            tracing::info!("write {} bytes to port {}", data.data.len(), port_name);
-                            let len = data.data.len();
-                            let res = port.write_all(&data.data).await;
-                            if let Some(tx) = ack_tx {
-                                let _ = tx.send(());
-                            }
-                            let _ = write_notifier_tx.send(len).await;
-                            if res.is_err() {
-                                break;
-                            }
+                    Some((WriteCmd::Dtr(v), ack_tx)) => {
+                        tracing::info!("set DTR to {} on port {}", v.dtr, port_name);
+                        if let Err(e) = port.with(|port| port.write_data_terminal_ready(v.dtr)) {
+                            metrics.record_error();
+                            tracing::warn!("Failed to set DTR to {}: {}", v.dtr, e);
                         }
-                        Some((WriteCmd::Dtr(v), ack_tx)) => {
-                            tracing::info!("set DTR to {} on port {}", v.dtr, port_name);
-                            if let Err(e) = port.write_data_terminal_ready(v.dtr) {
-                                tracing::warn!("Failed to set DTR to {}: {}", v.dtr, e);
-                            }
-                            if let Some(tx) = ack_tx {
-                                let _ = tx.send(());
-                            }
+                        if let Some(tx) = ack_tx {
+                            let _ = tx.send(Ok(WriteAck::default()));
                         }
-                        Some((WriteCmd::Rts(v), ack_tx)) => {
-                            tracing::info!("set RTS to {} on port {}", v.rts, port_name);
-                            if let Err(e) = port.write_request_to_send(v.rts) {
-                                tracing::warn!("Failed to set RTS to {}: {}", v.rts, e);
-                            }
-                            if let Some(tx) = ack_tx {
-                                let _ = tx.send(());
-                            }
+                    }
+                    Some((WriteCmd::Rts(v), ack_tx)) => {
+                        tracing::info!("set RTS to {} on port {}", v.rts, port_name);
+                        if let Err(e) = port.with(|port| port.write_request_to_send(v.rts)) {
+                            metrics.record_error();
+                            tracing::warn!("Failed to set RTS to {}: {}", v.rts, e);
                         }
-                        Some((WriteCmd::Close, ack_tx)) => {
-                            tracing::info!("closing port {}", port_name);
-                            if let Some(tx) = ack_tx {
-                                let _ = tx.send(());
-                            }
-                            break;
+                        if let Some(tx) = ack_tx {
+                            let _ = tx.send(Ok(WriteAck::default()));
                         }
-                        None => {
-                            break;
+                    }
+                    Some((WriteCmd::SetTransform(transform), ack_tx)) => {
+                        tracing::info!("set write transform on port {}: {:?}", port_name, transform);
+                        write_transform = transform;
+                        if let Some(tx) = ack_tx {
+                            let _ = tx.send(Ok(WriteAck::default()));
+                        }
+                    }
+                    Some((WriteCmd::Close, ack_tx)) => {
+                        tracing::info!("closing port {}", port_name);
+                        if let Some(tx) = ack_tx {
+                            let _ = tx.send(Ok(WriteAck::default()));
                         }
+                        break;
+                    }
+                    None => {
+                        // Held writes are part of the queue being flushed
+                        writer.write_all(&mut port, &write_transform, sequencer.release()).await;
+                        break;
                     }
                 }
+            }
 
-                // ── Modem status polling ──────────
-                _ = poll_timer.tick() => {
-                    let status = ModemStatus {
-                        cts: port.read_clear_to_send().unwrap_or(false),
-                        dsr: port.read_data_set_ready().unwrap_or(false),
-                        cd:  port.read_carrier_detect().unwrap_or(false),
-                        ring: port.read_ring_indicator().unwrap_or(false),
-                    };
-                    let _ = status_tx.send(status);
+            _ = tokio::time::sleep_until(sequencer.deadline().unwrap_or_else(tokio::time::Instant::now)),
+                if sequencer.deadline().is_some() => {
+                tracing::warn!("port {} gave up waiting for missing write sequence numbers", port_name);
+                if !writer.write_all(&mut port, &write_transform, sequencer.release()).await {
+                    break;
                 }
             }
+
+            // ── Modem status polling ──────────
+            _ = poll_timer.tick() => {
+                let status = port.with(|port| ModemStatus {
+                    cts: port.read_clear_to_send().unwrap_or(false),
+                    dsr: port.read_data_set_ready().unwrap_or(false),
+                    cd:  port.read_carrier_detect().unwrap_or(false),
+                    ring: port.read_ring_indicator().unwrap_or(false),
+                });
+                let _ = status_tx.send(status);
+            }
         }
-        let _ = port.shutdown().await;
-    });
+    }
+    cancel.cancel();
+    let _ = port.shutdown().await;
+}
 
-    (write_tx, event_rx, status_rx, write_notifier_rx)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serial_mgr::virtual_port::{create_virtual_port_pair, VirtualPort};
+
+    #[test]
+    fn transient_io_errors() {
+        use std::io::{Error, ErrorKind};
+
+        for kind in [
+            ErrorKind::WouldBlock,
+            ErrorKind::Interrupted,
+            ErrorKind::TimedOut,
+        ] {
+            assert!(is_transient_io_error(&Error::from(kind)));
+        }
+        for kind in [ErrorKind::BrokenPipe, ErrorKind::NotFound, ErrorKind::Other] {
+            assert!(!is_transient_io_error(&Error::from(kind)));
+        }
+    }
+
+    /// Replays a scripted sequence of read results.
+    struct ScriptedReader(std::collections::VecDeque<std::io::Result<Vec<u8>>>);
+
+    impl tokio::io::AsyncRead for ScriptedReader {
+        fn poll_read(
+            mut self: std::pin::Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+            buf: &mut tokio::io::ReadBuf<'_>,
+        ) -> std::task::Poll<std::io::Result<()>> {
+            // An exhausted script reads as EOF
+            if let Some(result) = self.0.pop_front() {
+                buf.put_slice(&result?);
+            }
+            std::task::Poll::Ready(Ok(()))
+        }
+    }
+
+    #[tokio::test]
+    async fn undecodable_reads_are_reported_and_forwarded_raw() {
+        let script = [Ok(b"4f4b".to_vec()), Ok(b"zz".to_vec())].into();
+        let mut reader = ScriptedReader(script);
+
+        let (event_tx, mut event_rx) = tokio::sync::mpsc::channel(channels::EVENT_CAPACITY);
+        let mut reads = ReadForwarder {
+            port_name: "COM1".to_string(),
+            event_tx,
+            metrics: PortTaskMetricsHandle::default(),
+            framing: ReadFraming::Raw,
+            transform: ReadTransform::HexDecode,
+            consecutive_breaks: 0,
+            consecutive_transient_errors: 0,
+            backpressure_reported: false,
+            dropped_events: 0,
+        };
+        let mut buf = [0u8; serial::READ_BUFFER_SIZE];
+        while reads.forward(reader.read(&mut buf).await, &buf).await {}
+        drop(reads);
+
+        match event_rx.recv().await {
+            Some(SerialEvent::Message(message)) => assert_eq!(message.data, b"OK"),
+            _ => panic!("expected decoded data"),
+        }
+        assert!(matches!(
+            event_rx.recv().await,
+            Some(SerialEvent::TransformFailed(_))
+        ));
+        match event_rx.recv().await {
+            Some(SerialEvent::Message(message)) => assert_eq!(message.data, b"zz"),
+            _ => panic!("expected raw data"),
+        }
+    }
+
+    #[tokio::test]
+    async fn would_block_reads_keep_port_open() {
+        use std::io::{Error, ErrorKind};
+
+        let mut script = std::collections::VecDeque::new();
+        for data in [b"a", b"b"] {
+            // More in a row than the transient error limit
+            for _ in 0..=serial::MAX_CONSECUTIVE_TRANSIENT_ERRORS {
+                script.push_back(Err(Error::from(ErrorKind::WouldBlock)));
+            }
+            script.push_back(Ok(data.to_vec()));
+        }
+        let mut reader = ScriptedReader(script);
+
+        let (event_tx, mut event_rx) = tokio::sync::mpsc::channel(channels::EVENT_CAPACITY);
+        let mut reads = ReadForwarder {
+            port_name: "COM1".to_string(),
+            event_tx,
+            metrics: PortTaskMetricsHandle::default(),
+            framing: ReadFraming::Raw,
+            transform: ReadTransform::None,
+            consecutive_breaks: 0,
+            consecutive_transient_errors: 0,
+            backpressure_reported: false,
+            dropped_events: 0,
+        };
+        let mut buf = [0u8; serial::READ_BUFFER_SIZE];
+        while reads.forward(reader.read(&mut buf).await, &buf).await {}
+        drop(reads);
+
+        let mut received = Vec::new();
+        while let Some(event) = event_rx.recv().await {
+            match event {
+                SerialEvent::Message(message) => received.extend(message.data),
+                _ => panic!("unexpected event"),
+            }
+        }
+        assert_eq!(received, b"ab");
+    }
+
+    #[tokio::test]
+    async fn stalled_consumer_gets_backpressure_then_drops() {
+        let (event_tx, mut event_rx) = tokio::sync::mpsc::channel(channels::EVENT_CAPACITY);
+        let metrics = PortTaskMetricsHandle::default();
+        let mut reads = ReadForwarder {
+            port_name: "COM1".to_string(),
+            event_tx,
+            metrics: metrics.clone(),
+            framing: ReadFraming::Raw,
+            transform: ReadTransform::None,
+            consecutive_breaks: 0,
+            consecutive_transient_errors: 0,
+            backpressure_reported: false,
+            dropped_events: 0,
+        };
+        // Nothing is consumed, so the last sends time out instead of blocking
+        for i in 0..channels::EVENT_CAPACITY {
+            reads.send_data(vec![i as u8]).await;
+        }
+        let dropped = metrics.snapshot(0).events_dropped;
+        assert!(dropped > 0);
+
+        let (mut backpressure, mut messages) = (0, 0);
+        while let Ok(event) = event_rx.try_recv() {
+            match event {
+                SerialEvent::Backpressure { capacity, .. } => {
+                    assert_eq!(capacity, channels::EVENT_CAPACITY);
+                    backpressure += 1;
+                }
+                SerialEvent::Message(_) => messages += 1,
+                _ => panic!("unexpected event"),
+            }
+        }
+        assert_eq!(backpressure, 1);
+        assert_eq!(messages + dropped, channels::EVENT_CAPACITY as u64);
+
+        // The drop is reported ahead of the next event once there is room
+        reads.send_data(b"next".to_vec()).await;
+        match event_rx.try_recv() {
+            Ok(SerialEvent::EventsDropped { dropped_count }) => assert_eq!(dropped_count, dropped),
+            _ => panic!("expected dropped events report"),
+        }
+        assert!(matches!(event_rx.try_recv(), Ok(SerialEvent::Message(_))));
+    }
+
+    #[tokio::test]
+    async fn chunked_write_reports_progress_per_chunk() {
+        let data = vec![0u8; 4 * serial::WRITE_PROGRESS_BYTES];
+        let mode = WriteMode::ChunkedWithDelay {
+            chunk_size: serial::WRITE_PROGRESS_BYTES,
+            delay_ms: 0,
+        };
+        let mut progress = WriteProgress::new("COM1".to_string(), "m1".to_string(), data.len());
+        let mut events = Vec::new();
+        let mut writer = Vec::new();
+        let (written, res) = write_with_mode(&mut writer, &data, mode, &mut |written| {
+            events.extend(progress.update(written));
+        })
+        .await;
+
+        assert!(res.is_ok());
+        assert_eq!(written, data.len());
+        let reported: Vec<usize> = events.iter().map(|e| e.bytes_written_so_far).collect();
+        assert_eq!(reported, [1024, 2048, 3072, 4096]);
+        let last = events.last().unwrap();
+        assert_eq!(last.total_bytes, data.len());
+        assert_eq!(last.percent_complete, 100.0);
+        assert_eq!(last.estimated_remaining_ms, Some(0));
+    }
+
+    /// A port task running on one end of a virtual pair.
+    struct VirtualTask {
+        peer: VirtualPort,
+        write_tx: WritePortSender,
+        event_rx: tokio::sync::mpsc::Receiver<SerialEvent>,
+        status_rx: tokio::sync::watch::Receiver<ModemStatus>,
+        write_status_rx: tokio::sync::mpsc::Receiver<WriteStatus>,
+        shutdown: tokio_util::sync::CancellationToken,
+    }
+
+    /// `prepare_peer` runs before the task starts, e.g. to set the peer's lines.
+    fn spawn_virtual(
+        read_strategy: ReadStrategy,
+        prepare_peer: impl FnOnce(&mut VirtualPort),
+    ) -> VirtualTask {
+        spawn_virtual_with(
+            read_strategy,
+            false,
+            serial::READ_BUFFER_SIZE,
+            no_reconnect(),
+            prepare_peer,
+        )
+    }
+
+    fn no_reconnect() -> Reconnect<impl FnMut() -> std::io::Result<VirtualPort>> {
+        Reconnect {
+            policy: ReconnectPolicy::Never,
+            open: || Err(std::io::ErrorKind::NotFound.into()),
+        }
+    }
+
+    fn spawn_virtual_with(
+        read_strategy: ReadStrategy,
+        rs485_mode: bool,
+        read_buffer_size: usize,
+        reconnect: Reconnect<impl FnMut() -> std::io::Result<VirtualPort> + Send + 'static>,
+        prepare_peer: impl FnOnce(&mut VirtualPort),
+    ) -> VirtualTask {
+        let (port, mut peer) = create_virtual_port_pair();
+        prepare_peer(&mut peer);
+        let shutdown = tokio_util::sync::CancellationToken::new();
+        let (write_tx, event_rx, status_rx, write_status_rx) = spawn_serial_task(
+            "virtual-a".to_string(),
+            port,
+            WriteMode::Immediate,
+            rs485_mode,
+            read_strategy,
+            ReadFraming::Raw,
+            ReadTransform::None,
+            read_buffer_size,
+            reconnect,
+            PortTaskMetricsHandle::default(),
+            shutdown.clone(),
+        );
+        VirtualTask {
+            peer,
+            write_tx,
+            event_rx,
+            status_rx,
+            write_status_rx,
+            shutdown,
+        }
+    }
+
+    /// Fails the test instead of hanging if `fut` does not complete.
+    async fn within<F: std::future::Future>(fut: F) -> F::Output {
+        tokio::time::timeout(std::time::Duration::from_secs(5), fut)
+            .await
+            .expect("timed out")
+    }
+
+    async fn send(task: &VirtualTask, cmd: WriteCmd) -> WriteAck {
+        let send = crate::serial_mgr::helpers::send_command_with_ack(
+            &task.write_tx,
+            cmd,
+            "test command",
+            "virtual-a",
+        );
+        within(send).await.unwrap()
+    }
+
+    /// Collects read events until `len` bytes arrived.
+    async fn read_events(task: &mut VirtualTask, len: usize) -> Vec<u8> {
+        let mut received = Vec::new();
+        while received.len() < len {
+            match within(task.event_rx.recv()).await {
+                Some(SerialEvent::Message(message)) => received.extend(message.data),
+                Some(_) => panic!("unexpected event"),
+                None => panic!("port task stopped"),
+            }
+        }
+        received
+    }
+
+    #[tokio::test]
+    async fn written_message_reaches_peer() {
+        let mut task = spawn_virtual(ReadStrategy::Async, |_| {});
+        let cmd = WriteCmd::Message(WritePortMessage {
+            message_id: "m1".to_string(),
+            data: b"ping".to_vec(),
+            sequence_number: None,
+        });
+        assert_eq!(send(&task, cmd).await.bytes_written, 4);
+
+        let mut buf = [0u8; 4];
+        within(task.peer.read_exact(&mut buf)).await.unwrap();
+        assert_eq!(&buf, b"ping");
+        match within(task.write_status_rx.recv()).await {
+            Some(WriteStatus::Sending(event)) => assert_eq!(event.data_len, 4),
+            _ => panic!("expected sending status"),
+        }
+        match within(task.write_status_rx.recv()).await {
+            Some(WriteStatus::Done { result, data }) => {
+                assert!(result.success);
+                assert_eq!(result.message_id, "m1");
+                assert_eq!(result.actual_bytes_written, 4);
+                assert_eq!(data, b"ping");
+            }
+            _ => panic!("expected write result"),
+        }
+    }
+
+    fn sequenced(sequence_number: u64, data: &[u8]) -> WriteCmd {
+        WriteCmd::Message(WritePortMessage {
+            message_id: format!("m{}", sequence_number),
+            data: data.to_vec(),
+            sequence_number: Some(sequence_number),
+        })
+    }
+
+    #[tokio::test]
+    async fn sequenced_writes_are_reordered() {
+        let mut task = spawn_virtual(ReadStrategy::Async, |_| {});
+        for (seq, data) in [(1, b"b"), (2, b"c"), (0, b"a")] {
+            task.write_tx
+                .send((sequenced(seq, data), None))
+                .await
+                .unwrap();
+        }
+
+        let mut buf = [0u8; 3];
+        within(task.peer.read_exact(&mut buf)).await.unwrap();
+        assert_eq!(&buf, b"abc");
+    }
+
+    #[tokio::test]
+    async fn sequence_gap_times_out_to_arrival_order() {
+        let mut task = spawn_virtual(ReadStrategy::Async, |_| {});
+        let started = std::time::Instant::now();
+        // Sequence number 0 never arrives in time
+        for (seq, data) in [(2, b"y"), (1, b"x")] {
+            task.write_tx
+                .send((sequenced(seq, data), None))
+                .await
+                .unwrap();
+        }
+
+        let mut buf = [0u8; 2];
+        within(task.peer.read_exact(&mut buf)).await.unwrap();
+        assert_eq!(&buf, b"yx");
+        assert!(
+            started.elapsed()
+                >= std::time::Duration::from_millis(serial::WRITE_SEQUENCE_TIMEOUT_MS)
+        );
+
+        // A late arrival of the skipped gap is not held
+        assert_eq!(send(&task, sequenced(0, b"z")).await.bytes_written, 1);
+        let mut buf = [0u8; 1];
+        within(task.peer.read_exact(&mut buf)).await.unwrap();
+        assert_eq!(&buf, b"z");
+    }
+
+    #[tokio::test]
+    async fn rs485_mode_releases_rts_after_write() {
+        let mut task = spawn_virtual_with(
+            ReadStrategy::Async,
+            true,
+            serial::READ_BUFFER_SIZE,
+            no_reconnect(),
+            |_| {},
+        );
+        send(&task, WriteCmd::Rts(WritePortRequestToSend { rts: true })).await;
+        assert!(task.peer.read_clear_to_send().unwrap());
+
+        let cmd = WriteCmd::Message(WritePortMessage {
+            message_id: "m1".to_string(),
+            data: b"485".to_vec(),
+            sequence_number: None,
+        });
+        send(&task, cmd).await;
+        let mut buf = [0u8; 3];
+        within(task.peer.read_exact(&mut buf)).await.unwrap();
+        assert_eq!(&buf, b"485");
+        assert!(!task.peer.read_clear_to_send().unwrap());
+    }
+
+    #[tokio::test]
+    async fn write_transform_wraps_later_messages() {
+        let mut task = spawn_virtual(ReadStrategy::Async, |_| {});
+        let transform = WriteTransform {
+            prefix: vec![0x02],
+            suffix: vec![0x03],
+            checksum_algorithm: Some(crate::serial::write_transform::ChecksumAlgorithm::Xor),
+        };
+        send(&task, WriteCmd::SetTransform(transform)).await;
+        let cmd = WriteCmd::Message(WritePortMessage {
+            message_id: "m1".to_string(),
+            data: b"ab".to_vec(),
+            sequence_number: None,
+        });
+        send(&task, cmd).await;
+
+        let mut buf = [0u8; 5];
+        within(task.peer.read_exact(&mut buf)).await.unwrap();
+        assert_eq!(buf, [0x02, b'a', b'b', 0x02 ^ b'a' ^ b'b', 0x03]);
+    }
+
+    #[tokio::test]
+    async fn peer_data_is_forwarded_as_read_events() {
+        let mut task = spawn_virtual(ReadStrategy::Async, |_| {});
+        within(task.peer.write_all(b"pong")).await.unwrap();
+        assert_eq!(read_events(&mut task, 4).await, b"pong");
+    }
+
+    #[tokio::test]
+    async fn polled_reads_forward_peer_data() {
+        let mut task = spawn_virtual(ReadStrategy::Polled { interval_ms: 1 }, |_| {});
+        within(task.peer.write_all(b"polled")).await.unwrap();
+        assert_eq!(read_events(&mut task, 6).await, b"polled");
+    }
+
+    #[tokio::test]
+    async fn large_read_buffer_forwards_data_in_one_event() {
+        let mut task = spawn_virtual_with(ReadStrategy::Async, false, 4096, no_reconnect(), |_| {});
+        let data: Vec<u8> = (0..3000).map(|i| i as u8).collect();
+        within(task.peer.write_all(&data)).await.unwrap();
+        match within(task.event_rx.recv()).await {
+            Some(SerialEvent::Message(message)) => assert_eq!(message.data, data),
+            _ => panic!("expected read data"),
+        }
+    }
+
+    #[tokio::test]
+    async fn control_lines_reach_peer_and_modem_status() {
+        let mut task = spawn_virtual(ReadStrategy::Async, |peer| {
+            peer.write_data_terminal_ready(true).unwrap();
+            peer.write_request_to_send(true).unwrap();
+        });
+        send(
+            &task,
+            WriteCmd::Dtr(WritePortDataTerminalReady { dtr: true }),
+        )
+        .await;
+        send(&task, WriteCmd::Rts(WritePortRequestToSend { rts: true })).await;
+        assert!(task.peer.read_data_set_ready().unwrap());
+        assert!(task.peer.read_carrier_detect().unwrap());
+        assert!(task.peer.read_clear_to_send().unwrap());
+
+        // The first status poll happens as soon as the task starts
+        let status = within(task.status_rx.wait_for(|status| status.dsr))
+            .await
+            .unwrap();
+        assert!(status.cd && status.cts && !status.ring);
+    }
+
+    #[tokio::test]
+    async fn close_command_releases_port() {
+        let mut task = spawn_virtual(ReadStrategy::Async, |_| {});
+        send(&task, WriteCmd::Close).await;
+
+        assert!(within(task.event_rx.recv()).await.is_none());
+        let mut buf = [0u8; 1];
+        assert_eq!(within(task.peer.read(&mut buf)).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn shutdown_flushes_queued_writes_then_releases_port() {
+        let mut task = spawn_virtual(ReadStrategy::Async, |_| {});
+        let cmd = WriteCmd::Message(WritePortMessage {
+            message_id: "m1".to_string(),
+            data: b"bye".to_vec(),
+            sequence_number: None,
+        });
+        // Queued without waiting for the ack, so it is still pending when cancelled
+        task.write_tx.send((cmd, None)).await.unwrap();
+        task.shutdown.cancel();
+
+        let mut buf = [0u8; 3];
+        within(task.peer.read_exact(&mut buf)).await.unwrap();
+        assert_eq!(&buf, b"bye");
+        assert!(within(task.event_rx.recv()).await.is_none());
+        assert_eq!(within(task.peer.read(&mut buf)).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn failed_write_is_reported_to_the_caller() {
+        let mut task = spawn_virtual(ReadStrategy::Async, |_| {});
+        task.peer.fail_writes(true);
+        let cmd = WriteCmd::Message(WritePortMessage {
+            message_id: "m1".to_string(),
+            data: b"ping".to_vec(),
+            sequence_number: None,
+        });
+        let send = crate::serial_mgr::helpers::send_command_with_ack(
+            &task.write_tx,
+            cmd,
+            "test command",
+            "virtual-a",
+        );
+        let err = within(send).await.unwrap_err();
+        assert!(err.contains("write failed after 0 bytes"), "{}", err);
+        loop {
+            match within(task.write_status_rx.recv()).await {
+                Some(WriteStatus::Done { result, .. }) => {
+                    assert!(!result.success);
+                    break;
+                }
+                Some(_) => {}
+                None => panic!("expected write result"),
+            }
+        }
+        // Without a reconnect policy the failed write stops the port
+        within(task.write_tx.closed()).await;
+    }
+
+    #[tokio::test]
+    async fn peer_hangup_stops_port_task() {
+        let VirtualTask {
+            peer,
+            write_tx,
+            mut event_rx,
+            ..
+        } = spawn_virtual(ReadStrategy::Async, |_| {});
+        drop(peer);
+
+        assert!(within(event_rx.recv()).await.is_none());
+        within(write_tx.closed()).await;
+    }
+
+    #[tokio::test]
+    async fn unplugged_port_is_reopened() {
+        let (replacement, mut new_peer) = create_virtual_port_pair();
+        let mut replacements = std::collections::VecDeque::from([
+            Err(std::io::ErrorKind::NotFound.into()),
+            Ok(replacement),
+        ]);
+        let reconnect = Reconnect {
+            policy: ReconnectPolicy::Attempts {
+                max_attempts: 3,
+                interval_ms: 1,
+            },
+            open: move || replacements.pop_front().unwrap(),
+        };
+        let mut task = spawn_virtual_with(
+            ReadStrategy::Async,
+            false,
+            serial::READ_BUFFER_SIZE,
+            reconnect,
+            |_| {},
+        );
+        let peer = std::mem::replace(&mut task.peer, create_virtual_port_pair().1);
+        peer.unplug();
+
+        for expected in [1, 2] {
+            match within(task.event_rx.recv()).await {
+                Some(SerialEvent::Reconnecting { attempt, .. }) => assert_eq!(attempt, expected),
+                _ => panic!("expected reconnecting"),
+            }
+        }
+        assert!(matches!(
+            within(task.event_rx.recv()).await,
+            Some(SerialEvent::Reconnected { attempts: 2 })
+        ));
+
+        within(new_peer.write_all(b"back")).await.unwrap();
+        assert_eq!(read_events(&mut task, 4).await, b"back");
+        let cmd = WriteCmd::Message(WritePortMessage {
+            message_id: "m1".to_string(),
+            data: b"ok".to_vec(),
+            sequence_number: None,
+        });
+        assert_eq!(send(&task, cmd).await.bytes_written, 2);
+        let mut buf = [0u8; 2];
+        within(new_peer.read_exact(&mut buf)).await.unwrap();
+        assert_eq!(&buf, b"ok");
+    }
+
+    #[tokio::test]
+    async fn reconnect_gives_up_after_max_attempts() {
+        let reconnect = Reconnect {
+            policy: ReconnectPolicy::Attempts {
+                max_attempts: 2,
+                interval_ms: 1,
+            },
+            open: || Err(std::io::ErrorKind::NotFound.into()),
+        };
+        let VirtualTask {
+            peer,
+            write_tx,
+            mut event_rx,
+            ..
+        } = spawn_virtual_with(
+            ReadStrategy::Async,
+            false,
+            serial::READ_BUFFER_SIZE,
+            reconnect,
+            |_| {},
+        );
+        peer.unplug();
+
+        for _ in 0..2 {
+            assert!(matches!(
+                within(event_rx.recv()).await,
+                Some(SerialEvent::Reconnecting { .. })
+            ));
+        }
+        match within(event_rx.recv()).await {
+            Some(SerialEvent::Error(e)) => assert_eq!(e.kind(), std::io::ErrorKind::NotFound),
+            _ => panic!("expected error"),
+        }
+        within(write_tx.closed()).await;
+    }
 }
@@ -0,0 +1,129 @@
+//! Runtime counters of a port task, for debugging throughput issues.
+
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+use std::time::{Duration, Instant};
+
+use crate::state::AppState;
+
+/// Snapshot of a port task's counters.
+///
+/// Rates and latencies are averaged over the lifetime of the task.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PortTaskMetrics {
+    pub loops_per_second: f64,
+    pub read_events_per_second: f64,
+    pub writes_per_second: f64,
+    /// Write commands queued but not yet picked up by the task.
+    pub write_queue_depth: usize,
+    /// Time to hand a read to the event channel, high values mean the consumer lags.
+    pub read_latency_avg_us: f64,
+    /// Time spent writing a message to the port.
+    pub write_latency_avg_us: f64,
+    pub total_reads: u64,
+    pub total_writes: u64,
+    pub errors: u64,
+    /// Read events dropped because the event channel stayed full.
+    pub events_dropped: u64,
+}
+
+#[derive(Debug)]
+struct Counters {
+    started: Instant,
+    loops: AtomicU64,
+    reads: AtomicU64,
+    writes: AtomicU64,
+    errors: AtomicU64,
+    events_dropped: AtomicU64,
+    read_latency_us: AtomicU64,
+    write_latency_us: AtomicU64,
+}
+
+/// Counters shared between a port task and `get_port_task_metrics`.
+#[derive(Debug, Clone)]
+pub struct PortTaskMetricsHandle(Arc<Counters>);
+
+impl Default for PortTaskMetricsHandle {
+    fn default() -> Self {
+        Self(Arc::new(Counters {
+            started: Instant::now(),
+            loops: AtomicU64::new(0),
+            reads: AtomicU64::new(0),
+            writes: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+            events_dropped: AtomicU64::new(0),
+            read_latency_us: AtomicU64::new(0),
+            write_latency_us: AtomicU64::new(0),
+        }))
+    }
+}
+
+impl PortTaskMetricsHandle {
+    pub fn record_loop(&self) {
+        self.0.loops.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_read(&self, latency: Duration) {
+        self.0.reads.fetch_add(1, Ordering::Relaxed);
+        self.0
+            .read_latency_us
+            .fetch_add(latency.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_write(&self, latency: Duration) {
+        self.0.writes.fetch_add(1, Ordering::Relaxed);
+        self.0
+            .write_latency_us
+            .fetch_add(latency.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_error(&self) {
+        self.0.errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_dropped_event(&self) {
+        self.0.events_dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self, write_queue_depth: usize) -> PortTaskMetrics {
+        let elapsed = self.0.started.elapsed().as_secs_f64().max(f64::EPSILON);
+        let loops = self.0.loops.load(Ordering::Relaxed);
+        let reads = self.0.reads.load(Ordering::Relaxed);
+        let writes = self.0.writes.load(Ordering::Relaxed);
+        let average = |total: &AtomicU64, count: u64| {
+            if count == 0 {
+                0.0
+            } else {
+                total.load(Ordering::Relaxed) as f64 / count as f64
+            }
+        };
+        PortTaskMetrics {
+            loops_per_second: loops as f64 / elapsed,
+            read_events_per_second: reads as f64 / elapsed,
+            writes_per_second: writes as f64 / elapsed,
+            write_queue_depth,
+            read_latency_avg_us: average(&self.0.read_latency_us, reads),
+            write_latency_avg_us: average(&self.0.write_latency_us, writes),
+            total_reads: reads,
+            total_writes: writes,
+            errors: self.0.errors.load(Ordering::Relaxed),
+            events_dropped: self.0.events_dropped.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Returns the runtime metrics of an open port's task.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn get_port_task_metrics(
+    state: tauri::State<'_, AppState>,
+    port_name: String,
+) -> Result<PortTaskMetrics, String> {
+    let handles = state.port_handles.get(&port_name).ok_or_else(|| {
+        tracing::error!("get metrics of port that is not open: {}", port_name);
+        format!("{} is not open", port_name)
+    })?;
+    let tx = &handles.write_port_tx;
+    Ok(handles.metrics.snapshot(tx.max_capacity() - tx.capacity()))
+}
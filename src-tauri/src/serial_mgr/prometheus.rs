@@ -0,0 +1,154 @@
+//! Port statistics in the Prometheus text exposition format, for test benches
+//! that scrape the app into Grafana.
+
+use std::fmt::Write;
+
+use crate::state::AppState;
+
+/// Statistics of one known port.
+#[derive(Debug, Clone)]
+struct PortSample {
+    port_name: String,
+    bytes_read: u128,
+    bytes_written: u128,
+    /// Write queue depth and read events, only for open ports.
+    task: Option<(usize, u64)>,
+}
+
+/// Escapes a label value: backslash, double quote and newline.
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Writes one metric family, all its samples must be contiguous.
+fn write_family<'a>(
+    out: &mut String,
+    name: &str,
+    kind: &str,
+    help: &str,
+    samples: impl Iterator<Item = (&'a str, String)>,
+) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+    for (port_name, value) in samples {
+        let _ = writeln!(
+            out,
+            "{}{{port=\"{}\"}} {}",
+            name,
+            escape_label(port_name),
+            value
+        );
+    }
+}
+
+fn render(samples: &[PortSample]) -> String {
+    let mut out = String::new();
+    let open = || {
+        samples
+            .iter()
+            .filter_map(|s| s.task.map(|task| (s.port_name.as_str(), task)))
+    };
+
+    let _ = writeln!(out, "# HELP serialport_open_ports Number of open ports.");
+    let _ = writeln!(out, "# TYPE serialport_open_ports gauge");
+    let _ = writeln!(out, "serialport_open_ports {}", open().count());
+    write_family(
+        &mut out,
+        "serialport_bytes_read_total",
+        "counter",
+        "Bytes read from the port.",
+        samples
+            .iter()
+            .map(|s| (s.port_name.as_str(), s.bytes_read.to_string())),
+    );
+    write_family(
+        &mut out,
+        "serialport_bytes_written_total",
+        "counter",
+        "Bytes written to the port.",
+        samples
+            .iter()
+            .map(|s| (s.port_name.as_str(), s.bytes_written.to_string())),
+    );
+    write_family(
+        &mut out,
+        "serialport_write_queue_depth",
+        "gauge",
+        "Write commands queued but not yet picked up by the port task.",
+        open().map(|(port_name, (depth, _))| (port_name, depth.to_string())),
+    );
+    write_family(
+        &mut out,
+        "serialport_read_events_total",
+        "counter",
+        "Reads forwarded by the port task since the port was opened.",
+        open().map(|(port_name, (_, reads))| (port_name, reads.to_string())),
+    );
+    out
+}
+
+/// Returns port statistics in the Prometheus text exposition format.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn get_metrics_text(state: tauri::State<'_, AppState>) -> Result<String, String> {
+    // Collect first so a `port_handles` guard is never held while locking `ports`
+    let tasks: Vec<(String, (usize, u64))> = state
+        .port_handles
+        .iter()
+        .map(|entry| {
+            let tx = &entry.write_port_tx;
+            let depth = tx.max_capacity() - tx.capacity();
+            let reads = entry.metrics.snapshot(depth).total_reads;
+            (entry.key().clone(), (depth, reads))
+        })
+        .collect();
+    let mut samples: Vec<PortSample> = state
+        .ports
+        .iter()
+        .map(|entry| PortSample {
+            port_name: entry.key().clone(),
+            bytes_read: entry.bytes_read,
+            bytes_written: entry.bytes_write,
+            task: tasks
+                .iter()
+                .find(|(port_name, _)| port_name == entry.key())
+                .map(|(_, task)| *task),
+        })
+        .collect();
+    samples.sort_by(|a, b| a.port_name.cmp(&b.port_name));
+    Ok(render(&samples))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_exposition_format() {
+        let samples = [
+            PortSample {
+                port_name: "COM1".to_string(),
+                bytes_read: 10,
+                bytes_written: 4,
+                task: Some((2, 3)),
+            },
+            PortSample {
+                port_name: "odd\"name".to_string(),
+                bytes_read: 0,
+                bytes_written: 0,
+                task: None,
+            },
+        ];
+        let text = render(&samples);
+        assert!(text.contains("serialport_open_ports 1\n"));
+        assert!(text.contains("serialport_bytes_read_total{port=\"COM1\"} 10\n"));
+        assert!(text.contains("serialport_bytes_written_total{port=\"odd\\\"name\"} 0\n"));
+        assert!(text.contains("serialport_write_queue_depth{port=\"COM1\"} 2\n"));
+        assert!(text.contains("serialport_read_events_total{port=\"COM1\"} 3\n"));
+        // Closed ports have no task metrics
+        assert!(!text.contains("serialport_read_events_total{port=\"odd"));
+        assert!(text.contains("# TYPE serialport_bytes_read_total counter\n"));
+    }
+}
@@ -0,0 +1,30 @@
+//! Backend clock, for the frontend to correct its timestamps against.
+
+use serde::Serialize;
+use time::UtcOffset;
+
+use crate::serial_mgr::helpers::timestamp_now_ms;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GetServerTimestampResult {
+    /// Milliseconds since the Unix epoch, as used for log timestamps.
+    pub timestamp_ms: u128,
+    /// Offset of the local timezone from UTC, 0 if it cannot be determined.
+    pub utc_offset_seconds: i32,
+}
+
+/// Returns the backend's current time.
+///
+/// The frontend calls this at startup and on reconnect to compute its clock
+/// offset from the backend.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn get_server_timestamp() -> Result<GetServerTimestampResult, String> {
+    let utc_offset = UtcOffset::current_local_offset().unwrap_or_else(|err| {
+        tracing::warn!("local timezone offset unavailable, using UTC: {}", err);
+        UtcOffset::UTC
+    });
+    Ok(GetServerTimestampResult {
+        timestamp_ms: timestamp_now_ms(),
+        utc_offset_seconds: utc_offset.whole_seconds(),
+    })
+}
@@ -0,0 +1,122 @@
+//! Log sessions of open ports.
+
+use parking_lot::RwLock;
+
+use crate::serial::port_type::PortType;
+use crate::serial_mgr::storage::{generate_device_fingerprint, Storage};
+use crate::state::{AppState, OpenedPortProfile, PortStatus};
+
+pub fn generate_session_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+/// Session an open port records its log entries under.
+///
+/// Shared between the port tasks and `rotate_session`, which can switch the
+/// current session without reopening the port.
+#[derive(Debug)]
+pub struct PortSession {
+    current_id: RwLock<String>,
+    pub port_name: String,
+    pub device_fingerprint: String,
+    pub vid: Option<String>,
+    pub pid: Option<String>,
+    pub serial_number: Option<String>,
+    pub baud_rate: u32,
+    pub config_json: String,
+}
+
+impl PortSession {
+    /// Describes the session of `port_name` opened with `profile`, whose ID is
+    /// `profile.session_id`.
+    pub fn new(
+        port_name: String,
+        port_type: PortType,
+        profile: &OpenedPortProfile,
+    ) -> Result<Self, serde_json::Error> {
+        let device_fingerprint = generate_device_fingerprint(&port_name, &port_type);
+        let (vid, pid, serial_number) = match port_type {
+            PortType::UsbPort(usb) => (
+                Some(format!("{:04X}", usb.vid)),
+                Some(format!("{:04X}", usb.pid)),
+                usb.serial_number,
+            ),
+            _ => (None, None, None),
+        };
+        Ok(Self {
+            current_id: RwLock::new(profile.session_id.clone()),
+            config_json: serde_json::to_string(profile)?,
+            port_name,
+            device_fingerprint,
+            vid,
+            pid,
+            serial_number,
+            baud_rate: profile.baud_rate,
+        })
+    }
+
+    pub fn current_id(&self) -> String {
+        self.current_id.read().clone()
+    }
+
+    /// Records `session_id` in storage with this port's device details.
+    pub async fn begin(&self, storage: &Storage, session_id: &str) -> Result<(), String> {
+        storage
+            .begin_session(
+                session_id,
+                &self.port_name,
+                &self.device_fingerprint,
+                self.vid.as_deref(),
+                self.pid.as_deref(),
+                self.serial_number.as_deref(),
+                self.baud_rate,
+                &self.config_json,
+            )
+            .await
+    }
+
+    /// Makes `session_id` current, returning the previous session ID.
+    fn replace_id(&self, session_id: String) -> String {
+        std::mem::replace(&mut *self.current_id.write(), session_id)
+    }
+}
+
+/// Start a new log session on an open port without closing it.
+///
+/// The previous session is ended but kept in storage. Returns the new session ID.
+#[tauri::command(rename_all = "camelCase")]
+#[tracing::instrument(name = "rotate_session", level = "debug", skip(state), fields(%port_name))]
+pub async fn rotate_session(
+    state: tauri::State<'_, AppState>,
+    port_name: String,
+) -> Result<String, String> {
+    let session = state
+        .port_handles
+        .get(&port_name)
+        .map(|handles| handles.session.clone())
+        .ok_or_else(|| {
+            tracing::error!("rotate session of port that is not open: {}", port_name);
+            format!("{} is not open", port_name)
+        })?;
+
+    let session_id = generate_session_id();
+    session
+        .begin(&state.storage, &session_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("begin rotated session failed: {}", e);
+            e
+        })?;
+    let previous = session.replace_id(session_id.clone());
+    if let Err(e) = state.storage.end_session(&previous).await {
+        tracing::warn!("end previous session {} failed: {}", previous, e);
+    }
+
+    if let Some(mut entry) = state.ports.get_mut(&port_name) {
+        if let PortStatus::Opened(profile) = &mut entry.port_status {
+            profile.session_id = session_id.clone();
+        }
+    }
+    tracing::info!("rotated session {} -> {}", previous, session_id);
+    Ok(session_id)
+}
@@ -14,9 +14,69 @@ pub struct Model {
     pub direction: String,
     pub timestamp: i64,
     pub data: Vec<u8>,
+    pub repeat_count: i64,
+    /// ID of the write a TX entry records, `None` for RX entries.
+    pub message_id: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {}
 
 impl ActiveModelBehavior for ActiveModel {}
+
+#[allow(dead_code)]
+impl Model {
+    /// `timestamp` as a UTC date, `None` if it is out of chrono's range.
+    pub fn timestamp_as_datetime(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        chrono::DateTime::from_timestamp_millis(self.timestamp)
+    }
+
+    /// Time from `other` to this entry, negative if `other` is later.
+    pub fn duration_since(&self, other: &Model) -> Option<chrono::Duration> {
+        Some(self.timestamp_as_datetime()? - other.timestamp_as_datetime()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry_at(timestamp: i64) -> Model {
+        Model {
+            id: 0,
+            device_fingerprint: String::new(),
+            session_id: String::new(),
+            vid: None,
+            pid: None,
+            serial_number: None,
+            port_name: String::new(),
+            direction: "RX".to_string(),
+            timestamp,
+            data: Vec::new(),
+            repeat_count: 1,
+            message_id: None,
+        }
+    }
+
+    #[test]
+    fn timestamps_convert_to_utc_dates() {
+        let entry = entry_at(1_700_000_000_123);
+        let datetime = entry.timestamp_as_datetime().unwrap();
+        assert_eq!(datetime.to_rfc3339(), "2023-11-14T22:13:20.123+00:00");
+        assert!(entry_at(i64::MAX).timestamp_as_datetime().is_none());
+    }
+
+    #[test]
+    fn duration_since_is_signed() {
+        let first = entry_at(1_000);
+        let second = entry_at(3_500);
+        assert_eq!(
+            second.duration_since(&first),
+            Some(chrono::Duration::milliseconds(2_500))
+        );
+        assert_eq!(
+            first.duration_since(&second),
+            Some(chrono::Duration::milliseconds(-2_500))
+        );
+    }
+}
@@ -1,21 +1,152 @@
+mod audit_entity;
 mod entity;
+mod session_entity;
 
+use dashmap::DashMap;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, ConnectOptions, Database, DatabaseConnection, EntityTrait,
-    QueryFilter, QueryOrder, QuerySelect, Set,
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, Condition, ConnectOptions, Database,
+    DatabaseConnection, DbBackend, EntityTrait, FromQueryResult, Order, QueryFilter, QueryOrder,
+    QuerySelect, Set, Statement, TransactionTrait,
 };
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use tokio::sync::broadcast;
 
+use crate::constants::{channels, storage};
+
+pub use audit_entity::Model as AuditEntry;
 /// Re-export the entity Model as LogEntry for external use
 pub use entity::Model as LogEntry;
 
+/// Owned arguments of [`Storage::insert`], for entries buffered before a batch insert.
+#[derive(Debug, Clone)]
+pub struct InsertParams {
+    pub device_fingerprint: String,
+    pub session_id: String,
+    pub vid: Option<String>,
+    pub pid: Option<String>,
+    pub serial_number: Option<String>,
+    pub port_name: String,
+    pub direction: String,
+    pub data: Vec<u8>,
+    pub timestamp_ms: Option<i64>,
+    pub message_id: Option<String>,
+}
+
+/// Operational event recorded in the audit log, apart from the data in `logs`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditEventType {
+    PortOpened,
+    PortClosed,
+    WriteStarted,
+    WriteCompleted,
+    /// One entry per batch of reads written to `logs`, with its message and
+    /// byte counts. The data itself is in `logs`.
+    ReadReceived,
+    /// Read events dropped because the consumer fell behind.
+    EventsDropped,
+    /// Reopened by the port's reconnect policy after its reads failed.
+    PortReconnected,
+    Error,
+}
+
+impl AuditEventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::PortOpened => "port_opened",
+            Self::PortClosed => "port_closed",
+            Self::WriteStarted => "write_started",
+            Self::WriteCompleted => "write_completed",
+            Self::ReadReceived => "read_received",
+            Self::EventsDropped => "events_dropped",
+            Self::PortReconnected => "port_reconnected",
+            Self::Error => "error",
+        }
+    }
+}
+
+/// Amount of log data stored for one device on one port.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, FromQueryResult)]
+pub struct StorageUsage {
+    pub device_fingerprint: String,
+    pub port_name: String,
+    pub session_count: i64,
+    pub total_entries: i64,
+    pub total_bytes_in_data_column: i64,
+}
+
+/// Outcome of [`Storage::integrity_check`].
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct StorageIntegrityReport {
+    pub ok: bool,
+    /// Problems reported by `PRAGMA integrity_check` and `PRAGMA foreign_key_check`.
+    pub issues: Vec<String>,
+}
+
+/// Outcome of [`Storage::repair`].
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct RepairReport {
+    pub size_before_bytes: u64,
+    pub size_after_bytes: u64,
+    /// Integrity of the database after the repair.
+    pub integrity: StorageIntegrityReport,
+}
+
+/// Message and byte counts of one session, returned by [`Storage::get_session_summary`].
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, FromQueryResult)]
+pub struct SessionSummary {
+    pub session_id: String,
+    pub message_count: i64,
+    pub rx_count: i64,
+    pub tx_count: i64,
+    pub total_bytes: i64,
+}
+
+/// Filters for [`Storage::query_logs`], `None` fields are not filtered on.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct LogQuery {
+    pub session_id: String,
+    /// `RX` or `TX`, case insensitive.
+    pub direction: Option<String>,
+    /// Inclusive lower bound of the entry timestamp.
+    pub start_ts_ms: Option<i64>,
+    /// Inclusive upper bound of the entry timestamp.
+    pub end_ts_ms: Option<i64>,
+    /// Byte sequence the data must contain, as hex, spaces are ignored.
+    pub pattern_hex: Option<String>,
+    pub limit: usize,
+    pub offset: usize,
+    /// Oldest entries first instead of newest first.
+    pub ascending: bool,
+}
+
+/// Text indexed for full-text search, with bytes that are not UTF-8 as `\xNN`.
+fn fts_text(data: &[u8]) -> String {
+    let mut text = String::with_capacity(data.len());
+    for chunk in data.utf8_chunks() {
+        text.push_str(chunk.valid());
+        for b in chunk.invalid() {
+            text.push_str(&format!("\\x{:02X}", b));
+        }
+    }
+    text
+}
+
+fn now_ms() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
+
 /// Storage for serial port logs using SeaORM with SQLite.
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct Storage {
     #[allow(dead_code)]
     db_path: PathBuf,
     connection: Arc<DatabaseConnection>,
+    /// Live subscribers to newly inserted entries, keyed by session ID.
+    session_channels: Arc<DashMap<String, broadcast::Sender<LogEntry>>>,
 }
 
 impl Storage {
@@ -23,10 +154,7 @@ impl Storage {
         let db_path = path.as_ref().to_path_buf();
         let db_url = format!("sqlite://{}?mode=rwc", db_path.display());
 
-        let mut opt = ConnectOptions::new(&db_url);
-        opt.sqlx_logging(false);
-
-        let connection = Database::connect(opt)
+        let connection = Database::connect(Self::connect_options(&db_url))
             .await
             .map_err(|e| format!("Failed to connect to database: {}", e))?;
 
@@ -36,15 +164,28 @@ impl Storage {
         Ok(Storage {
             db_path,
             connection: Arc::new(connection),
+            session_channels: Arc::new(DashMap::new()),
         })
     }
 
-    pub async fn new_in_memory() -> Self {
-        let db_url = "sqlite::memory:";
-        let mut opt = ConnectOptions::new(db_url);
-        opt.sqlx_logging(false);
+    /// Opens the application log database at `path`, falling back to an
+    /// in-memory database so the app keeps working if the file is unusable.
+    pub async fn new_app_storage<P: AsRef<Path>>(path: P) -> Self {
+        match Self::new(&path).await {
+            Ok(storage) => storage,
+            Err(e) => {
+                tracing::error!(
+                    "Failed to initialize storage at {}: {}, falling back to in-memory storage",
+                    path.as_ref().display(),
+                    e
+                );
+                Self::new_in_memory().await
+            }
+        }
+    }
 
-        let connection = Database::connect(opt)
+    pub async fn new_in_memory() -> Self {
+        let connection = Database::connect(Self::connect_options("sqlite::memory:"))
             .await
             .expect("Failed to connect to in-memory database");
 
@@ -55,15 +196,226 @@ impl Storage {
         Storage {
             db_path: PathBuf::from(":memory:"),
             connection: Arc::new(connection),
+            session_channels: Arc::new(DashMap::new()),
         }
     }
 
+    fn connect_options(db_url: &str) -> ConnectOptions {
+        let mut opt = ConnectOptions::new(db_url);
+        opt.sqlx_logging(false);
+        // Foreign keys are off by default in SQLite and the pragma is per connection
+        opt.map_sqlx_sqlite_opts(|opts| opts.foreign_keys(true));
+        opt
+    }
+
     async fn init_schema(conn: &DatabaseConnection) -> Result<(), String> {
         use sea_orm::ConnectionTrait;
 
+        conn.execute_unprepared(&format!(
+            r#"
+            PRAGMA foreign_keys = ON;
+            CREATE TABLE IF NOT EXISTS sessions (
+                session_id TEXT PRIMARY KEY NOT NULL,
+                port_name TEXT NOT NULL,
+                device_fingerprint TEXT NOT NULL,
+                vid TEXT,
+                pid TEXT,
+                serial_number TEXT,
+                baud_rate INTEGER NOT NULL,
+                config_json TEXT NOT NULL,
+                started_at INTEGER NOT NULL,
+                ended_at INTEGER
+            );
+            {}
+            -- No foreign key, audit entries are kept independently of session data
+            CREATE TABLE IF NOT EXISTS audit_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp INTEGER NOT NULL,
+                event_type TEXT NOT NULL,
+                port_name TEXT NOT NULL,
+                session_id TEXT NOT NULL,
+                details TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_audit_session_id ON audit_log(session_id);
+            "#,
+            Self::logs_ddl("logs")
+        ))
+        .await
+        .map_err(|e| format!("Failed to initialize schema: {}", e))?;
+
+        // Databases created before deduplication lack the repeat_count column
+        if !Self::logs_has_column(conn, "repeat_count").await? {
+            conn.execute_unprepared(
+                "ALTER TABLE logs ADD COLUMN repeat_count INTEGER NOT NULL DEFAULT 1",
+            )
+            .await
+            .map_err(|e| format!("Failed to add repeat_count column: {}", e))?;
+        }
+        if !Self::logs_has_column(conn, "message_id").await? {
+            conn.execute_unprepared("ALTER TABLE logs ADD COLUMN message_id TEXT")
+                .await
+                .map_err(|e| format!("Failed to add message_id column: {}", e))?;
+        }
+
+        // Databases created before the foreign key need the logs table rebuilt,
+        // SQLite cannot add a constraint to an existing table
+        let has_session_fk = conn
+            .query_one(Statement::from_string(
+                DbBackend::Sqlite,
+                "SELECT COUNT(*) AS cnt FROM pragma_foreign_key_list('logs') WHERE \"table\" = 'sessions'",
+            ))
+            .await
+            .map_err(|e| format!("Failed to inspect logs table: {}", e))?
+            .and_then(|row| row.try_get::<i64>("", "cnt").ok())
+            .unwrap_or(0)
+            > 0;
+        if !has_session_fk {
+            Self::rebuild_logs_with_session_fk(conn).await?;
+        }
+
+        // Created after the migrations, older logs tables lack the column
+        conn.execute_unprepared("CREATE INDEX IF NOT EXISTS idx_message_id ON logs(message_id)")
+            .await
+            .map_err(|e| format!("Failed to create message_id index: {}", e))?;
+        conn.execute_unprepared("CREATE INDEX IF NOT EXISTS idx_timestamp ON logs(timestamp)")
+            .await
+            .map_err(|e| format!("Failed to create timestamp index: {}", e))?;
+
+        Self::init_full_text_search(conn).await?;
+        Self::init_session_stats(conn).await
+    }
+
+    /// Creates the `session_stats` cache, kept up to date by triggers.
+    ///
+    /// Sessions get a row when they begin. Sessions from before the table
+    /// existed get one from the first [`Storage::get_session_summary`] scan.
+    /// Created after the migrations, rebuilding the logs table drops its triggers.
+    async fn init_session_stats(conn: &DatabaseConnection) -> Result<(), String> {
+        use sea_orm::ConnectionTrait;
+
+        conn.execute_unprepared(&format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS session_stats (
+                session_id TEXT PRIMARY KEY NOT NULL,
+                message_count INTEGER NOT NULL DEFAULT 0,
+                rx_count INTEGER NOT NULL DEFAULT 0,
+                tx_count INTEGER NOT NULL DEFAULT 0,
+                total_bytes INTEGER NOT NULL DEFAULT 0,
+                last_updated INTEGER NOT NULL,
+                FOREIGN KEY (session_id) REFERENCES sessions(session_id) ON DELETE CASCADE
+            );
+            CREATE TRIGGER IF NOT EXISTS session_stats_begin AFTER INSERT ON sessions BEGIN
+                INSERT OR IGNORE INTO session_stats (session_id, last_updated)
+                VALUES (NEW.session_id, {now});
+            END;
+            CREATE TRIGGER IF NOT EXISTS session_stats_insert AFTER INSERT ON logs BEGIN
+                UPDATE session_stats SET
+                    message_count = message_count + 1,
+                    rx_count = rx_count + (UPPER(NEW.direction) = 'RX'),
+                    tx_count = tx_count + (UPPER(NEW.direction) = 'TX'),
+                    total_bytes = total_bytes + LENGTH(NEW.data),
+                    last_updated = {now}
+                WHERE session_id = NEW.session_id;
+            END;
+            CREATE TRIGGER IF NOT EXISTS session_stats_delete AFTER DELETE ON logs BEGIN
+                UPDATE session_stats SET
+                    message_count = message_count - 1,
+                    rx_count = rx_count - (UPPER(OLD.direction) = 'RX'),
+                    tx_count = tx_count - (UPPER(OLD.direction) = 'TX'),
+                    total_bytes = total_bytes - LENGTH(OLD.data),
+                    last_updated = {now}
+                WHERE session_id = OLD.session_id;
+            END;
+            "#,
+            // SQLite has no millisecond clock function, julianday counts days from 4714 BC
+            now = "CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER)"
+        ))
+        .await
+        .map_err(|e| format!("Failed to create session stats: {}", e))?;
+        Ok(())
+    }
+
+    /// Creates the `logs_fts` index, indexing the existing logs the first time.
+    ///
+    /// Rows are added by `insert`, SQL cannot produce the `\xNN` escapes of
+    /// [`fts_text`]. Deletes, including cascaded ones, are mirrored by a trigger.
+    /// Created after the migrations, rebuilding the logs table drops its triggers.
+    async fn init_full_text_search(conn: &DatabaseConnection) -> Result<(), String> {
+        use sea_orm::ConnectionTrait;
+
+        let exists = conn
+            .query_one(Statement::from_string(
+                DbBackend::Sqlite,
+                "SELECT COUNT(*) AS cnt FROM sqlite_master WHERE name = 'logs_fts'",
+            ))
+            .await
+            .map_err(|e| format!("Failed to inspect schema: {}", e))?
+            .and_then(|row| row.try_get::<i64>("", "cnt").ok())
+            .unwrap_or(0)
+            > 0;
         conn.execute_unprepared(
             r#"
-            CREATE TABLE IF NOT EXISTS logs (
+            CREATE VIRTUAL TABLE IF NOT EXISTS logs_fts USING fts5(data);
+            CREATE TRIGGER IF NOT EXISTS logs_fts_delete AFTER DELETE ON logs BEGIN
+                DELETE FROM logs_fts WHERE rowid = OLD.id;
+            END;
+            "#,
+        )
+        .await
+        .map_err(|e| format!("Failed to create full-text index: {}", e))?;
+        if exists {
+            return Ok(());
+        }
+
+        let logs = entity::Entity::find()
+            .all(conn)
+            .await
+            .map_err(|e| format!("Failed to read logs to index: {}", e))?;
+        for log in &logs {
+            Self::index_log(conn, log.id, &log.data).await?;
+        }
+        if !logs.is_empty() {
+            tracing::info!("indexed {} existing logs for full-text search", logs.len());
+        }
+        Ok(())
+    }
+
+    async fn index_log<C: sea_orm::ConnectionTrait>(
+        conn: &C,
+        id: i64,
+        data: &[u8],
+    ) -> Result<(), String> {
+        conn.execute(Statement::from_sql_and_values(
+            DbBackend::Sqlite,
+            "INSERT INTO logs_fts (rowid, data) VALUES (?, ?)",
+            [id.into(), fts_text(data).into()],
+        ))
+        .await
+        .map_err(|e| format!("Failed to index log: {}", e))?;
+        Ok(())
+    }
+
+    async fn logs_has_column(conn: &DatabaseConnection, column: &str) -> Result<bool, String> {
+        use sea_orm::ConnectionTrait;
+
+        let count = conn
+            .query_one(Statement::from_sql_and_values(
+                DbBackend::Sqlite,
+                "SELECT COUNT(*) AS cnt FROM pragma_table_info('logs') WHERE name = ?",
+                [column.into()],
+            ))
+            .await
+            .map_err(|e| format!("Failed to inspect logs table: {}", e))?
+            .and_then(|row| row.try_get::<i64>("", "cnt").ok())
+            .unwrap_or(0);
+        Ok(count > 0)
+    }
+
+    /// DDL of the logs table and its indexes, created under `table`.
+    fn logs_ddl(table: &str) -> String {
+        format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS {table} (
                 id INTEGER PRIMARY KEY AUTOINCREMENT,
                 device_fingerprint TEXT NOT NULL,
                 session_id TEXT NOT NULL,
@@ -73,18 +425,58 @@ impl Storage {
                 port_name TEXT NOT NULL,
                 direction TEXT NOT NULL,
                 timestamp INTEGER NOT NULL,
-                data BLOB NOT NULL
+                data BLOB NOT NULL,
+                repeat_count INTEGER NOT NULL DEFAULT 1,
+                message_id TEXT,
+                FOREIGN KEY (session_id) REFERENCES sessions(session_id) ON DELETE CASCADE
             );
-            CREATE INDEX IF NOT EXISTS idx_session_id ON logs(session_id);
-            CREATE INDEX IF NOT EXISTS idx_device_fingerprint ON logs(device_fingerprint);
-            "#,
+            CREATE INDEX IF NOT EXISTS idx_session_id ON {table}(session_id);
+            CREATE INDEX IF NOT EXISTS idx_device_fingerprint ON {table}(device_fingerprint);
+            "#
         )
-        .await
-        .map_err(|e| format!("Failed to initialize schema: {}", e))?;
+    }
 
-        Ok(())
+    /// Copies the logs into a table with the session foreign key. Logs whose
+    /// session was never recorded get a placeholder session so they are kept.
+    async fn rebuild_logs_with_session_fk(conn: &DatabaseConnection) -> Result<(), String> {
+        use sea_orm::ConnectionTrait;
+
+        tracing::info!("migrating logs table to reference sessions");
+        let txn = conn
+            .begin()
+            .await
+            .map_err(|e| format!("Failed to begin logs migration: {}", e))?;
+        txn.execute_unprepared(&format!(
+            r#"
+            INSERT OR IGNORE INTO sessions
+                (session_id, port_name, device_fingerprint, vid, pid, serial_number,
+                 baud_rate, config_json, started_at, ended_at)
+            SELECT session_id, port_name, device_fingerprint, vid, pid, serial_number,
+                   0, '{{}}', MIN(timestamp), MAX(timestamp)
+            FROM logs
+            GROUP BY session_id;
+            DROP INDEX IF EXISTS idx_session_id;
+            DROP INDEX IF EXISTS idx_device_fingerprint;
+            {}
+            INSERT INTO logs_new
+                (id, device_fingerprint, session_id, vid, pid, serial_number,
+                 port_name, direction, timestamp, data, repeat_count, message_id)
+            SELECT id, device_fingerprint, session_id, vid, pid, serial_number,
+                   port_name, direction, timestamp, data, repeat_count, message_id
+            FROM logs;
+            DROP TABLE logs;
+            ALTER TABLE logs_new RENAME TO logs;
+            "#,
+            Self::logs_ddl("logs_new")
+        ))
+        .await
+        .map_err(|e| format!("Failed to migrate logs table: {}", e))?;
+        txn.commit()
+            .await
+            .map_err(|e| format!("Failed to commit logs migration: {}", e))
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn insert(
         &self,
         device_fingerprint: &str,
@@ -96,13 +488,9 @@ impl Storage {
         direction: &str,
         data: &[u8],
         timestamp_ms: Option<i64>,
+        message_id: Option<&str>,
     ) -> Result<i64, String> {
-        let timestamp = timestamp_ms.unwrap_or_else(|| {
-            std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_millis() as i64
-        });
+        let timestamp = timestamp_ms.unwrap_or_else(now_ms);
 
         let model = entity::ActiveModel {
             id: sea_orm::ActiveValue::NotSet,
@@ -115,14 +503,388 @@ impl Storage {
             direction: Set(direction.to_string()),
             timestamp: Set(timestamp),
             data: Set(data.to_vec()),
+            repeat_count: Set(1),
+            message_id: Set(message_id.map(|s| s.to_string())),
         };
 
+        let txn = self
+            .connection
+            .begin()
+            .await
+            .map_err(|e| format!("Failed to begin log insert: {}", e))?;
         let result = model
-            .insert(self.connection.as_ref())
+            .insert(&txn)
             .await
             .map_err(|e| format!("Failed to insert log: {}", e))?;
+        Self::index_log(&txn, result.id, &result.data).await?;
+        txn.commit()
+            .await
+            .map_err(|e| format!("Failed to commit log insert: {}", e))?;
+
+        let id = result.id;
+        self.publish(result);
+        Ok(id)
+    }
+
+    /// Inserts `entries` in one transaction, with one multi-row `INSERT` per
+    /// [`storage::LOG_BATCH_MAX_ROWS`] entries. Returns the ids in input order.
+    pub async fn insert_batch(&self, entries: Vec<InsertParams>) -> Result<Vec<i64>, String> {
+        if entries.is_empty() {
+            return Ok(Vec::new());
+        }
+        let now = now_ms();
+        let models: Vec<LogEntry> = entries
+            .into_iter()
+            .map(|entry| LogEntry {
+                id: 0,
+                device_fingerprint: entry.device_fingerprint,
+                session_id: entry.session_id,
+                vid: entry.vid,
+                pid: entry.pid,
+                serial_number: entry.serial_number,
+                port_name: entry.port_name,
+                direction: entry.direction,
+                timestamp: entry.timestamp_ms.unwrap_or(now),
+                data: entry.data,
+                repeat_count: 1,
+                message_id: entry.message_id,
+            })
+            .collect();
+
+        let txn = self
+            .connection
+            .begin()
+            .await
+            .map_err(|e| format!("Failed to begin log batch insert: {}", e))?;
+        let mut inserted = Vec::with_capacity(models.len());
+        for chunk in models.chunks(storage::LOG_BATCH_MAX_ROWS) {
+            let rows = chunk.iter().map(|model| entity::ActiveModel {
+                id: sea_orm::ActiveValue::NotSet,
+                device_fingerprint: Set(model.device_fingerprint.clone()),
+                session_id: Set(model.session_id.clone()),
+                vid: Set(model.vid.clone()),
+                pid: Set(model.pid.clone()),
+                serial_number: Set(model.serial_number.clone()),
+                port_name: Set(model.port_name.clone()),
+                direction: Set(model.direction.clone()),
+                timestamp: Set(model.timestamp),
+                data: Set(model.data.clone()),
+                repeat_count: Set(model.repeat_count),
+                message_id: Set(model.message_id.clone()),
+            });
+            let last_id = entity::Entity::insert_many(rows)
+                .exec(&txn)
+                .await
+                .map_err(|e| format!("Failed to insert log batch: {}", e))?
+                .last_insert_id;
+            // Rows of one statement get consecutive rowids while the transaction holds the write lock
+            let first_id = last_id - chunk.len() as i64 + 1;
+            for (id, model) in (first_id..).zip(chunk) {
+                Self::index_log(&txn, id, &model.data).await?;
+                inserted.push(LogEntry {
+                    id,
+                    ..model.clone()
+                });
+            }
+        }
+        txn.commit()
+            .await
+            .map_err(|e| format!("Failed to commit log batch insert: {}", e))?;
+
+        let ids = inserted.iter().map(|entry| entry.id).collect();
+        for entry in inserted {
+            self.publish(entry);
+        }
+        Ok(ids)
+    }
+
+    /// Like [`Storage::insert`], but folds a message identical to the session's
+    /// previous entry into that entry's `repeat_count`, as long as it arrives
+    /// within `min_dedup_interval_ms` of it. Returns the id of the affected row.
+    #[allow(dead_code)]
+    pub async fn insert_deduped(
+        &self,
+        entry: InsertParams,
+        min_dedup_interval_ms: u64,
+    ) -> Result<i64, String> {
+        let timestamp = entry.timestamp_ms.unwrap_or_else(now_ms);
+
+        let last = entity::Entity::find()
+            .filter(entity::Column::SessionId.eq(entry.session_id.as_str()))
+            .order_by_desc(entity::Column::Id)
+            .one(self.connection.as_ref())
+            .await
+            .map_err(|e| format!("Failed to query last log: {}", e))?;
+
+        if let Some(last) = last {
+            let within_interval = timestamp.saturating_sub(last.timestamp)
+                < i64::try_from(min_dedup_interval_ms).unwrap_or(i64::MAX);
+            if last.direction == entry.direction && last.data == entry.data && within_interval {
+                let repeat_count = last.repeat_count + 1;
+                let mut model: entity::ActiveModel = last.into();
+                model.repeat_count = Set(repeat_count);
+                let result = model
+                    .update(self.connection.as_ref())
+                    .await
+                    .map_err(|e| format!("Failed to update log repeat count: {}", e))?;
+
+                let id = result.id;
+                self.publish(result);
+                return Ok(id);
+            }
+        }
+
+        self.insert(
+            &entry.device_fingerprint,
+            &entry.session_id,
+            entry.vid.as_deref(),
+            entry.pid.as_deref(),
+            entry.serial_number.as_deref(),
+            &entry.port_name,
+            &entry.direction,
+            &entry.data,
+            Some(timestamp),
+            entry.message_id.as_deref(),
+        )
+        .await
+    }
+
+    /// Records the start of a session. Must be called before logging to it.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn begin_session(
+        &self,
+        session_id: &str,
+        port_name: &str,
+        device_fingerprint: &str,
+        vid: Option<&str>,
+        pid: Option<&str>,
+        serial_number: Option<&str>,
+        baud_rate: u32,
+        config_json: &str,
+    ) -> Result<(), String> {
+        let model = session_entity::ActiveModel {
+            session_id: Set(session_id.to_string()),
+            port_name: Set(port_name.to_string()),
+            device_fingerprint: Set(device_fingerprint.to_string()),
+            vid: Set(vid.map(|s| s.to_string())),
+            pid: Set(pid.map(|s| s.to_string())),
+            serial_number: Set(serial_number.map(|s| s.to_string())),
+            baud_rate: Set(baud_rate as i64),
+            config_json: Set(config_json.to_string()),
+            started_at: Set(now_ms()),
+            ended_at: Set(None),
+        };
+
+        session_entity::Entity::insert(model)
+            .exec(self.connection.as_ref())
+            .await
+            .map_err(|e| format!("Failed to begin session: {}", e))?;
+
+        Ok(())
+    }
+
+    /// Marks a session as ended.
+    pub async fn end_session(&self, session_id: &str) -> Result<(), String> {
+        let result = session_entity::Entity::update_many()
+            .col_expr(session_entity::Column::EndedAt, Expr::value(now_ms()))
+            .filter(session_entity::Column::SessionId.eq(session_id))
+            .exec(self.connection.as_ref())
+            .await
+            .map_err(|e| format!("Failed to end session: {}", e))?;
+
+        if result.rows_affected == 0 {
+            return Err(format!("Session {} not found", session_id));
+        }
+        Ok(())
+    }
+
+    /// Deletes a session and its log entries, returning how many entries were removed.
+    ///
+    /// The entries would go through the foreign key anyway, they are deleted
+    /// first so they can be counted.
+    pub async fn delete_session(&self, session_id: &str) -> Result<u64, String> {
+        let txn = self
+            .connection
+            .begin()
+            .await
+            .map_err(|e| format!("Failed to begin session delete: {}", e))?;
+        let logs = entity::Entity::delete_many()
+            .filter(entity::Column::SessionId.eq(session_id))
+            .exec(&txn)
+            .await
+            .map_err(|e| format!("Failed to delete session logs: {}", e))?;
+        let result = session_entity::Entity::delete_by_id(session_id.to_string())
+            .exec(&txn)
+            .await
+            .map_err(|e| format!("Failed to delete session: {}", e))?;
+
+        if result.rows_affected == 0 {
+            return Err(format!("Session {} not found", session_id));
+        }
+        txn.commit()
+            .await
+            .map_err(|e| format!("Failed to commit session delete: {}", e))?;
+        self.session_channels.remove(session_id);
+        Ok(logs.rows_affected)
+    }
+
+    /// Counts the messages and bytes logged in a session.
+    ///
+    /// Served from `session_stats`, which triggers keep exact on every insert
+    /// and delete. The logs are only scanned, and the cache refreshed, for
+    /// sessions without a row, such as those from before the table existed,
+    /// or whose row was invalidated.
+    pub async fn get_session_summary(&self, session_id: &str) -> Result<SessionSummary, String> {
+        use sea_orm::ConnectionTrait;
+
+        let select_cached = || {
+            SessionSummary::find_by_statement(Statement::from_sql_and_values(
+                DbBackend::Sqlite,
+                r#"
+                SELECT session_id, message_count, rx_count, tx_count, total_bytes
+                FROM session_stats
+                WHERE session_id = ? AND last_updated > 0
+                "#,
+                [session_id.into()],
+            ))
+            .one(self.connection.as_ref())
+        };
+        if let Some(summary) = select_cached()
+            .await
+            .map_err(|e| format!("Failed to query session stats: {}", e))?
+        {
+            return Ok(summary);
+        }
+
+        let session_exists = session_entity::Entity::find_by_id(session_id.to_string())
+            .one(self.connection.as_ref())
+            .await
+            .map_err(|e| format!("Failed to query session: {}", e))?
+            .is_some();
+        if !session_exists {
+            return Err(format!("Session {} not found", session_id));
+        }
+
+        // One statement, so inserts made during the scan are not lost from the cache
+        self.connection
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Sqlite,
+                r#"
+                INSERT INTO session_stats
+                    (session_id, message_count, rx_count, tx_count, total_bytes, last_updated)
+                SELECT ?,
+                       COUNT(*),
+                       COALESCE(SUM(UPPER(direction) = 'RX'), 0),
+                       COALESCE(SUM(UPPER(direction) = 'TX'), 0),
+                       COALESCE(SUM(LENGTH(data)), 0),
+                       ?
+                FROM logs
+                WHERE session_id = ?
+                ON CONFLICT(session_id) DO UPDATE SET
+                    message_count = excluded.message_count,
+                    rx_count = excluded.rx_count,
+                    tx_count = excluded.tx_count,
+                    total_bytes = excluded.total_bytes,
+                    last_updated = excluded.last_updated
+                "#,
+                [session_id.into(), now_ms().into(), session_id.into()],
+            ))
+            .await
+            .map_err(|e| format!("Failed to refresh session stats: {}", e))?;
+        select_cached()
+            .await
+            .map_err(|e| format!("Failed to query session stats: {}", e))?
+            .ok_or_else(|| format!("Failed to refresh session stats for {}", session_id))
+    }
+
+    /// Marks the cached statistics of a session as stale, forcing the next
+    /// [`Storage::get_session_summary`] to rescan the logs.
+    #[allow(dead_code)]
+    pub async fn invalidate_session_stats(&self, session_id: &str) -> Result<(), String> {
+        use sea_orm::ConnectionTrait;
+
+        self.connection
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Sqlite,
+                "UPDATE session_stats SET last_updated = 0 WHERE session_id = ?",
+                [session_id.into()],
+            ))
+            .await
+            .map_err(|e| format!("Failed to invalidate session stats: {}", e))?;
+        Ok(())
+    }
+
+    /// Subscribes to entries inserted for `session_id` from now on.
+    pub fn subscribe_to_session(&self, session_id: &str) -> broadcast::Receiver<LogEntry> {
+        self.session_channels
+            .entry(session_id.to_string())
+            .or_insert_with(|| broadcast::channel(channels::LOG_TAIL_CAPACITY).0)
+            .subscribe()
+    }
+
+    /// Forwards a freshly inserted entry to the subscribers of its session.
+    fn publish(&self, entry: LogEntry) {
+        let session_id = entry.session_id.clone();
+        let no_receivers = match self.session_channels.get(&session_id) {
+            Some(sender) => sender.send(entry).is_err(),
+            None => return,
+        };
+        if no_receivers {
+            self.session_channels
+                .remove_if(&session_id, |_, sender| sender.receiver_count() == 0);
+        }
+    }
+
+    /// Records an operational event for a port in the audit log.
+    pub async fn record_audit(
+        &self,
+        event_type: AuditEventType,
+        port_name: &str,
+        session_id: &str,
+        details: serde_json::Value,
+    ) -> Result<(), String> {
+        let model = audit_entity::ActiveModel {
+            timestamp: Set(now_ms()),
+            event_type: Set(event_type.as_str().to_string()),
+            port_name: Set(port_name.to_string()),
+            session_id: Set(session_id.to_string()),
+            details: Set(details.to_string()),
+            ..Default::default()
+        };
+        audit_entity::Entity::insert(model)
+            .exec(self.connection.as_ref())
+            .await
+            .map_err(|e| format!("Failed to record audit event: {}", e))?;
+        Ok(())
+    }
 
-        Ok(result.id)
+    /// Returns audit entries, of one session or of all, newest first.
+    pub async fn get_audit_log(
+        &self,
+        session_id: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<AuditEntry>, String> {
+        audit_entity::Entity::find()
+            .filter(
+                Condition::all()
+                    .add_option(session_id.map(|id| audit_entity::Column::SessionId.eq(id))),
+            )
+            .order_by_desc(audit_entity::Column::Timestamp)
+            .order_by_desc(audit_entity::Column::Id)
+            .limit(Some(limit as u64))
+            .all(self.connection.as_ref())
+            .await
+            .map_err(|e| format!("Failed to query audit log: {}", e))
+    }
+
+    /// Deletes all log entries older than `cutoff_ms`, returning how many were removed.
+    pub async fn delete_before(&self, cutoff_ms: i64) -> Result<u64, String> {
+        let result = entity::Entity::delete_many()
+            .filter(entity::Column::Timestamp.lt(cutoff_ms))
+            .exec(self.connection.as_ref())
+            .await
+            .map_err(|e| format!("Failed to delete old logs: {}", e))?;
+        Ok(result.rows_affected)
     }
 
     pub async fn get_by_session(
@@ -141,6 +903,233 @@ impl Storage {
             .map_err(|e| format!("Failed to query logs by session: {}", e))
     }
 
+    /// Returns the entries of a session logged between `start_ms` and `end_ms`, oldest first.
+    ///
+    /// Both bounds are inclusive Unix epoch milliseconds.
+    pub async fn get_by_time_range(
+        &self,
+        session_id: &str,
+        start_ms: i64,
+        end_ms: i64,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<LogEntry>, String> {
+        entity::Entity::find()
+            .filter(entity::Column::SessionId.eq(session_id))
+            .filter(entity::Column::Timestamp.gte(start_ms))
+            .filter(entity::Column::Timestamp.lte(end_ms))
+            .order_by_asc(entity::Column::Timestamp)
+            .order_by_asc(entity::Column::Id)
+            .limit(Some(limit as u64))
+            .offset(Some(offset as u64))
+            .all(self.connection.as_ref())
+            .await
+            .map_err(|e| format!("Failed to query logs by time range: {}", e))
+    }
+
+    /// Returns the entries of a session matching every filter set in `query`.
+    pub async fn query_logs(&self, query: &LogQuery) -> Result<Vec<LogEntry>, String> {
+        let pattern = query
+            .pattern_hex
+            .as_deref()
+            .map(|pattern| hex::decode(pattern.replace(' ', "")))
+            .transpose()
+            .map_err(|e| format!("Invalid hex pattern: {}", e))?
+            .filter(|pattern| !pattern.is_empty());
+
+        // Every value is bound as a parameter, nothing from the query is spliced into SQL
+        let condition = Condition::all()
+            .add(entity::Column::SessionId.eq(query.session_id.as_str()))
+            .add_option(
+                query
+                    .direction
+                    .as_deref()
+                    .map(|direction| entity::Column::Direction.eq(direction.to_uppercase())),
+            )
+            .add_option(
+                query
+                    .start_ts_ms
+                    .map(|ts| entity::Column::Timestamp.gte(ts)),
+            )
+            .add_option(query.end_ts_ms.map(|ts| entity::Column::Timestamp.lte(ts)))
+            .add_option(
+                pattern.map(|pattern| Expr::cust_with_values("instr(data, ?) > 0", [pattern])),
+            );
+        let order = if query.ascending {
+            Order::Asc
+        } else {
+            Order::Desc
+        };
+
+        entity::Entity::find()
+            .filter(condition)
+            .order_by(entity::Column::Timestamp, order.clone())
+            .order_by(entity::Column::Id, order)
+            .limit(Some(query.limit as u64))
+            .offset(Some(query.offset as u64))
+            .all(self.connection.as_ref())
+            .await
+            .map_err(|e| format!("Failed to query logs: {}", e))
+    }
+
+    /// Returns the entries of a session whose data matches an FTS5 `query`, newest first.
+    ///
+    /// Bytes that are not UTF-8 are indexed as `\xNN`, the tokenizer sees them as `xNN`.
+    #[allow(dead_code)]
+    pub async fn full_text_search(
+        &self,
+        session_id: &str,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<LogEntry>, String> {
+        entity::Entity::find()
+            .from_raw_sql(Statement::from_sql_and_values(
+                DbBackend::Sqlite,
+                r#"
+                SELECT logs.* FROM logs
+                JOIN logs_fts ON logs.id = logs_fts.rowid
+                WHERE logs_fts MATCH ? AND logs.session_id = ?
+                ORDER BY logs.timestamp DESC, logs.id DESC
+                LIMIT ?
+                "#,
+                [query.into(), session_id.into(), (limit as i64).into()],
+            ))
+            .all(self.connection.as_ref())
+            .await
+            .map_err(|e| format!("Failed to search logs: {}", e))
+    }
+
+    /// Summarizes stored log data per device and port, largest first.
+    #[allow(dead_code)]
+    pub async fn get_storage_usage(&self) -> Result<Vec<StorageUsage>, String> {
+        StorageUsage::find_by_statement(Statement::from_string(
+            DbBackend::Sqlite,
+            r#"
+            SELECT device_fingerprint,
+                   port_name,
+                   COUNT(DISTINCT session_id) AS session_count,
+                   COUNT(*) AS total_entries,
+                   COALESCE(SUM(LENGTH(data)), 0) AS total_bytes_in_data_column
+            FROM logs
+            GROUP BY device_fingerprint, port_name
+            ORDER BY total_bytes_in_data_column DESC
+            "#,
+        ))
+        .all(self.connection.as_ref())
+        .await
+        .map_err(|e| format!("Failed to query storage usage: {}", e))
+    }
+
+    /// Size of the database file in bytes, as `page_count * page_size`.
+    pub async fn total_db_size_bytes(&self) -> Result<u64, String> {
+        use sea_orm::ConnectionTrait;
+
+        let row = self
+            .connection
+            .query_one(Statement::from_string(
+                DbBackend::Sqlite,
+                "SELECT page_count * page_size AS size FROM pragma_page_count(), pragma_page_size()",
+            ))
+            .await
+            .map_err(|e| format!("Failed to query database size: {}", e))?
+            .ok_or_else(|| "Failed to query database size: no result".to_string())?;
+        let size: i64 = row
+            .try_get("", "size")
+            .map_err(|e| format!("Failed to read database size: {}", e))?;
+        Ok(size as u64)
+    }
+
+    /// Checks the database for corruption and for rows referencing missing parents.
+    pub async fn integrity_check(&self) -> Result<StorageIntegrityReport, String> {
+        use sea_orm::ConnectionTrait;
+
+        let mut issues = Vec::new();
+        let rows = self
+            .connection
+            .query_all(Statement::from_string(
+                DbBackend::Sqlite,
+                "PRAGMA integrity_check",
+            ))
+            .await
+            .map_err(|e| format!("Failed to run integrity check: {}", e))?;
+        for row in rows {
+            let message: String = row
+                .try_get_by_index(0)
+                .map_err(|e| format!("Failed to read integrity check result: {}", e))?;
+            // A healthy database reports a single "ok" row
+            if message != "ok" {
+                issues.push(message);
+            }
+        }
+
+        let rows = self
+            .connection
+            .query_all(Statement::from_string(
+                DbBackend::Sqlite,
+                "PRAGMA foreign_key_check",
+            ))
+            .await
+            .map_err(|e| format!("Failed to run foreign key check: {}", e))?;
+        for row in rows {
+            let table: String = row.try_get("", "table").unwrap_or_default();
+            let rowid: Option<i64> = row.try_get("", "rowid").unwrap_or_default();
+            let parent: String = row.try_get("", "parent").unwrap_or_default();
+            issues.push(format!(
+                "{} row {} references a missing {} row",
+                table,
+                rowid.map_or_else(|| "?".to_string(), |id| id.to_string()),
+                parent
+            ));
+        }
+
+        Ok(StorageIntegrityReport {
+            ok: issues.is_empty(),
+            issues,
+        })
+    }
+
+    /// Rebuilds the indexes and the database file, then checks it again.
+    ///
+    /// `VACUUM` copies all content into a fresh file, which fixes most
+    /// corruption outside the table data itself. Indexes, triggers and the
+    /// full-text table are re-created if they went missing.
+    pub async fn repair(&self) -> Result<RepairReport, String> {
+        use sea_orm::ConnectionTrait;
+
+        let size_before_bytes = self.total_db_size_bytes().await?;
+        self.connection
+            .execute_unprepared("REINDEX")
+            .await
+            .map_err(|e| format!("Failed to rebuild indexes: {}", e))?;
+        Self::init_schema(&self.connection).await?;
+        self.connection
+            .execute_unprepared("VACUUM")
+            .await
+            .map_err(|e| format!("Failed to vacuum database: {}", e))?;
+        let size_after_bytes = self.total_db_size_bytes().await?;
+        tracing::info!(
+            "repaired database, {} bytes before, {} bytes after",
+            size_before_bytes,
+            size_after_bytes
+        );
+
+        Ok(RepairReport {
+            size_before_bytes,
+            size_after_bytes,
+            integrity: self.integrity_check().await?,
+        })
+    }
+
+    /// Finds the entry logged for the write with `message_id`.
+    pub async fn get_by_message_id(&self, message_id: &str) -> Result<Option<LogEntry>, String> {
+        entity::Entity::find()
+            .filter(entity::Column::MessageId.eq(message_id))
+            .order_by_desc(entity::Column::Id)
+            .one(self.connection.as_ref())
+            .await
+            .map_err(|e| format!("Failed to query log by message id: {}", e))
+    }
+
     #[allow(dead_code)]
     pub async fn get_by_device(
         &self,
@@ -165,10 +1154,13 @@ impl Default for Storage {
         // This creates a placeholder that should be replaced with proper async initialization
         // In practice, AppState initialization should use Storage::new_in_memory().await
 
-        // Use tokio's block_in_place if we're in a tokio runtime, otherwise panic
+        // Use tokio's block_in_place if we're in a tokio runtime, otherwise
+        // spin up a temporary runtime
         let rt = tokio::runtime::Handle::try_current();
         match rt {
-            Ok(handle) => handle.block_on(async { Storage::new_in_memory().await }),
+            Ok(handle) => tokio::task::block_in_place(|| {
+                handle.block_on(async { Storage::new_in_memory().await })
+            }),
             Err(_) => {
                 // Create a temporary runtime for initialization
                 let rt = tokio::runtime::Runtime::new().expect("Failed to create runtime");
@@ -185,8 +1177,658 @@ pub fn generate_device_fingerprint(
     match port_type {
         crate::serial::port_type::PortType::UsbPort(usb) => {
             let serial = usb.serial_number.as_deref().unwrap_or("unknown");
-            format!("usb:{:04X}:{:04X}:{}", usb.vid, usb.pid, serial)
+            match usb.hub_port {
+                Some(hub_port) => format!(
+                    "usb:{:04X}:{:04X}:{}/hub:{}",
+                    usb.vid, usb.pid, serial, hub_port
+                ),
+                None => format!("usb:{:04X}:{:04X}:{}", usb.vid, usb.pid, serial),
+            }
         }
         _ => format!("port:{}", port_name),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Inserts a received message on COM1, returning its id.
+    async fn insert_rx(
+        storage: &Storage,
+        session_id: &str,
+        data: &[u8],
+        timestamp_ms: Option<i64>,
+    ) -> i64 {
+        storage
+            .insert(
+                "port:COM1",
+                session_id,
+                None,
+                None,
+                None,
+                "COM1",
+                "RX",
+                data,
+                timestamp_ms,
+                None,
+            )
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn delete_session_cascades_to_logs() {
+        let storage = Storage::new_in_memory().await;
+        storage
+            .begin_session("s1", "COM1", "port:COM1", None, None, None, 9600, "{}")
+            .await
+            .unwrap();
+        for data in [b"a", b"b"] {
+            insert_rx(&storage, "s1", data, None).await;
+        }
+        assert_eq!(storage.get_by_session("s1", 10, 0).await.unwrap().len(), 2);
+
+        assert_eq!(storage.delete_session("s1").await.unwrap(), 2);
+        assert!(storage
+            .get_by_session("s1", 10, 0)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(storage.delete_session("s1").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn insert_batch_returns_ids_in_order() {
+        let storage = Storage::new_in_memory().await;
+        storage
+            .begin_session("s1", "COM1", "port:COM1", None, None, None, 9600, "{}")
+            .await
+            .unwrap();
+        let entries: Vec<InsertParams> = (0..storage::LOG_BATCH_MAX_ROWS + 3)
+            .map(|i| InsertParams {
+                device_fingerprint: "port:COM1".to_string(),
+                session_id: "s1".to_string(),
+                vid: None,
+                pid: None,
+                serial_number: None,
+                port_name: "COM1".to_string(),
+                direction: "RX".to_string(),
+                data: format!("frame{}", i).into_bytes(),
+                timestamp_ms: Some(i as i64),
+                message_id: None,
+            })
+            .collect();
+
+        let ids = storage.insert_batch(entries).await.unwrap();
+        let mut stored = storage.get_by_session("s1", 1000, 0).await.unwrap();
+        stored.reverse();
+        assert_eq!(stored.iter().map(|e| e.id).collect::<Vec<_>>(), ids);
+        // The full-text index must point at the same rows
+        let last = storage::LOG_BATCH_MAX_ROWS + 2;
+        let found = storage
+            .full_text_search("s1", &format!("frame{}", last), 10)
+            .await
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, ids[last]);
+        assert!(storage.insert_batch(Vec::new()).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn session_summary_is_served_from_cache_until_invalidated() {
+        use sea_orm::ConnectionTrait;
+
+        let storage = Storage::new_in_memory().await;
+        storage
+            .begin_session("s1", "COM1", "port:COM1", None, None, None, 9600, "{}")
+            .await
+            .unwrap();
+        for (direction, data) in [("rx", &b"abc"[..]), ("RX", b"de"), ("tx", b"f")] {
+            storage
+                .insert(
+                    "port:COM1",
+                    "s1",
+                    None,
+                    None,
+                    None,
+                    "COM1",
+                    direction,
+                    data,
+                    None,
+                    None,
+                )
+                .await
+                .unwrap();
+        }
+
+        let summary = storage.get_session_summary("s1").await.unwrap();
+        assert_eq!(
+            (
+                summary.message_count,
+                summary.rx_count,
+                summary.tx_count,
+                summary.total_bytes
+            ),
+            (3, 2, 1, 6)
+        );
+
+        // A cached value that disagrees with the logs shows the scan was skipped,
+        // however long ago the row was last updated
+        storage
+            .connection
+            .execute_unprepared("UPDATE session_stats SET message_count = 99, last_updated = 1")
+            .await
+            .unwrap();
+        let summary = storage.get_session_summary("s1").await.unwrap();
+        assert_eq!(summary.message_count, 99);
+
+        storage.invalidate_session_stats("s1").await.unwrap();
+        let summary = storage.get_session_summary("s1").await.unwrap();
+        assert_eq!(summary.message_count, 3);
+        assert!(storage.get_session_summary("missing").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn integrity_check_reports_orphaned_logs_until_repaired() {
+        use sea_orm::ConnectionTrait;
+
+        let storage = Storage::new_in_memory().await;
+        let report = storage.integrity_check().await.unwrap();
+        assert!(report.ok, "{:?}", report.issues);
+
+        storage
+            .begin_session("s1", "COM1", "port:COM1", None, None, None, 9600, "{}")
+            .await
+            .unwrap();
+        storage
+            .insert(
+                "port:COM1",
+                "s1",
+                None,
+                None,
+                None,
+                "COM1",
+                "rx",
+                b"a",
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+        // Deleting the session behind the foreign key's back orphans its logs
+        storage
+            .connection
+            .execute_unprepared(
+                "PRAGMA foreign_keys = OFF; DELETE FROM sessions; PRAGMA foreign_keys = ON;",
+            )
+            .await
+            .unwrap();
+        let report = storage.integrity_check().await.unwrap();
+        assert!(!report.ok);
+        assert!(report.issues.iter().any(|issue| issue.starts_with("logs")));
+
+        // Repairing rebuilds the file, it does not invent the missing session
+        let repaired = storage.repair().await.unwrap();
+        assert!(!repaired.integrity.ok);
+        assert!(repaired.size_after_bytes > 0);
+    }
+
+    #[tokio::test]
+    async fn insert_requires_existing_session() {
+        let storage = Storage::new_in_memory().await;
+        let result = storage
+            .insert(
+                "port:COM1",
+                "missing",
+                None,
+                None,
+                None,
+                "COM1",
+                "rx",
+                b"a",
+                None,
+                None,
+            )
+            .await;
+        assert!(result.is_err());
+    }
+
+    fn deduped_params(direction: &str, data: &[u8], timestamp_ms: i64) -> InsertParams {
+        InsertParams {
+            device_fingerprint: "port:COM1".to_string(),
+            session_id: "s1".to_string(),
+            vid: None,
+            pid: None,
+            serial_number: None,
+            port_name: "COM1".to_string(),
+            direction: direction.to_string(),
+            data: data.to_vec(),
+            timestamp_ms: Some(timestamp_ms),
+            message_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn insert_deduped_folds_repeats_within_interval() {
+        let storage = Storage::new_in_memory().await;
+        storage
+            .begin_session("s1", "COM1", "port:COM1", None, None, None, 9600, "{}")
+            .await
+            .unwrap();
+
+        let first = storage
+            .insert_deduped(deduped_params("RX", b"OK", 100), 1000)
+            .await
+            .unwrap();
+        let repeat = storage
+            .insert_deduped(deduped_params("RX", b"OK", 600), 1000)
+            .await
+            .unwrap();
+        assert_eq!(repeat, first);
+        let logs = storage.get_by_session("s1", 10, 0).await.unwrap();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].repeat_count, 2);
+        // The entry keeps the time of its first occurrence
+        assert_eq!(logs[0].timestamp, 100);
+    }
+
+    #[tokio::test]
+    async fn insert_deduped_keeps_different_messages_apart() {
+        let storage = Storage::new_in_memory().await;
+        storage
+            .begin_session("s1", "COM1", "port:COM1", None, None, None, 9600, "{}")
+            .await
+            .unwrap();
+
+        let rx = storage
+            .insert_deduped(deduped_params("RX", b"OK", 100), 1000)
+            .await
+            .unwrap();
+        let other_data = storage
+            .insert_deduped(deduped_params("RX", b"ERROR", 200), 1000)
+            .await
+            .unwrap();
+        let other_direction = storage
+            .insert_deduped(deduped_params("TX", b"ERROR", 300), 1000)
+            .await
+            .unwrap();
+        assert!(rx < other_data && other_data < other_direction);
+        let logs = storage.get_by_session("s1", 10, 0).await.unwrap();
+        assert_eq!(logs.len(), 3);
+        assert!(logs.iter().all(|entry| entry.repeat_count == 1));
+    }
+
+    #[tokio::test]
+    async fn insert_deduped_starts_new_entry_after_interval() {
+        let storage = Storage::new_in_memory().await;
+        storage
+            .begin_session("s1", "COM1", "port:COM1", None, None, None, 9600, "{}")
+            .await
+            .unwrap();
+
+        let first = storage
+            .insert_deduped(deduped_params("RX", b"OK", 100), 1000)
+            .await
+            .unwrap();
+        let late = storage
+            .insert_deduped(deduped_params("RX", b"OK", 1100), 1000)
+            .await
+            .unwrap();
+        assert_ne!(late, first);
+        assert_eq!(storage.get_by_session("s1", 10, 0).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn repeat_count_is_added_to_old_logs_table() {
+        use sea_orm::ConnectionTrait;
+
+        let connection = Database::connect(Storage::connect_options("sqlite::memory:"))
+            .await
+            .unwrap();
+        // The logs table as created before deduplication
+        connection
+            .execute_unprepared(
+                r#"
+                CREATE TABLE logs (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    device_fingerprint TEXT NOT NULL,
+                    session_id TEXT NOT NULL,
+                    vid TEXT,
+                    pid TEXT,
+                    serial_number TEXT,
+                    port_name TEXT NOT NULL,
+                    direction TEXT NOT NULL,
+                    timestamp INTEGER NOT NULL,
+                    data BLOB NOT NULL
+                );
+                INSERT INTO logs (device_fingerprint, session_id, port_name, direction, timestamp, data)
+                VALUES ('port:COM1', 's1', 'COM1', 'RX', 100, X'4F4B');
+                "#,
+            )
+            .await
+            .unwrap();
+        assert!(!Storage::logs_has_column(&connection, "repeat_count")
+            .await
+            .unwrap());
+
+        Storage::init_schema(&connection).await.unwrap();
+        assert!(Storage::logs_has_column(&connection, "repeat_count")
+            .await
+            .unwrap());
+        let storage = Storage {
+            db_path: PathBuf::from(":memory:"),
+            connection: Arc::new(connection),
+            session_channels: Arc::new(DashMap::new()),
+        };
+        let logs = storage.get_by_session("s1", 10, 0).await.unwrap();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].repeat_count, 1);
+
+        // Existing entries can be deduplicated against
+        let id = storage
+            .insert_deduped(deduped_params("RX", b"OK", 200), 1000)
+            .await
+            .unwrap();
+        assert_eq!(id, logs[0].id);
+        let logs = storage.get_by_session("s1", 10, 0).await.unwrap();
+        assert_eq!(logs[0].repeat_count, 2);
+    }
+
+    #[tokio::test]
+    async fn storage_usage_is_grouped_by_device_and_port() {
+        let storage = Storage::new_in_memory().await;
+        for (session_id, port_name) in [("s1", "COM1"), ("s2", "COM1"), ("s3", "COM2")] {
+            let fingerprint = format!("port:{}", port_name);
+            storage
+                .begin_session(
+                    session_id,
+                    port_name,
+                    &fingerprint,
+                    None,
+                    None,
+                    None,
+                    9600,
+                    "{}",
+                )
+                .await
+                .unwrap();
+        }
+        insert_rx(&storage, "s1", b"abc", None).await;
+        insert_rx(&storage, "s2", b"de", None).await;
+        let size_before = storage.total_db_size_bytes().await.unwrap();
+        storage
+            .insert(
+                "port:COM2",
+                "s3",
+                None,
+                None,
+                None,
+                "COM2",
+                "RX",
+                &[0u8; 64 * 1024],
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        let usage = storage.get_storage_usage().await.unwrap();
+        let summary: Vec<_> = usage
+            .iter()
+            .map(|u| {
+                (
+                    u.device_fingerprint.as_str(),
+                    u.port_name.as_str(),
+                    u.session_count,
+                    u.total_entries,
+                    u.total_bytes_in_data_column,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("port:COM2", "COM2", 1, 1, 64 * 1024),
+                ("port:COM1", "COM1", 2, 2, 5),
+            ]
+        );
+
+        let size_after = storage.total_db_size_bytes().await.unwrap();
+        assert!(size_before > 0);
+        assert!(size_after >= size_before + 64 * 1024);
+    }
+
+    #[tokio::test]
+    async fn get_by_message_id() {
+        let storage = Storage::new_in_memory().await;
+        storage
+            .begin_session("s1", "COM1", "port:COM1", None, None, None, 9600, "{}")
+            .await
+            .unwrap();
+        let id = storage
+            .insert(
+                "port:COM1",
+                "s1",
+                None,
+                None,
+                None,
+                "COM1",
+                "TX",
+                b"a",
+                None,
+                Some("m1"),
+            )
+            .await
+            .unwrap();
+
+        let entry = storage.get_by_message_id("m1").await.unwrap().unwrap();
+        assert_eq!(entry.id, id);
+        assert!(storage.get_by_message_id("m2").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn query_logs_combines_filters() {
+        let storage = Storage::new_in_memory().await;
+        storage
+            .begin_session("s1", "COM1", "port:COM1", None, None, None, 9600, "{}")
+            .await
+            .unwrap();
+        let entries: [(&str, &[u8], i64); 4] = [
+            ("RX", b"\x01\x02\x03", 100),
+            ("TX", b"\x01\x02\x03", 200),
+            ("RX", b"\x02\x03\x04", 300),
+            ("RX", b"\x05", 400),
+        ];
+        for (direction, data, ts) in entries {
+            storage
+                .insert(
+                    "port:COM1",
+                    "s1",
+                    None,
+                    None,
+                    None,
+                    "COM1",
+                    direction,
+                    data,
+                    Some(ts),
+                    None,
+                )
+                .await
+                .unwrap();
+        }
+
+        let query = LogQuery {
+            session_id: "s1".to_string(),
+            direction: Some("rx".to_string()),
+            start_ts_ms: Some(100),
+            end_ts_ms: Some(300),
+            pattern_hex: Some("02 03".to_string()),
+            limit: 10,
+            offset: 0,
+            ascending: true,
+        };
+        let timestamps: Vec<i64> = storage
+            .query_logs(&query)
+            .await
+            .unwrap()
+            .iter()
+            .map(|entry| entry.timestamp)
+            .collect();
+        assert_eq!(timestamps, vec![100, 300]);
+
+        let invalid = LogQuery {
+            pattern_hex: Some("zz".to_string()),
+            ..query
+        };
+        assert!(storage.query_logs(&invalid).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn get_by_time_range_includes_both_bounds() {
+        let storage = Storage::new_in_memory().await;
+        storage
+            .begin_session("s1", "COM1", "port:COM1", None, None, None, 9600, "{}")
+            .await
+            .unwrap();
+        for ts in [100, 200, 300] {
+            insert_rx(&storage, "s1", b"x", Some(ts)).await;
+        }
+        let timestamps = |logs: Vec<LogEntry>| -> Vec<i64> {
+            logs.iter().map(|entry| entry.timestamp).collect()
+        };
+
+        let logs = storage
+            .get_by_time_range("s1", 100, 300, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(timestamps(logs), vec![100, 200, 300]);
+        let logs = storage
+            .get_by_time_range("s1", 200, 200, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(timestamps(logs), vec![200]);
+        let logs = storage
+            .get_by_time_range("s1", 201, 299, 10, 0)
+            .await
+            .unwrap();
+        assert!(logs.is_empty());
+        let logs = storage
+            .get_by_time_range("s2", 100, 300, 10, 0)
+            .await
+            .unwrap();
+        assert!(logs.is_empty());
+    }
+
+    #[tokio::test]
+    async fn delete_before_keeps_entries_at_cutoff() {
+        let storage = Storage::new_in_memory().await;
+        storage
+            .begin_session("s1", "COM1", "port:COM1", None, None, None, 9600, "{}")
+            .await
+            .unwrap();
+        for ts in [100, 200, 300] {
+            insert_rx(&storage, "s1", b"x", Some(ts)).await;
+        }
+
+        assert_eq!(storage.delete_before(200).await.unwrap(), 1);
+        let logs = storage
+            .get_by_time_range("s1", 0, 150, 10, 0)
+            .await
+            .unwrap();
+        assert!(logs.is_empty());
+        assert_eq!(storage.get_by_session("s1", 10, 0).await.unwrap().len(), 2);
+        assert_eq!(storage.delete_before(1000).await.unwrap(), 2);
+        assert!(storage
+            .get_by_session("s1", 10, 0)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn full_text_search_matches_decoded_text() {
+        let storage = Storage::new_in_memory().await;
+        for session_id in ["s1", "s2"] {
+            storage
+                .begin_session(
+                    session_id,
+                    "COM1",
+                    "port:COM1",
+                    None,
+                    None,
+                    None,
+                    9600,
+                    "{}",
+                )
+                .await
+                .unwrap();
+        }
+        let entries: [(&str, &[u8]); 3] = [
+            ("s1", b"OK temperature=21"),
+            ("s1", b"ERROR \xff\xfe"),
+            ("s2", b"OK other session"),
+        ];
+        for (session_id, data) in entries {
+            storage
+                .insert(
+                    "port:COM1",
+                    session_id,
+                    None,
+                    None,
+                    None,
+                    "COM1",
+                    "RX",
+                    data,
+                    None,
+                    None,
+                )
+                .await
+                .unwrap();
+        }
+
+        let found = storage.full_text_search("s1", "ok", 10).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].data, b"OK temperature=21");
+        let found = storage.full_text_search("s1", "xFF", 10).await.unwrap();
+        assert_eq!(found[0].data, b"ERROR \xff\xfe");
+        assert!(storage.full_text_search("s1", "\"", 10).await.is_err());
+
+        storage.delete_session("s2").await.unwrap();
+        assert!(storage
+            .full_text_search("s2", "other", 10)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn audit_log_is_filtered_by_session_newest_first() {
+        let storage = Storage::new_in_memory().await;
+        let events = [
+            (AuditEventType::PortOpened, "s1"),
+            (AuditEventType::WriteStarted, "s1"),
+            (AuditEventType::PortOpened, "s2"),
+        ];
+        for (event_type, session_id) in events {
+            storage
+                .record_audit(event_type, "COM1", session_id, serde_json::json!({}))
+                .await
+                .unwrap();
+        }
+
+        let types: Vec<String> = storage
+            .get_audit_log(Some("s1"), 10)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.event_type)
+            .collect();
+        assert_eq!(types, vec!["write_started", "port_opened"]);
+        assert_eq!(storage.get_audit_log(None, 10).await.unwrap().len(), 3);
+        assert_eq!(
+            storage.get_audit_log(None, 1).await.unwrap()[0].session_id,
+            "s2"
+        );
+    }
+}
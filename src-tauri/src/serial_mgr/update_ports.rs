@@ -1,17 +1,64 @@
+use std::{collections::HashSet, time::Duration};
+
 use rootcause::Report;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use tokio_serial::SerialPortInfo;
+use tracing::Instrument;
 
-use crate::state::{AppState, PortInfo, PortStatus};
+use crate::{
+    constants::serial,
+    events::{event_names, PortClosedEvent, PortListChangedEvent, PortRemovedEvent},
+    serial::{port_type::PortType, reconnect_policy::ReconnectPolicy},
+    serial_mgr::{helpers::timestamp_now_ms, port_task::WriteCmd},
+    state::{AppState, OpenPortInfo, PortInfo, PortStatus},
+};
 
 pub async fn update_available_ports<'a>(
+    app: &AppHandle,
     state: &tauri::State<'a, AppState>,
 ) -> Result<Vec<PortInfo>, Report> {
-    let system_ports_res = tokio_serial::available_ports()?;
+    // Enumeration can take a while on some platforms, keep it off the async workers
+    let system_ports_res = tokio::task::spawn_blocking(tokio_serial::available_ports).await??;
     tracing::trace!(
         "get all available ports from system success, cnt: {}",
         system_ports_res.len()
     );
-    for port in system_ports_res.iter() {
-        if state.ports.contains_key(&port.port_name) {
+    Ok(apply_port_list(
+        app,
+        state,
+        &system_ports_res,
+        timestamp_now_ms(),
+    ))
+}
+
+/// Merges one scan result into `state.ports` and returns the updated list.
+///
+/// Closed ports the OS no longer reports are listed as disconnected for
+/// [`serial::DISCONNECTED_PORT_RETENTION_MS`], then removed and announced with
+/// a single `port_list_changed` event. Open ports that disappear are closed
+/// as if their connection was lost, unless their reconnect policy reopens them.
+fn apply_port_list<R: Runtime>(
+    app: &AppHandle<R>,
+    state: &AppState,
+    system_ports: &[SerialPortInfo],
+    now_ms: u128,
+) -> Vec<PortInfo> {
+    for port in system_ports.iter() {
+        if let Some(mut entry) = state.ports.get_mut(&port.port_name) {
+            entry.last_seen_ms = Some(now_ms);
+            if matches!(entry.port_status, PortStatus::Disconnected) {
+                tracing::debug!("port {} reconnected", port.port_name);
+                entry.port_status = PortStatus::Closed;
+            }
+            // A replugged device may report different USB info under the same name.
+            // Opened ports keep theirs so the active session stays consistent.
+            if matches!(entry.port_status, PortStatus::Closed) {
+                let port_type = PortType::from_system(&port.port_name, port.port_type.clone());
+                if entry.port_type != port_type {
+                    tracing::debug!("port {} type changed", port.port_name);
+                    entry.port_type = port_type;
+                }
+            }
             continue;
         }
 
@@ -20,23 +67,289 @@ pub async fn update_available_ports<'a>(
             port.port_name.clone(),
             PortInfo {
                 port_name: port.port_name.clone(),
-                port_type: port.port_type.clone().into(),
+                port_type: PortType::from_system(&port.port_name, port.port_type.clone()),
                 port_status: PortStatus::Closed,
                 bytes_read: 0,
                 bytes_write: 0,
+                last_seen_ms: Some(now_ms),
             },
         );
     }
-    Ok(state
+
+    let current_port_names: HashSet<&str> = system_ports
+        .iter()
+        .map(|port| port.port_name.as_str())
+        .collect();
+    // Collected first so a `port_handles` guard is never held while locking `ports`
+    let open_port_names: HashSet<String> = state
+        .port_handles
+        .iter()
+        .map(|entry| entry.key().clone())
+        .collect();
+    let is_stale = |info: &PortInfo| {
+        let recently_seen = info.last_seen_ms.is_some_and(|seen| {
+            now_ms.saturating_sub(seen) < serial::DISCONNECTED_PORT_RETENTION_MS
+        });
+        !recently_seen
+            && matches!(
+                info.port_status,
+                PortStatus::Closed | PortStatus::Disconnected
+            )
+    };
+    let mut stale_ports = Vec::new();
+    let mut lost_ports = Vec::new();
+    for mut entry in state.ports.iter_mut() {
+        if current_port_names.contains(entry.key().as_str()) {
+            continue;
+        }
+        match entry.port_status {
+            // Marked closing so the next scan or a close_port call leaves it alone.
+            // Ports with a reconnect policy wait for the device to come back instead.
+            PortStatus::Opened(ref profile)
+                if profile.reconnect_policy == ReconnectPolicy::Never
+                    && open_port_names.contains(entry.key()) =>
+            {
+                lost_ports.push(entry.key().clone());
+                entry.port_status = PortStatus::Closing;
+            }
+            _ if is_stale(&entry) => stale_ports.push(entry.key().clone()),
+            PortStatus::Closed => {
+                tracing::debug!("port {} disconnected", entry.key());
+                entry.port_status = PortStatus::Disconnected;
+            }
+            _ => {}
+        }
+    }
+
+    for port_name in lost_ports {
+        close_lost_port(app, state, port_name);
+    }
+
+    let mut removed = Vec::new();
+    for port_name in stale_ports {
+        if state
+            .ports
+            .remove_if(&port_name, |_, info| is_stale(info))
+            .is_none()
+        {
+            continue;
+        }
+        tracing::info!("port removed: {}", port_name);
+        if let Err(err) = app.emit(
+            event_names::PORT_REMOVED,
+            PortRemovedEvent::new(port_name.clone()),
+        ) {
+            tracing::error!("emit port removed event failed: {}", err);
+        }
+        removed.push(port_name);
+    }
+    if !removed.is_empty() {
+        if let Err(err) = app.emit(
+            event_names::PORT_LIST_CHANGED,
+            PortListChangedEvent::new(removed),
+        ) {
+            tracing::error!("emit port list changed event failed: {}", err);
+        }
+    }
+
+    state
         .ports
         .iter()
         .map(|entry| entry.value().clone())
-        .collect())
+        .collect()
+}
+
+/// Closes an open port that is no longer reported by the system.
+///
+/// Asks the port task to close like `close_port` does, without waiting for
+/// the ack since the scan must not block on a port that is gone. The task's
+/// cleanup then sets the status to Closed and removes the handle.
+fn close_lost_port<R: Runtime>(app: &AppHandle<R>, state: &AppState, port_name: String) {
+    let Some((sender, shutdown)) = state
+        .port_handles
+        .get(&port_name)
+        .map(|handles| (handles.write_port_tx.clone(), handles.shutdown.clone()))
+    else {
+        return;
+    };
+    tracing::warn!("open port {} unplugged, closing it", port_name);
+    if let Err(err) = sender.try_send((WriteCmd::Close, None)) {
+        // Queue full or task already gone, cancelling stops it either way
+        tracing::debug!("send close to port {} failed: {}", port_name, err);
+        shutdown.cancel();
+    }
+    if let Err(err) = app.emit(
+        event_names::PORT_CLOSED,
+        PortClosedEvent::connection_lost(port_name),
+    ) {
+        tracing::error!("emit port closed event failed: {}", err);
+    }
 }
 
+/// Spawns the task that periodically rescans the system for serial ports.
+///
+/// `AppState` must already be managed by the app when this is called.
+pub fn spawn_port_scan_task(app: AppHandle) -> tauri::async_runtime::JoinHandle<()> {
+    tauri::async_runtime::spawn(
+        async move {
+            loop {
+                let state = app.state::<AppState>();
+                if let Err(err) = update_available_ports(&app, &state).await {
+                    tracing::warn!("background port scan failed: {}", err);
+                }
+                // Read on every round so config changes apply without a restart
+                let interval_ms = state.config.read().port_scan_interval_ms;
+                tokio::time::sleep(Duration::from_millis(interval_ms)).await;
+            }
+        }
+        .instrument(tracing::debug_span!("port scan")),
+    )
+}
+
+/// Returns the port list maintained by the background scan task.
 #[tauri::command(rename_all = "camelCase")]
 pub async fn get_all_port_info(state: tauri::State<'_, AppState>) -> Result<Vec<PortInfo>, String> {
     tracing::info!("get all port info");
-    let res = update_available_ports(&state).await;
-    res.map_err(|err| err.to_string())
+    Ok(state
+        .ports
+        .iter()
+        .map(|entry| entry.value().clone())
+        .collect())
+}
+
+/// Returns the currently open ports.
+///
+/// Cheaper than `get_all_port_info` for frequent polling, closed ports are left out.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn get_open_ports(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<OpenPortInfo>, String> {
+    // Collect first so a `port_handles` guard is never held while locking `ports`
+    let handles: Vec<(String, String, u128)> = state
+        .port_handles
+        .iter()
+        .map(|entry| {
+            (
+                entry.key().clone(),
+                entry.session.current_id(),
+                entry.opened_at_ms,
+            )
+        })
+        .collect();
+    Ok(handles
+        .into_iter()
+        .filter_map(|(port_name, session_id, opened_at_ms)| {
+            let entry = state.ports.get(&port_name)?;
+            // Handles and status change separately while a port opens or closes
+            let PortStatus::Opened(profile) = &entry.port_status else {
+                return None;
+            };
+            Some(OpenPortInfo {
+                session_id,
+                opened_at_ms,
+                bytes_read: entry.bytes_read,
+                bytes_write: entry.bytes_write,
+                profile: profile.clone(),
+                port_name,
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serial_mgr::open_port::setup_port_task;
+    use crate::serial_mgr::virtual_port::create_virtual_port_pair;
+    use crate::state::OpenedPortProfile;
+    use tauri::Listener;
+    use tokio_serial::SerialPortType;
+
+    fn system_ports(names: &[&str]) -> Vec<SerialPortInfo> {
+        names
+            .iter()
+            .map(|name| SerialPortInfo {
+                port_name: name.to_string(),
+                port_type: SerialPortType::Unknown,
+            })
+            .collect()
+    }
+
+    // Multi-threaded because the default `Storage` blocks in place while it connects
+    #[tokio::test(flavor = "multi_thread")]
+    async fn unplugged_ports_are_removed_after_retention() {
+        let app = tauri::test::mock_app();
+        app.manage(AppState::default());
+        let state = app.state::<AppState>();
+        let (changed_tx, mut changed_rx) = tokio::sync::mpsc::unbounded_channel();
+        app.listen(event_names::PORT_LIST_CHANGED, move |event| {
+            let _ = changed_tx.send(event.payload().to_string());
+        });
+
+        let ports = apply_port_list(app.handle(), &state, &system_ports(&["COM1", "COM2"]), 0);
+        assert_eq!(ports.len(), 2);
+
+        // Still listed while the device may be replugged
+        apply_port_list(app.handle(), &state, &system_ports(&["COM1"]), 1);
+        assert!(matches!(
+            state.ports.get("COM2").unwrap().port_status,
+            PortStatus::Disconnected
+        ));
+        assert!(changed_rx.try_recv().is_err());
+
+        let now_ms = serial::DISCONNECTED_PORT_RETENTION_MS + 1;
+        let ports = apply_port_list(app.handle(), &state, &system_ports(&["COM1"]), now_ms);
+        assert_eq!(ports.len(), 1);
+        assert!(state.ports.get("COM2").is_none());
+        let payload: PortListChangedEvent =
+            serde_json::from_str(&changed_rx.try_recv().unwrap()).unwrap();
+        assert_eq!(payload.removed, vec!["COM2".to_string()]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn unplugged_open_port_is_closed() {
+        let app = tauri::test::mock_app();
+        app.manage(AppState::default());
+        let state = app.state::<AppState>();
+        let (closed_tx, mut closed_rx) = tokio::sync::mpsc::unbounded_channel();
+        app.listen(event_names::PORT_CLOSED, move |event| {
+            let _ = closed_tx.send(event.payload().to_string());
+        });
+
+        apply_port_list(app.handle(), &state, &system_ports(&["virtual-a"]), 0);
+        let profile = OpenedPortProfile {
+            baud_rate: 9600,
+            ..Default::default()
+        };
+        state.ports.get_mut("virtual-a").unwrap().port_status = PortStatus::Opened(profile.clone());
+        let (port, _peer) = create_virtual_port_pair();
+        let (handles, _) = setup_port_task(
+            "virtual-a".to_string(),
+            port,
+            || Err(std::io::ErrorKind::NotFound.into()),
+            app.handle().clone(),
+            PortType::Unknown,
+            profile,
+        )
+        .unwrap();
+        state.port_handles.insert("virtual-a".to_string(), handles);
+
+        apply_port_list(app.handle(), &state, &system_ports(&[]), 1);
+        let payload: PortClosedEvent =
+            serde_json::from_str(&closed_rx.try_recv().unwrap()).unwrap();
+        assert_eq!(payload.port_name, "virtual-a");
+        assert_eq!(payload.reason, "Connection Lost");
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !state.port_handles.is_empty() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("timed out");
+        assert!(matches!(
+            state.ports.get("virtual-a").unwrap().port_status,
+            PortStatus::Closed
+        ));
+    }
 }
@@ -0,0 +1,294 @@
+//! In-process serial port pair, for testing port tasks without hardware.
+
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncWrite, DuplexStream, ReadBuf};
+use tokio_serial::{ClearBuffer, DataBits, FlowControl, Parity, SerialPort, StopBits};
+
+/// Bytes in flight per direction before writes wait for the peer to read.
+const PIPE_CAPACITY: usize = 64 * 1024;
+
+/// Output lines set by one end of a pair.
+#[derive(Debug, Default)]
+struct Lines {
+    dtr: AtomicBool,
+    rts: AtomicBool,
+}
+
+/// One end of a virtual null-modem cable, see [`create_virtual_port_pair`].
+///
+/// Line settings are stored but have no effect. The peer sees DTR as DSR and
+/// carrier detect, and RTS as CTS.
+#[derive(Debug)]
+pub struct VirtualPort {
+    name: String,
+    stream: DuplexStream,
+    baud_rate: u32,
+    data_bits: DataBits,
+    flow_control: FlowControl,
+    parity: Parity,
+    stop_bits: StopBits,
+    timeout: Duration,
+    local: Arc<Lines>,
+    remote: Arc<Lines>,
+    /// Bytes the peer wrote that this end has not read yet.
+    unread: Arc<AtomicUsize>,
+    peer_unread: Arc<AtomicUsize>,
+    /// Set by [`VirtualPort::unplug`], shared by both ends.
+    unplugged: Arc<AtomicBool>,
+    /// Set by [`VirtualPort::fail_writes`], shared by both ends.
+    writes_fail: Arc<AtomicBool>,
+}
+
+/// Creates two connected ports: bytes written to one are read from the other.
+pub fn create_virtual_port_pair() -> (VirtualPort, VirtualPort) {
+    let (a_stream, b_stream) = tokio::io::duplex(PIPE_CAPACITY);
+    let (a_lines, b_lines) = (Arc::<Lines>::default(), Arc::<Lines>::default());
+    let (a_unread, b_unread) = (Arc::<AtomicUsize>::default(), Arc::<AtomicUsize>::default());
+    let (unplugged, writes_fail) = (Arc::<AtomicBool>::default(), Arc::<AtomicBool>::default());
+    (
+        VirtualPort::new(
+            "virtual-a",
+            a_stream,
+            (a_lines.clone(), b_lines.clone()),
+            (a_unread.clone(), b_unread.clone()),
+            (unplugged.clone(), writes_fail.clone()),
+        ),
+        VirtualPort::new(
+            "virtual-b",
+            b_stream,
+            (b_lines, a_lines),
+            (b_unread, a_unread),
+            (unplugged, writes_fail),
+        ),
+    )
+}
+
+impl VirtualPort {
+    /// `lines` and `unread` are pairs of this end's value and the peer's.
+    fn new(
+        name: &str,
+        stream: DuplexStream,
+        (local, remote): (Arc<Lines>, Arc<Lines>),
+        (unread, peer_unread): (Arc<AtomicUsize>, Arc<AtomicUsize>),
+        (unplugged, writes_fail): (Arc<AtomicBool>, Arc<AtomicBool>),
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            stream,
+            baud_rate: 9600,
+            data_bits: DataBits::Eight,
+            flow_control: FlowControl::None,
+            parity: Parity::None,
+            stop_bits: StopBits::One,
+            timeout: Duration::ZERO,
+            local,
+            remote,
+            unread,
+            peer_unread,
+            unplugged,
+            writes_fail,
+        }
+    }
+
+    /// Disconnects the pair like a pulled cable: reads on the other end fail
+    /// instead of reaching EOF.
+    pub fn unplug(self) {
+        self.unplugged.store(true, Ordering::SeqCst);
+    }
+
+    /// While set, writes on both ends fail with `BrokenPipe` but reads keep
+    /// working, like a device that stopped accepting data.
+    pub fn fail_writes(&self, fail: bool) {
+        self.writes_fail.store(fail, Ordering::SeqCst);
+    }
+}
+
+impl AsyncRead for VirtualPort {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let before = buf.filled().len();
+        let res = Pin::new(&mut self.stream).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = res {
+            let read = buf.filled().len() - before;
+            if read == 0 && buf.remaining() > 0 && self.unplugged.load(Ordering::SeqCst) {
+                return Poll::Ready(Err(std::io::Error::other("virtual port unplugged")));
+            }
+            self.unread.fetch_sub(read, Ordering::SeqCst);
+        }
+        res
+    }
+}
+
+impl AsyncWrite for VirtualPort {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        if self.writes_fail.load(Ordering::SeqCst) {
+            return Poll::Ready(Err(std::io::ErrorKind::BrokenPipe.into()));
+        }
+        let res = Pin::new(&mut self.stream).poll_write(cx, buf);
+        if let Poll::Ready(Ok(n)) = res {
+            self.peer_unread.fetch_add(n, Ordering::SeqCst);
+        }
+        res
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.stream).poll_shutdown(cx)
+    }
+}
+
+/// Like a non-blocking OS port, reports `WouldBlock` instead of waiting.
+fn poll_now<T>(
+    poll: impl FnOnce(&mut Context<'_>) -> Poll<std::io::Result<T>>,
+) -> std::io::Result<T> {
+    match poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(res) => res,
+        Poll::Pending => Err(std::io::ErrorKind::WouldBlock.into()),
+    }
+}
+
+impl std::io::Read for VirtualPort {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let mut buf = ReadBuf::new(buf);
+        poll_now(|cx| Pin::new(&mut *self).poll_read(cx, &mut buf))?;
+        Ok(buf.filled().len())
+    }
+}
+
+impl std::io::Write for VirtualPort {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        poll_now(|cx| Pin::new(&mut *self).poll_write(cx, buf))
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        poll_now(|cx| Pin::new(&mut *self).poll_flush(cx))
+    }
+}
+
+impl SerialPort for VirtualPort {
+    fn name(&self) -> Option<String> {
+        Some(self.name.clone())
+    }
+
+    fn baud_rate(&self) -> tokio_serial::Result<u32> {
+        Ok(self.baud_rate)
+    }
+
+    fn data_bits(&self) -> tokio_serial::Result<DataBits> {
+        Ok(self.data_bits)
+    }
+
+    fn flow_control(&self) -> tokio_serial::Result<FlowControl> {
+        Ok(self.flow_control)
+    }
+
+    fn parity(&self) -> tokio_serial::Result<Parity> {
+        Ok(self.parity)
+    }
+
+    fn stop_bits(&self) -> tokio_serial::Result<StopBits> {
+        Ok(self.stop_bits)
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> tokio_serial::Result<()> {
+        self.baud_rate = baud_rate;
+        Ok(())
+    }
+
+    fn set_data_bits(&mut self, data_bits: DataBits) -> tokio_serial::Result<()> {
+        self.data_bits = data_bits;
+        Ok(())
+    }
+
+    fn set_flow_control(&mut self, flow_control: FlowControl) -> tokio_serial::Result<()> {
+        self.flow_control = flow_control;
+        Ok(())
+    }
+
+    fn set_parity(&mut self, parity: Parity) -> tokio_serial::Result<()> {
+        self.parity = parity;
+        Ok(())
+    }
+
+    fn set_stop_bits(&mut self, stop_bits: StopBits) -> tokio_serial::Result<()> {
+        self.stop_bits = stop_bits;
+        Ok(())
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> tokio_serial::Result<()> {
+        self.timeout = timeout;
+        Ok(())
+    }
+
+    fn write_request_to_send(&mut self, level: bool) -> tokio_serial::Result<()> {
+        self.local.rts.store(level, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn write_data_terminal_ready(&mut self, level: bool) -> tokio_serial::Result<()> {
+        self.local.dtr.store(level, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn read_clear_to_send(&mut self) -> tokio_serial::Result<bool> {
+        Ok(self.remote.rts.load(Ordering::SeqCst))
+    }
+
+    fn read_data_set_ready(&mut self) -> tokio_serial::Result<bool> {
+        Ok(self.remote.dtr.load(Ordering::SeqCst))
+    }
+
+    fn read_ring_indicator(&mut self) -> tokio_serial::Result<bool> {
+        Ok(false)
+    }
+
+    fn read_carrier_detect(&mut self) -> tokio_serial::Result<bool> {
+        Ok(self.remote.dtr.load(Ordering::SeqCst))
+    }
+
+    fn bytes_to_read(&self) -> tokio_serial::Result<u32> {
+        Ok(self.unread.load(Ordering::SeqCst) as u32)
+    }
+
+    fn bytes_to_write(&self) -> tokio_serial::Result<u32> {
+        Ok(0)
+    }
+
+    fn clear(&self, _buffer_to_clear: ClearBuffer) -> tokio_serial::Result<()> {
+        Ok(())
+    }
+
+    fn try_clone(&self) -> tokio_serial::Result<Box<dyn SerialPort>> {
+        Err(tokio_serial::Error::new(
+            tokio_serial::ErrorKind::Io(std::io::ErrorKind::Other),
+            "virtual ports cannot be cloned",
+        ))
+    }
+
+    fn set_break(&self) -> tokio_serial::Result<()> {
+        Ok(())
+    }
+
+    fn clear_break(&self) -> tokio_serial::Result<()> {
+        Ok(())
+    }
+}
@@ -1,56 +1,287 @@
 //! Write operations for serial ports.
 
-use crate::serial_mgr::helpers::{get_port_sender, send_command_with_ack};
+use std::num::NonZeroUsize;
+use std::time::{Duration, Instant};
+
+use crate::constants::serial::{self, LineEnding};
+use crate::serial::write_transform::WriteTransform;
+use crate::serial_mgr::helpers::{get_port_sender, send_command_with_ack, unescape_text};
 use crate::serial_mgr::port_task::{
     WriteCmd, WritePortDataTerminalReady, WritePortMessage, WritePortRequestToSend,
 };
-use crate::state::AppState;
+use crate::state::{AppState, PortStatus};
+use crate::util::WriteAck;
+
+/// Message IDs recently written by `write_port`, with the time they were sent.
+#[derive(Debug)]
+pub struct SentMessageIds(lru::LruCache<String, Instant>);
+
+impl Default for SentMessageIds {
+    fn default() -> Self {
+        let capacity = NonZeroUsize::new(serial::SENT_MESSAGE_ID_CAPACITY)
+            .expect("message ID capacity is not zero");
+        Self(lru::LruCache::new(capacity))
+    }
+}
+
+impl SentMessageIds {
+    /// Records `message_id` as sent at `now`, returning `false` if it was
+    /// already sent within the TTL.
+    pub fn insert(&mut self, message_id: &str, now: Instant) -> bool {
+        let ttl = Duration::from_millis(serial::SENT_MESSAGE_ID_TTL_MS);
+        if let Some(sent) = self.0.get(message_id) {
+            if now.saturating_duration_since(*sent) < ttl {
+                return false;
+            }
+        }
+        self.0.put(message_id.to_string(), now);
+        true
+    }
+
+    pub fn remove(&mut self, message_id: &str) {
+        self.0.pop(message_id);
+    }
+
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+}
+
+/// Outcome of [`write_port`].
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct WritePortResult {
+    pub bytes_written: usize,
+    pub latency_ms: f64,
+}
+
+impl From<WriteAck> for WritePortResult {
+    fn from(ack: WriteAck) -> Self {
+        Self {
+            bytes_written: ack.bytes_written,
+            latency_ms: ack.elapsed_ns as f64 / 1_000_000.0,
+        }
+    }
+}
 
 /// Write data to a serial port.
+///
+/// A `message_id` already written within the last minute is acknowledged
+/// without writing again, so a frontend retrying after a reconnect does not
+/// send the same command twice. Such a skipped write reports 0 bytes written.
+///
+/// Writes with a `sequence_number` reach the port in sequence order, even
+/// when sent concurrently, see [`WritePortMessage::sequence_number`].
 #[tauri::command(rename_all = "camelCase")]
+#[tracing::instrument(name = "write_port", level = "debug", skip_all, fields(%port_name, %message_id))]
 pub async fn write_port(
     state: tauri::State<'_, AppState>,
     port_name: String,
     data: Vec<u8>,
     message_id: String,
+    sequence_number: Option<u64>,
+) -> Result<WritePortResult, String> {
+    if !state
+        .sent_message_ids
+        .lock()
+        .insert(&message_id, Instant::now())
+    {
+        tracing::info!("skip duplicate write of message {}", message_id);
+        return Ok(WriteAck::default().into());
+    }
+
+    let result = async {
+        let sender = get_port_sender(&state, &port_name).await?;
+        let cmd = WriteCmd::Message(WritePortMessage {
+            data,
+            message_id: message_id.clone(),
+            sequence_number,
+        });
+        send_command_with_ack(&sender, cmd, "write port data", &port_name).await
+    }
+    .await;
+    // The write did not complete, possibly failing at the port, so a retry must go through
+    if result.is_err() {
+        state.sent_message_ids.lock().remove(&message_id);
+    }
+    result.map(WritePortResult::from)
+}
+
+/// Forget all message IDs written by `write_port`, so they can be sent again.
+#[tauri::command(rename_all = "camelCase")]
+pub async fn clear_message_id_cache(state: tauri::State<'_, AppState>) -> Result<(), String> {
+    state.sent_message_ids.lock().clear();
+    Ok(())
+}
+
+/// Write text to a serial port, optionally expanding escape sequences.
+///
+/// See [`unescape_text`] for the supported escapes. `line_ending` is appended
+/// after expansion, and the result goes through the same path as `write_port`.
+#[tauri::command(rename_all = "camelCase")]
+#[tracing::instrument(
+    name = "write_port_text",
+    level = "debug",
+    skip_all,
+    fields(%port_name, message_id = tracing::field::Empty)
+)]
+pub async fn write_port_text(
+    state: tauri::State<'_, AppState>,
+    port_name: String,
+    text: String,
+    line_ending: LineEnding,
+    escape_sequences: bool,
 ) -> Result<(), String> {
-    let span = tracing::debug_span!("write_port", %port_name, %message_id);
-    let _guard = span.enter();
+    let message_id = uuid::Uuid::new_v4().to_string();
+    tracing::Span::current().record("message_id", message_id.as_str());
+
+    let mut data = if escape_sequences {
+        unescape_text(&text)
+    } else {
+        text.into_bytes()
+    };
+    data.extend_from_slice(line_ending.as_bytes());
+
+    let sender = get_port_sender(&state, &port_name).await?;
+    let cmd = WriteCmd::Message(WritePortMessage {
+        data,
+        message_id,
+        sequence_number: None,
+    });
+
+    send_command_with_ack(&sender, cmd, "write port text", &port_name)
+        .await
+        .map(|_| ())
+}
+
+/// Outcome of [`write_port_chunked`].
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ChunkedWriteResult {
+    pub total_chunks: usize,
+    pub total_bytes: usize,
+    pub elapsed_ms: u64,
+}
+
+/// Write a large payload to a serial port in chunks of `chunk_size` bytes.
+///
+/// Each chunk is acknowledged by the port task before the next is queued, so
+/// only one chunk sits in the write channel at a time. Chunks get the message
+/// IDs `{message_id_prefix}-0`, `{message_id_prefix}-1`, ...
+#[tauri::command(rename_all = "camelCase")]
+#[tracing::instrument(
+    name = "write_port_chunked",
+    level = "debug",
+    skip(state, data),
+    fields(%port_name, len = data.len())
+)]
+pub async fn write_port_chunked(
+    state: tauri::State<'_, AppState>,
+    port_name: String,
+    data: Vec<u8>,
+    chunk_size: usize,
+    inter_chunk_delay_ms: u64,
+    message_id_prefix: String,
+) -> Result<ChunkedWriteResult, String> {
+    if chunk_size == 0 {
+        tracing::error!("chunked write with zero chunk size");
+        return Err("chunk size must be greater than 0".to_string());
+    }
 
     let sender = get_port_sender(&state, &port_name).await?;
-    let cmd = WriteCmd::Message(WritePortMessage { data, message_id });
+    let started = std::time::Instant::now();
+    let total_chunks = data.len().div_ceil(chunk_size);
+    for (index, chunk) in data.chunks(chunk_size).enumerate() {
+        if index > 0 && inter_chunk_delay_ms > 0 {
+            tokio::time::sleep(std::time::Duration::from_millis(inter_chunk_delay_ms)).await;
+        }
+        let cmd = WriteCmd::Message(WritePortMessage {
+            data: chunk.to_vec(),
+            message_id: format!("{}-{}", message_id_prefix, index),
+            sequence_number: None,
+        });
+        send_command_with_ack(&sender, cmd, "write port chunk", &port_name)
+            .await
+            .map_err(|e| format!("chunk {}/{}: {}", index + 1, total_chunks, e))?;
+    }
 
-    send_command_with_ack(&sender, cmd, "write port data", &port_name).await
+    Ok(ChunkedWriteResult {
+        total_chunks,
+        total_bytes: data.len(),
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    })
 }
 
 /// Set the Request to Send (RTS) signal.
 #[tauri::command(rename_all = "camelCase")]
+#[tracing::instrument(name = "write_rts", level = "debug", skip(state), fields(%port_name))]
 pub async fn write_request_to_send(
     state: tauri::State<'_, AppState>,
     port_name: String,
     rts: bool,
 ) -> Result<(), String> {
-    let span = tracing::debug_span!("write_rts", %port_name, rts);
-    let _guard = span.enter();
-
     let sender = get_port_sender(&state, &port_name).await?;
     let cmd = WriteCmd::Rts(WritePortRequestToSend { rts });
 
-    send_command_with_ack(&sender, cmd, "write RTS", &port_name).await
+    send_command_with_ack(&sender, cmd, "write RTS", &port_name)
+        .await
+        .map(|_| ())
 }
 
 /// Set the Data Terminal Ready (DTR) signal.
 #[tauri::command(rename_all = "camelCase")]
+#[tracing::instrument(name = "write_dtr", level = "debug", skip(state), fields(%port_name))]
 pub async fn write_data_terminal_ready(
     state: tauri::State<'_, AppState>,
     port_name: String,
     dtr: bool,
 ) -> Result<(), String> {
-    let span = tracing::debug_span!("write_dtr", %port_name, dtr);
-    let _guard = span.enter();
-
     let sender = get_port_sender(&state, &port_name).await?;
     let cmd = WriteCmd::Dtr(WritePortDataTerminalReady { dtr });
 
-    send_command_with_ack(&sender, cmd, "write DTR", &port_name).await
+    send_command_with_ack(&sender, cmd, "write DTR", &port_name)
+        .await
+        .map(|_| ())
+}
+
+/// Set the prefix, checksum and suffix added to every message written to a port.
+///
+/// Applies to messages sent after this returns, until the port is closed.
+#[tauri::command(rename_all = "camelCase")]
+#[tracing::instrument(name = "set_write_transform", level = "debug", skip(state), fields(%port_name))]
+pub async fn set_write_transform(
+    state: tauri::State<'_, AppState>,
+    port_name: String,
+    transform: WriteTransform,
+) -> Result<(), String> {
+    let sender = get_port_sender(&state, &port_name).await?;
+    let cmd = WriteCmd::SetTransform(transform.clone());
+    send_command_with_ack(&sender, cmd, "set write transform", &port_name).await?;
+
+    if let Some(mut entry) = state.ports.get_mut(&port_name) {
+        if let PortStatus::Opened(profile) = &mut entry.port_status {
+            profile.write_transform = transform;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_message_id_is_skipped_within_ttl() {
+        let mut sent = SentMessageIds::default();
+        let now = Instant::now();
+        let ttl = Duration::from_millis(serial::SENT_MESSAGE_ID_TTL_MS);
+
+        assert!(sent.insert("a", now));
+        assert!(!sent.insert("a", now + ttl / 2));
+        assert!(sent.insert("b", now));
+        assert!(sent.insert("a", now + ttl));
+
+        sent.remove("b");
+        assert!(sent.insert("b", now));
+        sent.clear();
+        assert!(sent.insert("a", now + ttl));
+    }
 }
@@ -1,14 +1,22 @@
 use crate::{
+    config::{AppConfig, LogLevelHandle},
+    constants::{app_config, serial},
     serial::{
         data_bits::DataBits, flow_control::FlowControl, parity::Parity, port_type::PortType,
-        stop_bits::StopBits,
+        read_framing::ReadFraming, read_strategy::ReadStrategy, read_transform::ReadTransform,
+        reconnect_policy::ReconnectPolicy, stop_bits::StopBits, write_mode::WriteMode,
+        write_transform::WriteTransform,
     },
-    serial_mgr::port_task::WritePortSender,
+    serial_mgr::port_task::{ModemStatus, WritePortSender},
+    serial_mgr::port_task_metrics::PortTaskMetricsHandle,
+    serial_mgr::session::{generate_session_id, PortSession},
     serial_mgr::storage::Storage,
+    serial_mgr::write_port::SentMessageIds,
 };
 use dashmap::DashMap;
+use std::sync::Arc;
 
-#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct OpenedPortProfile {
     pub baud_rate: u32,
     pub flow_control: FlowControl,
@@ -20,12 +28,70 @@ pub struct OpenedPortProfile {
     pub data_set_ready: bool,
     pub ring_indicator: bool,
     pub timeout_ms: u64,
+    pub write_mode: WriteMode,
+    pub read_strategy: ReadStrategy,
+    pub read_framing: ReadFraming,
+    /// Applied to read data before it is emitted and logged.
+    pub read_transform: ReadTransform,
+    /// Added around every written message, changed by `set_write_transform`.
+    pub write_transform: WriteTransform,
+    /// Bytes read from the port at once, the most a single read event carries.
+    pub read_buffer_size: usize,
+    /// Whether the port is reopened after its reads fail.
+    pub reconnect_policy: ReconnectPolicy,
+    /// Deassert DTR before closing, so the device sees the host disconnect.
+    pub dtr_on_close: bool,
+    /// Assert RTS only while writing, for half-duplex RS-485 transceivers.
+    pub rs485_mode: bool,
+    /// Session new log entries are recorded under, changed by `rotate_session`.
+    pub session_id: String,
+}
+
+/// 115200 8N1 without flow control, what most serial terminals start with.
+///
+/// Each default profile gets a new session ID.
+impl Default for OpenedPortProfile {
+    fn default() -> Self {
+        Self {
+            baud_rate: 115_200,
+            flow_control: FlowControl::None,
+            data_bits: DataBits::Eight,
+            parity: Parity::None,
+            stop_bits: StopBits::One,
+            carrier_detect: false,
+            clear_to_send: false,
+            data_set_ready: false,
+            ring_indicator: false,
+            timeout_ms: app_config::DEFAULT_TIMEOUT_MS,
+            write_mode: WriteMode::default(),
+            read_strategy: ReadStrategy::default(),
+            read_framing: ReadFraming::default(),
+            read_transform: ReadTransform::default(),
+            write_transform: WriteTransform::default(),
+            read_buffer_size: serial::READ_BUFFER_SIZE,
+            reconnect_policy: ReconnectPolicy::default(),
+            dtr_on_close: false,
+            rs485_mode: false,
+            session_id: generate_session_id(),
+        }
+    }
 }
 
-#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum PortStatus {
     Opened(OpenedPortProfile),
     Closed,
+    /// Closed and missing from the latest scan, but seen recently.
+    Disconnected,
+    /// `open_port` accepted the request, the port task is not running yet.
+    Opening,
+    /// `close_port` sent the close, the port task has not exited yet.
+    Closing,
+    /// The connection was lost and the port is reopened at `next_attempt_at_ms`.
+    Reconnecting {
+        attempt: u32,
+        next_attempt_at_ms: u128,
+    },
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -35,16 +101,101 @@ pub struct PortInfo {
     pub port_status: PortStatus,
     pub bytes_read: u128,
     pub bytes_write: u128,
+    /// Last scan that found the port, in milliseconds since Unix epoch.
+    /// `None` for ports never reported by the OS, such as virtual ports.
+    pub last_seen_ms: Option<u128>,
+}
+
+/// An open port as returned by `get_open_ports`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct OpenPortInfo {
+    pub port_name: String,
+    pub session_id: String,
+    /// Time the port was opened, in milliseconds since Unix epoch.
+    pub opened_at_ms: u128,
+    pub bytes_read: u128,
+    pub bytes_write: u128,
+    pub profile: OpenedPortProfile,
 }
 
-#[derive(Debug)]
+/// Handles to a running port task.
+///
+/// Deliberately not `Clone`: the write channel is owned by the entry in
+/// `AppState::port_handles`, and removing that entry is what closes the port.
 pub struct PortHandles {
     pub write_port_tx: WritePortSender,
+    /// Counters updated by the port task, read by `get_port_task_metrics`.
+    pub metrics: PortTaskMetricsHandle,
+    /// Last modem line state polled by the port task, read by `get_modem_status`.
+    pub modem_status_rx: tokio::sync::watch::Receiver<ModemStatus>,
+    /// Current log session, shared with the port task.
+    pub session: Arc<PortSession>,
+    /// Time the port was opened, in milliseconds since Unix epoch.
+    pub opened_at_ms: u128,
+    /// Cancels the port task, used on shutdown.
+    pub shutdown: tokio_util::sync::CancellationToken,
+    /// Finishes once the port is released and its session ended.
+    /// Taken by whoever waits for it.
+    pub task: Option<tokio::task::JoinHandle<()>>,
+}
+
+impl std::fmt::Debug for PortHandles {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The channel internals are noise, its free capacity is what matters
+        struct WritePortSenderDebug(usize);
+        impl std::fmt::Debug for WritePortSenderDebug {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "WritePortSender {{ capacity: {} }}", self.0)
+            }
+        }
+
+        f.debug_struct("PortHandles")
+            .field(
+                "write_port_tx",
+                &WritePortSenderDebug(self.write_port_tx.capacity()),
+            )
+            .field("metrics", &self.metrics)
+            .field("modem_status_rx", &*self.modem_status_rx.borrow())
+            .field("session", &self.session)
+            .field("opened_at_ms", &self.opened_at_ms)
+            .field("shutdown", &self.shutdown)
+            .field("task", &self.task)
+            .finish()
+    }
 }
 
-#[derive(Default)]
+/// Global application state managed by Tauri.
+///
+/// Cloning is cheap and shares state: every field is behind an `Arc` (or is
+/// `Storage`, which wraps its connection in one), so a clone moved into an
+/// async task sees the same ports, handles and database as the managed instance.
+#[derive(Debug, Default, Clone)]
 pub struct AppState {
-    pub ports: DashMap<String, PortInfo>,
-    pub port_handles: DashMap<String, PortHandles>,
+    /// All known ports, keyed by port name.
+    pub ports: Arc<DashMap<String, PortInfo>>,
+    /// Channels to the tasks of currently open ports, keyed by port name.
+    pub port_handles: Arc<DashMap<String, PortHandles>>,
+    /// Persistent log storage, shared by all port tasks.
     pub storage: Storage,
+    /// Tasks forwarding new log entries to the frontend, keyed by session ID.
+    pub log_tails: Arc<DashMap<String, tauri::async_runtime::JoinHandle<()>>>,
+    /// Background task keeping `ports` in sync with the system, aborted on exit.
+    pub port_scan_task: Arc<std::sync::Mutex<Option<tauri::async_runtime::JoinHandle<()>>>>,
+    /// Application settings, reloaded when the config file changes.
+    pub config: Arc<parking_lot::RwLock<AppConfig>>,
+    /// Location of the config file.
+    pub config_path: Arc<std::path::PathBuf>,
+    /// Background task watching the config file, aborted on exit.
+    pub config_watch_task: Arc<std::sync::Mutex<Option<tauri::async_runtime::JoinHandle<()>>>>,
+    /// Changes the level of the global subscriber, `None` until logging is set up.
+    pub log_level: Option<LogLevelHandle>,
+    /// Message IDs recently written, used by `write_port` to skip duplicates.
+    pub sent_message_ids: Arc<parking_lot::Mutex<SentMessageIds>>,
+}
+
+/// Returns the whole application state pretty-printed, for developer diagnostics.
+#[cfg(debug_assertions)]
+#[tauri::command(rename_all = "camelCase")]
+pub async fn get_debug_dump(state: tauri::State<'_, AppState>) -> Result<String, String> {
+    Ok(format!("{:#?}", *state))
 }
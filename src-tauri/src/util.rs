@@ -1 +1,114 @@
-pub type AckSender<T> = tokio::sync::mpsc::Sender<(T, Option<tokio::sync::oneshot::Sender<()>>)>;
+//! Channel types shared between Tauri commands and port tasks, and lock helpers.
+
+use std::sync::LockResult;
+
+/// What a port task reports when acknowledging a command.
+///
+/// Only writes fill it in, other commands acknowledge with the default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WriteAck {
+    pub bytes_written: usize,
+    /// Time spent writing to the port.
+    pub elapsed_ns: u64,
+}
+
+/// One-shot sender used by a port task to acknowledge a processed command,
+/// with the error if the command failed.
+pub type AckTx = tokio::sync::oneshot::Sender<Result<WriteAck, String>>;
+
+/// Sending half of a command channel with optional acknowledgment.
+///
+/// Each message carries the command together with an optional [`AckTx`].
+/// When present, the receiving task signals it once the command has been
+/// processed, so callers can wait for completion.
+pub type AckSender<T> = tokio::sync::mpsc::Sender<(T, Option<AckTx>)>;
+
+/// Receiving half matching [`AckSender`].
+pub type AckReceiver<T> = tokio::sync::mpsc::Receiver<(T, Option<AckTx>)>;
+
+/// Returns the guard of a std lock, recovering it if a previous holder panicked.
+///
+/// The data behind the app's locks is replaced wholesale, never left half
+/// updated, so it stays usable after a panic. Failing instead would make the
+/// guarded feature unavailable until the app restarts.
+pub fn unwrap_lock<G>(result: LockResult<G>, context: &str) -> G {
+    result.unwrap_or_else(|poisoned| {
+        tracing::warn!("recovered poisoned lock: {}", context);
+        poisoned.into_inner()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ack_channel<T>() -> (AckSender<T>, AckReceiver<T>) {
+        tokio::sync::mpsc::channel(4)
+    }
+
+    #[tokio::test]
+    async fn send_without_ack() {
+        let (tx, mut rx) = ack_channel::<u32>();
+        tx.send((1, None)).await.unwrap();
+
+        let (value, ack) = rx.recv().await.unwrap();
+        assert_eq!(value, 1);
+        assert!(ack.is_none());
+    }
+
+    #[tokio::test]
+    async fn send_with_ack_is_acknowledged() {
+        let (tx, mut rx) = ack_channel::<u32>();
+        let (ack_tx, ack_rx) = tokio::sync::oneshot::channel();
+        tx.send((2, Some(ack_tx))).await.unwrap();
+
+        let (value, ack) = rx.recv().await.unwrap();
+        assert_eq!(value, 2);
+        ack.expect("ack sender present")
+            .send(Ok(WriteAck::default()))
+            .unwrap();
+
+        assert!(ack_rx.await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn ack_fails_when_waiter_dropped() {
+        let (tx, mut rx) = ack_channel::<u32>();
+        let (ack_tx, ack_rx) = tokio::sync::oneshot::channel();
+        tx.send((3, Some(ack_tx))).await.unwrap();
+        drop(ack_rx);
+
+        let (_, ack) = rx.recv().await.unwrap();
+        assert!(ack
+            .expect("ack sender present")
+            .send(Ok(WriteAck::default()))
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn ack_wait_fails_when_task_drops_ack() {
+        let (tx, mut rx) = ack_channel::<u32>();
+        let (ack_tx, ack_rx) = tokio::sync::oneshot::channel();
+        tx.send((4, Some(ack_tx))).await.unwrap();
+
+        let (_, ack) = rx.recv().await.unwrap();
+        drop(ack);
+
+        assert!(ack_rx.await.is_err());
+    }
+
+    #[test]
+    fn unwrap_lock_recovers_poisoned_lock() {
+        let lock = std::sync::Arc::new(std::sync::RwLock::new(1));
+        let lock_for_thread = lock.clone();
+        let _ = std::thread::spawn(move || {
+            let _guard = lock_for_thread.write().unwrap();
+            panic!("poison the lock");
+        })
+        .join();
+        assert!(lock.is_poisoned());
+
+        *unwrap_lock(lock.write(), "test") += 1;
+        assert_eq!(*unwrap_lock(lock.read(), "test"), 2);
+    }
+}
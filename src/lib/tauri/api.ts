@@ -4,8 +4,8 @@
  */
 
 import { invokeCommand } from "./invoke";
-import type { SerialPortInfo } from "./tauriTypes";
-import { OpenPortCommand } from "./tauriCommands";
+import type { SerialPortInfo, WritePortResult } from "./tauriTypes";
+import { GetSessionSummaryCommand, OpenPortCommand } from "./tauriCommands";
 
 /**
  * High-level API for Tauri serial port operations
@@ -61,17 +61,21 @@ export class TauriSerialAPI {
    * @param portName - Target port name
    * @param data - Data to send (Uint8Array or number array)
    * @param messageId - Optional message ID for tracking (auto-generated if not provided)
+   * @param sequenceNumber - Optional position, counted from 0 per opened port, that
+   *   keeps concurrent writes in order
    */
   static async writePort(
     portName: string,
     data: Uint8Array | number[],
     messageId?: string,
-  ): Promise<void> {
+    sequenceNumber?: number,
+  ): Promise<WritePortResult> {
     const dataArray = data instanceof Uint8Array ? Array.from(data) : data;
     return await invokeCommand("write_port", {
       portName,
       data: dataArray,
       messageId: messageId ?? `msg_${Date.now()}`,
+      sequenceNumber,
     });
   }
 
@@ -178,9 +182,70 @@ export class TauriSerialAPI {
     return rawLogs.map((log) => ({
       id: log.id.toString(),
       timestamp: log.timestamp,
-      direction: log.direction === "in" ? "RX" : "TX",
+      direction: log.direction === "RX" ? "RX" : "TX",
+      data: new Uint8Array(log.data),
+      format: "HEX" as const,
+    }));
+  }
+
+  /**
+   * Retrieve the logs of a session between two timestamps, oldest first
+   * @param sessionId - The session ID to fetch logs for
+   * @param startMs - Start of the range in Unix epoch milliseconds, inclusive
+   * @param endMs - End of the range in Unix epoch milliseconds, inclusive
+   * @param limit - Max number of logs to retrieve
+   * @param offset - Offset for pagination
+   */
+  static async getLogsByTimeRange(
+    sessionId: string,
+    startMs: number,
+    endMs: number,
+    limit: number = 100,
+    offset: number = 0,
+  ): Promise<import("../../types").LogEntry[]> {
+    const rawLogs = await invokeCommand("get_logs_by_time_range", {
+      sessionId,
+      startMs,
+      endMs,
+      limit,
+      offset,
+    });
+
+    return rawLogs.map((log) => ({
+      id: log.id.toString(),
+      timestamp: log.timestamp,
+      direction: log.direction === "RX" ? "RX" : "TX",
       data: new Uint8Array(log.data),
       format: "HEX" as const,
     }));
   }
+
+  /**
+   * Get the message and byte counts of a session
+   * @param sessionId - The session ID to summarize
+   * @returns Counts of all, RX and TX messages and the total logged bytes
+   */
+  static async getSessionSummary(
+    sessionId: string,
+  ): Promise<GetSessionSummaryCommand["returns"]> {
+    return await invokeCommand("get_session_summary", { sessionId });
+  }
+
+  /**
+   * Delete a session and its logs, fails while a port is logging to it
+   * @param sessionId - The session ID to delete
+   * @returns Number of deleted log entries
+   */
+  static async deleteSessionLogs(sessionId: string): Promise<number> {
+    return await invokeCommand("delete_session_logs", { sessionId });
+  }
+
+  /**
+   * Delete the logs of all sessions older than a cutoff
+   * @param cutoffMs - Cutoff in Unix epoch milliseconds, exclusive
+   * @returns Number of deleted log entries
+   */
+  static async pruneLogsBefore(cutoffMs: number): Promise<number> {
+    return await invokeCommand("prune_logs_before", { cutoffMs });
+  }
 }
@@ -24,6 +24,7 @@ export const TauriEventNames = {
 
 export const PortOpenedEventSchema = z.object({
   portName: z.string(),
+  sessionId: z.string(),
   timestampMs: z.number(),
 });
 
@@ -39,9 +40,24 @@ export const PortReadEventSchema = z.object({
   data: z.array(z.number()),
 });
 
+export const PortErrorCodeSchema = z.enum([
+  "Io",
+  "Break",
+  "PermissionDenied",
+  "NotFound",
+  "Busy",
+  "OpenFailed",
+  "ReadTransform",
+]);
+
 export const PortErrorEventSchema = z.object({
   portName: z.string().optional(),
   error: z.string(),
+  code: PortErrorCodeSchema.optional(),
+  // Break errors are recoverable, the port stays open
+  recoverable: z.boolean().optional(),
+  durationHintMs: z.number().nullable().optional(),
+  timestampMs: z.number().optional(),
 });
 
 export const PortStatusEventSchema = z.object({
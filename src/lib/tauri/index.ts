@@ -22,7 +22,11 @@ export {
 } from "./events";
 
 // Unified serial port type exports
-export type { SerialPortInfo, SerialPortInfoArray } from "./tauriTypes";
+export type {
+  SerialPortInfo,
+  SerialPortInfoArray,
+  WritePortResult,
+} from "./tauriTypes";
 
 // Command type exports
 export type * from "./tauriCommands";
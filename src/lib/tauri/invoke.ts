@@ -23,6 +23,7 @@ const LogEntryArraySchema = z.array(LogEntrySchema);
 const RESPONSE_SCHEMAS: Partial<Record<TauriCommand["name"], z.ZodSchema>> = {
   get_all_port_info: SerialPortInfoArraySchema,
   get_logs: LogEntryArraySchema,
+  get_logs_by_time_range: LogEntryArraySchema,
   // Commands that return void don't need schemas
   // (open_port, close_port, write_port, etc. all return void)
 };
@@ -3,8 +3,14 @@
  * Uses discriminated union pattern for type-safe command invocation
  */
 
-import { DataBits, FlowControl, Parity, StopBits } from "@/types";
-import type { SerialPortInfo } from "./tauriTypes";
+import {
+  DataBits,
+  FlowControl,
+  Parity,
+  ReconnectPolicy,
+  StopBits,
+} from "@/types";
+import type { SerialPortInfo, WritePortResult } from "./tauriTypes";
 
 // ============================================================================
 // Individual Command Definitions
@@ -27,6 +33,10 @@ export interface OpenPortCommand {
     stopBits: StopBits;
     dataTerminalReady: boolean;
     timeoutMs: number;
+    /** Bytes read at once, the most a single read event carries (default 1024) */
+    readBufferSize?: number;
+    /** Whether the port is reopened after its reads fail (default "Never") */
+    reconnectPolicy?: ReconnectPolicy;
   };
   returns: void;
 }
@@ -45,8 +55,9 @@ export interface WritePortCommand {
     portName: string;
     data: number[]; // Vec<u8> in Rust
     messageId: string;
+    sequenceNumber?: number; // Option<u64> in Rust, writes reach the port in this order
   };
-  returns: void;
+  returns: WritePortResult;
 }
 
 export interface WriteRequestToSendCommand {
@@ -134,6 +145,48 @@ export interface GetLogsCommand {
   }[];
 }
 
+export interface GetLogsByTimeRangeCommand {
+  name: "get_logs_by_time_range";
+  args: {
+    sessionId: string;
+    startMs: number;
+    endMs: number;
+    limit: number;
+    offset: number;
+  };
+  returns: GetLogsCommand["returns"];
+}
+
+export interface GetSessionSummaryCommand {
+  name: "get_session_summary";
+  args: {
+    sessionId: string;
+  };
+  returns: {
+    session_id: string;
+    message_count: number;
+    rx_count: number;
+    tx_count: number;
+    total_bytes: number;
+  };
+}
+
+export interface DeleteSessionLogsCommand {
+  name: "delete_session_logs";
+  args: {
+    sessionId: string;
+  };
+  returns: number;
+}
+
+export interface PruneLogsBeforeCommand {
+  name: "prune_logs_before";
+  args: {
+    cutoffMs: number;
+  };
+  returns: number;
+}
+
 // ============================================================================
 // Discriminated Union of All Commands
 // ============================================================================
@@ -150,7 +203,11 @@ export type TauriCommand =
   | WarnCommand
   | ErrorCommand
   | DebugCommand
-  | GetLogsCommand;
+  | GetLogsCommand
+  | GetLogsByTimeRangeCommand
+  | GetSessionSummaryCommand
+  | DeleteSessionLogsCommand
+  | PruneLogsBeforeCommand;
 
 // ============================================================================
 // Helper Types for Type Extraction
@@ -27,6 +27,7 @@ export const UsbPortInfoSchema = z.object({
   manufacturer: z.string().nullable(),
   product: z.string().nullable(),
   interface: z.number().nullable(),
+  hub_port: z.number().nullable(),
 });
 
 /**
@@ -39,6 +40,20 @@ export const PortTypeSchema = z.union([
   z.literal("Unknown"), // Unknown connection type
 ]);
 
+/**
+ * Reconnect policy schema - tagged union for Rust enum serialization
+ */
+export const ReconnectPolicySchema = z.union([
+  z.literal("Never"), // Close the port on the first failed read
+  z.object({
+    Attempts: z.object({
+      max_attempts: z.number(),
+      interval_ms: z.number(),
+    }),
+  }),
+  z.object({ Infinite: z.object({ interval_ms: z.number() }) }),
+]);
+
 /**
  * Opened port profile schema
  */
@@ -53,6 +68,11 @@ export const OpenedPortProfileSchema = z.object({
   data_set_ready: z.boolean(),
   ring_indicator: z.boolean(),
   timeout_ms: z.number(),
+  read_buffer_size: z.number(),
+  reconnect_policy: ReconnectPolicySchema,
+  dtr_on_close: z.boolean(),
+  rs485_mode: z.boolean(),
+  session_id: z.string(),
 });
 
 /**
@@ -61,6 +81,15 @@ export const OpenedPortProfileSchema = z.object({
 export const PortStatusSchema = z.union([
   z.object({ Opened: OpenedPortProfileSchema }), // Port is open
   z.literal("Closed"), // Port is closed
+  z.literal("Disconnected"), // Port is closed and was unplugged recently
+  z.literal("Opening"), // Open requested, port task not running yet
+  z.literal("Closing"), // Close requested, port task still running
+  z.object({
+    Reconnecting: z.object({
+      attempt: z.number(),
+      next_attempt_at_ms: z.number(),
+    }),
+  }), // Connection lost, waiting for the next reopen attempt
 ]);
 
 /**
@@ -72,6 +101,7 @@ export const SerialPortInfoSchema = z.object({
   port_status: PortStatusSchema,
   bytes_read: z.number(), // u128 from Rust
   bytes_write: z.number(), // u128 from Rust
+  last_seen_ms: z.number().nullable(), // u128 from Rust
 });
 
 export const SerialPortInfoArraySchema = z.array(SerialPortInfoSchema);
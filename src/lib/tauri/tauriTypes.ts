@@ -28,3 +28,11 @@ export type SerialPortInfo = z.infer<typeof SerialPortInfoSchema>;
  * Array of serial port information
  */
 export type SerialPortInfoArray = z.infer<typeof SerialPortInfoArraySchema>;
+
+/**
+ * Outcome of a write_port call, a skipped duplicate write reports 0 bytes
+ */
+export interface WritePortResult {
+  bytes_written: number;
+  latency_ms: number;
+}
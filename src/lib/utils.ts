@@ -81,5 +81,23 @@ export function getErrorMessage(error: unknown): string {
   }
   if (error instanceof Error) return error.message;
   if (typeof error === "string") return error;
+  // open_port fails with { error, diagnostic }
+  if (
+    typeof error === "object" &&
+    error !== null &&
+    "error" in error &&
+    typeof error.error === "string"
+  ) {
+    const diagnostic = "diagnostic" in error ? error.diagnostic : null;
+    if (
+      typeof diagnostic === "object" &&
+      diagnostic !== null &&
+      "suggested_fix" in diagnostic &&
+      typeof diagnostic.suggested_fix === "string"
+    ) {
+      return `${error.error}. ${diagnostic.suggested_fix}`;
+    }
+    return error.error;
+  }
   return "An unknown error occurred";
 }
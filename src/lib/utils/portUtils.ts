@@ -145,6 +145,8 @@ export function getPortTooltip(portInfo: RustPortInfo): string {
     }
 
     if (usb.serial_number) lines.push(`Serial: ${usb.serial_number}`);
+    if (usb.interface != null) lines.push(`Interface: ${usb.interface}`);
+    if (usb.hub_port != null) lines.push(`Hub port: ${usb.hub_port}`);
   } else {
     // Non-USB port type
     if (typeof port_type === "string") {
@@ -155,8 +157,18 @@ export function getPortTooltip(portInfo: RustPortInfo): string {
   // Add status
   if (typeof port_status === "object" && "Opened" in port_status) {
     lines.push("Status: Open");
+  } else if (typeof port_status === "object" && "Reconnecting" in port_status) {
+    lines.push(
+      `Status: Reconnecting (attempt ${port_status.Reconnecting.attempt})`,
+    );
   } else if (port_status === "Closed") {
     lines.push("Status: Closed");
+  } else if (port_status === "Disconnected") {
+    lines.push("Status: Disconnected");
+  } else if (port_status === "Opening") {
+    lines.push("Status: Opening");
+  } else if (port_status === "Closing") {
+    lines.push("Status: Closing");
   }
 
   return lines.join("\n");
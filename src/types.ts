@@ -111,6 +111,7 @@ import {
 import {
   UsbPortInfoSchema,
   PortTypeSchema,
+  ReconnectPolicySchema,
   OpenedPortProfileSchema,
   PortStatusSchema,
   SerialPortInfoSchema,
@@ -280,6 +281,7 @@ export type PersistedStoreState = z.infer<typeof PersistedStoreStateSchema>;
 // Port info
 export type UsbPortInfo = z.infer<typeof UsbPortInfoSchema>;
 export type PortType = z.infer<typeof PortTypeSchema>;
+export type ReconnectPolicy = z.infer<typeof ReconnectPolicySchema>;
 export type OpenedPortProfile = z.infer<typeof OpenedPortProfileSchema>;
 export type PortStatus = z.infer<typeof PortStatusSchema>;
 export type SerialPortInfo = z.infer<typeof SerialPortInfoSchema>;
@@ -423,6 +425,7 @@ export {
   PersistedStoreStateSchema,
   UsbPortInfoSchema,
   PortTypeSchema,
+  ReconnectPolicySchema,
   OpenedPortProfileSchema,
   PortStatusSchema,
   SerialPortInfoSchema,